@@ -0,0 +1,5 @@
+module counter(input clk, output reg [1:0] q);
+  initial q = 2'b00;
+  always @(posedge clk)
+    q <= q + 1;
+endmodule
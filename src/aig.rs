@@ -0,0 +1,994 @@
+use crate::blif::{CombinationalLoop, InputValue, Latch, LatchInit, LatchType, LogicGate, Model};
+use crate::netlist::{Cell, Netlist};
+use crate::simulation::SignalState;
+
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+
+/// A literal: a variable of an `Aig`, possibly complemented, numbered as in
+/// AIGER (`2 * var + complemented`).
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+pub struct Lit(u32);
+
+impl Lit {
+    pub const FALSE: Lit = Lit(0);
+    pub const TRUE: Lit = Lit(1);
+
+    pub fn new(var: usize, complemented: bool) -> Self {
+        Self(2 * var as u32 + complemented as u32)
+    }
+
+    pub fn var(self) -> usize {
+        (self.0 / 2) as usize
+    }
+
+    pub fn is_complemented(self) -> bool {
+        self.0 & 1 == 1
+    }
+
+    pub fn is_constant(self) -> bool {
+        self.var() == 0
+    }
+
+    /// The literal, complemented when `complement` is set.
+    fn complement_if(self, complement: bool) -> Self {
+        Self(self.0 ^ complement as u32)
+    }
+}
+
+impl std::ops::Not for Lit {
+    type Output = Lit;
+
+    fn not(self) -> Lit {
+        Lit(self.0 ^ 1)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct AigLatch {
+    pub name: String,
+    /// Value taken on the next clock edge.
+    pub next: Lit,
+    /// `None` when uninitialised.
+    pub init: Option<bool>,
+}
+
+/// A structurally hashed and-inverter graph.
+///
+/// Variables are numbered as in AIGER: 0 is the constant, then come the
+/// inputs, the latches and the AND nodes, each AND node after its fanins.
+/// Inputs and latches are therefore added before any AND node.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Aig {
+    pub name: String,
+    pub inputs: Vec<String>,
+    pub latches: Vec<AigLatch>,
+    pub outputs: Vec<(String, Lit)>,
+    /// Primary input clocking the latches on its rising edge, left out of
+    /// `inputs`, or `None` for the global clock.
+    pub clock: Option<String>,
+    ands: Vec<(Lit, Lit)>,
+    strash: HashMap<(Lit, Lit), Lit>,
+}
+
+impl Aig {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            inputs: Vec::new(),
+            latches: Vec::new(),
+            outputs: Vec::new(),
+            clock: None,
+            ands: Vec::new(),
+            strash: HashMap::new(),
+        }
+    }
+
+    pub fn add_input(&mut self, name: String) -> Lit {
+        assert!(self.latches.is_empty() && self.ands.is_empty(), "inputs are added before latches and AND nodes");
+
+        self.inputs.push(name);
+        Lit::new(self.inputs.len(), false)
+    }
+
+    /// A latch whose next value is `FALSE` until set with `set_next`.
+    pub fn add_latch(&mut self, name: String, init: Option<bool>) -> Lit {
+        assert!(self.ands.is_empty(), "latches are added before AND nodes");
+
+        self.latches.push(AigLatch { name, next: Lit::FALSE, init });
+        Lit::new(self.inputs.len() + self.latches.len(), false)
+    }
+
+    pub fn set_next(&mut self, latch: usize, next: Lit) {
+        self.latches[latch].next = next;
+    }
+
+    pub fn add_output(&mut self, name: String, lit: Lit) {
+        self.outputs.push((name, lit));
+    }
+
+    /// Highest variable, the `M` of AIGER headers.
+    pub fn max_var(&self) -> usize {
+        self.inputs.len() + self.latches.len() + self.ands.len()
+    }
+
+    /// Fanins of the AND nodes, in variable order.
+    pub fn ands(&self) -> &[(Lit, Lit)] {
+        &self.ands
+    }
+
+    /// Variable of the first AND node.
+    fn first_and(&self) -> usize {
+        self.inputs.len() + self.latches.len() + 1
+    }
+
+    /// The conjunction of `a` and `b`, folding constants and trivial cases and
+    /// reusing any node with the same fanins.
+    pub fn and(&mut self, a: Lit, b: Lit) -> Lit {
+        let (a, b) = if a > b { (a, b) } else { (b, a) };
+
+        if b == Lit::FALSE || a == !b {
+            return Lit::FALSE;
+        }
+        if b == Lit::TRUE || a == b {
+            return a;
+        }
+
+        if let Some(lit) = self.strash.get(&(a, b)) {
+            return *lit;
+        }
+
+        let lit = Lit::new(self.max_var() + 1, false);
+        self.ands.push((a, b));
+        self.strash.insert((a, b), lit);
+
+        lit
+    }
+
+    pub fn or(&mut self, a: Lit, b: Lit) -> Lit {
+        !self.and(!a, !b)
+    }
+
+    /// Outputs and next latch values, given the inputs and the latch values.
+    pub fn simulate(&self, inputs: &[bool], state: &[bool]) -> (Vec<bool>, Vec<bool>) {
+        let mut values = vec![false];
+        values.extend_from_slice(inputs);
+        values.extend_from_slice(state);
+
+        for (a, b) in &self.ands {
+            let value = |lit: &Lit| values[lit.var()] ^ lit.is_complemented();
+            values.push(value(a) && value(b));
+        }
+
+        let value = |lit: Lit| values[lit.var()] ^ lit.is_complemented();
+
+        (
+            self.outputs.iter().map(|(_, lit)| value(*lit)).collect(),
+            self.latches.iter().map(|latch| value(latch.next)).collect(),
+        )
+    }
+
+    /// The AIG of `model`, with its instances flattened.
+    ///
+    /// Covers are decomposed into sums of products. The latches of the
+    /// hierarchy become AIG latches, so they must all be on the global clock,
+    /// or all on the rising edge of the same primary input, which then only
+    /// serves as the clock. Values are two-valued: models which may compute
+    /// unknown or high-impedance values are refused, and latches initialised
+    /// to a don't-care or unknown value are left uninitialised.
+    pub fn from_model(model: &Model) -> Result<Self, AigError> {
+        let netlist = Netlist::new(model).map_err(AigError::Loop)?;
+        let name = |net: usize| netlist.net_name(net).to_string();
+
+        let mut clock = None;
+        let mut global = false;
+        for latch in &netlist.latches {
+            match latch.control {
+                None => global = true,
+                Some((LatchType::RisingEdge, net)) if netlist.inputs.contains(&net) && clock.is_none_or(|clock| clock == net) => {
+                    clock = Some(net);
+                }
+                Some((_, net)) => return Err(AigError::Clock(name(net))),
+            }
+        }
+        if let (true, Some(clock)) = (global, clock) {
+            return Err(AigError::Clock(name(clock)));
+        }
+
+        Self::lower(model, &netlist, clock)
+    }
+
+    /// The combinational logic of `model`, as by `from_model` but whatever
+    /// the clocking of its latches: control nets stay ordinary nets, and the
+    /// AIG latches only stand for the outputs and next states of the latches,
+    /// not for how they are clocked.
+    pub fn combinational(model: &Model) -> Result<Self, AigError> {
+        let netlist = Netlist::new(model).map_err(AigError::Loop)?;
+        Self::lower(model, &netlist, None)
+    }
+
+    /// `netlist`, lowered from `model`, with `clock` the primary input
+    /// clocking its latches, if any.
+    fn lower(model: &Model, netlist: &Netlist, clock: Option<usize>) -> Result<Self, AigError> {
+        let name = |net: usize| netlist.net_name(net).to_string();
+
+        let mut aig = Self::new(model.name.clone());
+        aig.clock = clock.map(name);
+
+        let mut values: Vec<Option<Lit>> = vec![None; netlist.net_count()];
+
+        for net in &netlist.inputs {
+            if Some(*net) != clock {
+                values[*net] = Some(aig.add_input(name(*net)));
+            }
+        }
+
+        for latch in &netlist.latches {
+            let init = match latch.init {
+                LatchInit::Low => Some(false),
+                LatchInit::High => Some(true),
+                LatchInit::DontCare | LatchInit::Unknown => None,
+            };
+            values[latch.output] = Some(aig.add_latch(name(latch.output), init));
+        }
+
+        let get = |values: &[Option<Lit>], net: usize| match values[net] {
+            Some(lit) => Ok(lit),
+            None if Some(net) == clock => Err(AigError::Clock(name(net))),
+            None => Err(AigError::Unknown(name(net))),
+        };
+
+        for node in &netlist.nodes {
+            if node.resolve || values[node.output].is_some() {
+                return Err(AigError::MultipleDrivers(name(node.output)));
+            }
+
+            let lit = match &node.cell {
+                Cell::Cover(cover) => {
+                    let fanin = netlist.fanin(cover);
+                    let mut sum = Lit::FALSE;
+
+                    for (care, bits) in netlist.rows(cover) {
+                        let mut cube = Lit::TRUE;
+
+                        for (i, net) in fanin.iter().enumerate() {
+                            if care[i / 64] >> (i % 64) & 1 == 1 {
+                                let input = get(&values, *net)?;
+                                cube = aig.and(cube, input.complement_if(bits[i / 64] >> (i % 64) & 1 == 0));
+                            }
+                        }
+
+                        sum = aig.or(sum, cube);
+                    }
+
+                    sum.complement_if(cover.matched != SignalState::High)
+                }
+                Cell::Tbuf { .. } => return Err(AigError::TriState(name(node.output))),
+                Cell::Buffer(input) => get(&values, *input)?,
+                Cell::Const(SignalState::High) => Lit::TRUE,
+                Cell::Const(SignalState::Low) => Lit::FALSE,
+                Cell::Const(_) => return Err(AigError::Unknown(name(node.output))),
+            };
+
+            values[node.output] = Some(lit);
+        }
+
+        for (i, latch) in netlist.latches.iter().enumerate() {
+            let next = get(&values, latch.input)?;
+            aig.set_next(i, next);
+        }
+
+        for net in &netlist.outputs {
+            let lit = get(&values, *net)?;
+            aig.add_output(name(*net), lit);
+        }
+
+        Ok(aig)
+    }
+
+    /// A flat model with a two-input `.names` per AND node, named after the
+    /// output it drives or `n{var}`, and buffers, inverters or constants for
+    /// the outputs and next latch values that are not plain nodes.
+    pub fn to_model(&self) -> Model {
+        let mut taken: HashSet<String> = self.inputs.iter()
+            .chain(self.latches.iter().map(|latch| &latch.name))
+            .chain(self.outputs.iter().map(|(name, _)| name))
+            .chain(self.clock.iter())
+            .cloned()
+            .collect();
+
+        let mut fresh = |base: String| {
+            let mut name = base;
+            while taken.contains(&name) {
+                name.push('_');
+            }
+            taken.insert(name.clone());
+
+            name
+        };
+
+        let mut names: Vec<Option<String>> = vec![None; self.max_var() + 1];
+        for (i, input) in self.inputs.iter().enumerate() {
+            names[i + 1] = Some(input.clone());
+        }
+        for (i, latch) in self.latches.iter().enumerate() {
+            names[self.inputs.len() + i + 1] = Some(latch.name.clone());
+        }
+
+        // AND nodes take the name of the first output they drive as they are.
+        for (name, lit) in &self.outputs {
+            let var = lit.var();
+            if !lit.is_complemented() && var >= self.first_and() && names[var].is_none() {
+                names[var] = Some(name.clone());
+            }
+        }
+
+        for (var, name) in names.iter_mut().enumerate().skip(self.first_and()) {
+            if name.is_none() {
+                *name = Some(fresh(format!("n{}", var)));
+            }
+        }
+
+        let name = |lit: Lit| names[lit.var()].clone().unwrap();
+        let value = |lit: Lit| if lit.is_complemented() { InputValue::Complemented } else { InputValue::Uncomplemented };
+
+        let mut gates: Vec<LogicGate> = self.ands.iter().enumerate().map(|(i, (a, b))| {
+            LogicGate::new(vec![name(*a), name(*b)], name(Lit::new(self.first_and() + i, false)), vec![
+                (vec![value(*a), value(*b)], InputValue::Uncomplemented),
+            ])
+        }).collect();
+
+        // A gate driving `output` with the value of `lit`.
+        let copy = |lit: Lit, output: String| match lit {
+            Lit::FALSE => LogicGate::new(vec![], output, vec![]),
+            Lit::TRUE => LogicGate::new(vec![], output, vec![(vec![], InputValue::Uncomplemented)]),
+            lit => LogicGate::new(vec![name(lit)], output, vec![(vec![value(lit)], InputValue::Uncomplemented)]),
+        };
+
+        for (output, lit) in &self.outputs {
+            if lit.is_complemented() || lit.is_constant() || name(*lit) != *output {
+                gates.push(copy(*lit, output.clone()));
+            }
+        }
+
+        let mut latches = Vec::new();
+        for latch in &self.latches {
+            let input = if latch.next.is_complemented() || latch.next.is_constant() {
+                let input = fresh(format!("{}$next", latch.name));
+                gates.push(copy(latch.next, input.clone()));
+                input
+            } else {
+                name(latch.next)
+            };
+
+            let init = match latch.init {
+                Some(false) => LatchInit::Low,
+                Some(true) => LatchInit::High,
+                None => LatchInit::Unknown,
+            };
+            let control = self.clock.clone().map(|clock| (LatchType::RisingEdge, clock));
+
+            latches.push(Latch::new(input, latch.name.clone(), control, init));
+        }
+
+        let inputs = self.clock.iter().chain(&self.inputs).cloned().collect();
+        let outputs = self.outputs.iter().map(|(name, _)| name.clone()).collect();
+
+        let mut model = Model::new(self.name.clone(), inputs, outputs, gates);
+        model.latches = latches;
+
+        model
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum AigError {
+    Loop(CombinationalLoop),
+    /// A net driven by a tri-state buffer, which may be high impedance.
+    TriState(String),
+    MultipleDrivers(String),
+    /// A net read without being driven, or driven by an unknown model.
+    Unknown(String),
+    /// A latch control other than the clock shared by every latch, or a
+    /// clock also read by the logic.
+    Clock(String),
+}
+
+impl std::fmt::Display for AigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Loop(err) => write!(f, "{}", err),
+            Self::TriState(net) => write!(f, "net `{}` is driven by a tri-state buffer", net),
+            Self::MultipleDrivers(net) => write!(f, "net `{}` has several drivers", net),
+            Self::Unknown(net) => write!(f, "net `{}` has no known driver", net),
+            Self::Clock(net) => {
+                write!(f, "net `{}` cannot clock the latches: they must share the global clock or the rising edge of a primary input used for nothing else", net)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AigError {}
+
+/// Write `aig` as ASCII AIGER (`aag`), with a symbol table.
+pub fn write_aag(out: &mut dyn Write, aig: &Aig) -> io::Result<()> {
+    write_header(out, aig, "aag")?;
+
+    for i in 0..aig.inputs.len() {
+        writeln!(out, "{}", Lit::new(i + 1, false).0)?;
+    }
+    write_latches(out, aig, true)?;
+    for (_, lit) in &aig.outputs {
+        writeln!(out, "{}", lit.0)?;
+    }
+    for (i, (a, b)) in aig.ands.iter().enumerate() {
+        writeln!(out, "{} {} {}", Lit::new(aig.first_and() + i, false).0, a.0, b.0)?;
+    }
+
+    write_symbols(out, aig)
+}
+
+/// Write `aig` as binary AIGER (`aig`), with a symbol table.
+pub fn write_aig(out: &mut dyn Write, aig: &Aig) -> io::Result<()> {
+    write_header(out, aig, "aig")?;
+
+    write_latches(out, aig, false)?;
+    for (_, lit) in &aig.outputs {
+        writeln!(out, "{}", lit.0)?;
+    }
+
+    let mut bytes = Vec::new();
+    for (i, (a, b)) in aig.ands.iter().enumerate() {
+        let lhs = Lit::new(aig.first_and() + i, false).0;
+        encode(&mut bytes, lhs - a.0);
+        encode(&mut bytes, a.0 - b.0);
+    }
+    out.write_all(&bytes)?;
+
+    write_symbols(out, aig)
+}
+
+fn write_header(out: &mut dyn Write, aig: &Aig, format: &str) -> io::Result<()> {
+    writeln!(out, "{} {} {} {} {} {}", format, aig.max_var(), aig.inputs.len(), aig.latches.len(), aig.outputs.len(), aig.ands.len())
+}
+
+/// Latch lines, leaving out initial values of 0 and the current value in
+/// binary files.
+fn write_latches(out: &mut dyn Write, aig: &Aig, current: bool) -> io::Result<()> {
+    for (i, latch) in aig.latches.iter().enumerate() {
+        let lit = Lit::new(aig.inputs.len() + i + 1, false);
+        if current {
+            write!(out, "{} ", lit.0)?;
+        }
+        write!(out, "{}", latch.next.0)?;
+
+        match latch.init {
+            Some(false) => writeln!(out)?,
+            Some(true) => writeln!(out, " 1")?,
+            None => writeln!(out, " {}", lit.0)?,
+        }
+    }
+
+    Ok(())
+}
+
+fn write_symbols(out: &mut dyn Write, aig: &Aig) -> io::Result<()> {
+    for (i, input) in aig.inputs.iter().enumerate() {
+        writeln!(out, "i{} {}", i, input)?;
+    }
+    for (i, latch) in aig.latches.iter().enumerate() {
+        writeln!(out, "l{} {}", i, latch.name)?;
+    }
+    for (i, (output, _)) in aig.outputs.iter().enumerate() {
+        writeln!(out, "o{} {}", i, output)?;
+    }
+
+    Ok(())
+}
+
+/// Seven bits at a time, least significant first, the high bit set on all
+/// but the last byte.
+fn encode(bytes: &mut Vec<u8>, mut x: u32) {
+    while x >= 0x80 {
+        bytes.push((x & 0x7f) as u8 | 0x80);
+        x >>= 7;
+    }
+    bytes.push(x as u8);
+}
+
+/// An error in an AIGER file.
+#[derive(Debug, PartialEq, Clone)]
+pub struct AigerError {
+    /// 1-based line of the error, the first line of the AND section for
+    /// errors within binary AND nodes.
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for AigerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for AigerError {}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    /// Line of `position`.
+    line: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn error(&self, message: String) -> AigerError {
+        AigerError { line: self.line, message }
+    }
+
+    /// The next line, without its terminator, or `None` at the end.
+    fn next_line(&mut self) -> Result<Option<&'a str>, AigerError> {
+        if self.position == self.bytes.len() {
+            return Ok(None);
+        }
+
+        let rest = &self.bytes[self.position..];
+        let end = rest.iter().position(|byte| *byte == b'\n').unwrap_or(rest.len());
+        let line = std::str::from_utf8(&rest[..end]).map_err(|_| self.error("invalid UTF-8".into()))?;
+
+        self.position = (self.position + end + 1).min(self.bytes.len());
+        self.line += 1;
+
+        Ok(Some(line.trim_end_matches('\r')))
+    }
+
+    fn line(&mut self, what: &str) -> Result<&'a str, AigerError> {
+        self.next_line()?.ok_or_else(|| self.error(format!("expected {}, found the end of the file", what)))
+    }
+
+    /// A line of exactly `count` numbers, or between `count` and `max`.
+    fn numbers(&mut self, what: &str, count: usize, max: usize) -> Result<Vec<u32>, AigerError> {
+        let line = self.line(what)?;
+        let numbers = line.split(' ')
+            .map(|number| number.parse::<u32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| self.error(format!("invalid {} `{}`", what, line)))?;
+
+        if numbers.len() < count || numbers.len() > max {
+            return Err(self.error(format!("invalid {} `{}`", what, line)));
+        }
+
+        Ok(numbers)
+    }
+
+    fn varint(&mut self) -> Result<u32, AigerError> {
+        let mut x: u32 = 0;
+
+        for shift in (0..32).step_by(7) {
+            let byte = *self.bytes.get(self.position).ok_or_else(|| self.error("truncated AND node".into()))?;
+            self.position += 1;
+
+            x |= ((byte & 0x7f) as u32) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(x);
+            }
+        }
+
+        Err(self.error("invalid AND node".into()))
+    }
+}
+
+/// Binary AIGER files list no inputs, so their input count is bounded by
+/// this rather than by the size of the file.
+pub const MAX_BINARY_INPUTS: usize = 1 << 20;
+
+/// Read an ASCII (`aag`) or binary (`aig`) AIGER file into an AIG named
+/// `name`. Inputs, latches and outputs without a symbol are named after
+/// their kind and index, as `i0`, `l0` and `o0`.
+pub fn read_aiger(bytes: &[u8], name: &str) -> Result<Aig, AigerError> {
+    let mut reader = Reader { bytes, position: 0, line: 0 };
+
+    let header = reader.line("a header")?;
+    let (binary, counts) = match header.split_once(' ') {
+        Some(("aag", counts)) => (false, counts),
+        Some(("aig", counts)) => (true, counts),
+        _ => return Err(reader.error(format!("invalid header `{}`", header))),
+    };
+    let counts = counts.split(' ')
+        .map(|count| count.parse::<usize>())
+        .collect::<Result<Vec<_>, _>>()
+        .ok()
+        .filter(|counts| (5..=9).contains(&counts.len()))
+        .ok_or_else(|| reader.error(format!("invalid header `{}`", header)))?;
+
+    if counts[5..].iter().any(|count| *count != 0) {
+        return Err(reader.error("bad state, constraint, justice and fairness properties are not supported".into()));
+    }
+
+    let (max_var, input_count, latch_count, output_count, and_count) = (counts[0], counts[1], counts[2], counts[3], counts[4]);
+    let defined = input_count.checked_add(latch_count).and_then(|count| count.checked_add(and_count));
+    if binary && defined != Some(max_var) {
+        return Err(reader.error(format!("invalid header `{}`: M is not I + L + A", header)));
+    }
+    if defined.is_none_or(|defined| max_var > defined) {
+        return Err(reader.error(format!("invalid header `{}`: M is more than I + L + A", header)));
+    }
+    if binary && input_count > MAX_BINARY_INPUTS {
+        return Err(reader.error(format!("invalid header `{}`: more than {} inputs", header, MAX_BINARY_INPUTS)));
+    }
+    let max_lit = u32::try_from(max_var)
+        .ok()
+        .and_then(|max_var| max_var.checked_mul(2)?.checked_add(1))
+        .ok_or_else(|| reader.error(format!("invalid header `{}`: M is too large", header)))?;
+
+    let mut aig = Aig::new(name.to_string());
+    // Literal of the AIG of each variable of the file, grown as variables
+    // are defined rather than sized from the header.
+    let mut map: Vec<Option<Lit>> = vec![Some(Lit::FALSE)];
+
+    // A variable the file defines, as an input, a latch or an AND node.
+    let define = |reader: &Reader, map: &mut Vec<Option<Lit>>, lit: u32, what: &str| {
+        let var = lit as usize / 2;
+
+        if lit > max_lit || lit < 2 || lit % 2 == 1 {
+            Err(reader.error(format!("invalid {} literal {}", what, lit)))
+        } else if mapped(map, var).is_some() {
+            Err(reader.error(format!("literal {} is defined twice", lit)))
+        } else {
+            if map.len() <= var {
+                map.resize(var + 1, None);
+            }
+            Ok(var)
+        }
+    };
+
+    for i in 0..input_count {
+        let var = if binary {
+            map.push(None);
+            i + 1
+        } else {
+            let lit = reader.numbers("input", 1, 1)?[0];
+            define(&reader, &mut map, lit, "input")?
+        };
+        map[var] = Some(aig.add_input(format!("i{}", i)));
+    }
+
+    let mut next = Vec::new();
+    for i in 0..latch_count {
+        let numbers = if binary {
+            let mut numbers = vec![2 * (input_count + i + 1) as u32];
+            numbers.extend(reader.numbers("latch", 1, 2)?);
+            numbers
+        } else {
+            reader.numbers("latch", 2, 3)?
+        };
+
+        let var = define(&reader, &mut map, numbers[0], "latch")?;
+        let init = match numbers.get(2) {
+            None | Some(0) => Some(false),
+            Some(1) => Some(true),
+            Some(init) if *init == numbers[0] => None,
+            Some(init) => return Err(reader.error(format!("invalid initial value {}", init))),
+        };
+
+        map[var] = Some(aig.add_latch(format!("l{}", i), init));
+        next.push((numbers[1], reader.line));
+    }
+
+    let mut outputs = Vec::new();
+    for _ in 0..output_count {
+        outputs.push((reader.numbers("output", 1, 1)?[0], reader.line));
+    }
+
+    // Fanins of each AND node, and the line defining it. Binary AND nodes
+    // are on no line of their own, and count as the line after the outputs.
+    let mut definitions: HashMap<usize, (u32, u32, usize)> = HashMap::new();
+    let mut order = Vec::new();
+    if binary && and_count > 0 {
+        reader.line += 1;
+    }
+    for i in 0..and_count {
+        let (lhs, a, b) = if binary {
+            let lhs = 2 * (input_count + latch_count + i + 1) as u32;
+            let (delta0, delta1) = (reader.varint()?, reader.varint()?);
+            let a = lhs.checked_sub(delta0).filter(|a| delta0 > 0 && *a > 0);
+
+            match a.and_then(|a| Some((a, a.checked_sub(delta1)?))) {
+                Some((a, b)) => (lhs, a, b),
+                None => return Err(reader.error(format!("invalid AND node {}", lhs))),
+            }
+        } else {
+            let numbers = reader.numbers("AND node", 3, 3)?;
+            (numbers[0], numbers[1], numbers[2])
+        };
+
+        let var = define(&reader, &mut map, lhs, "AND node")?;
+        if definitions.insert(var, (a, b, reader.line)).is_some() {
+            return Err(reader.error(format!("literal {} is defined twice", lhs)));
+        }
+        order.push(var);
+    }
+
+    for var in order {
+        resolve(var, &definitions, &mut map, &mut aig, max_lit)?;
+    }
+
+    let lit = |map: &[Option<Lit>], lit: u32, line: usize| {
+        mapped(map, lit as usize / 2)
+            .map(|mapped| mapped.complement_if(lit % 2 == 1))
+            .ok_or_else(|| AigerError { line, message: format!("literal {} is not defined", lit) })
+    };
+
+    for (i, (next, line)) in next.into_iter().enumerate() {
+        let next = lit(&map, next, line)?;
+        aig.set_next(i, next);
+    }
+    for (i, (output, line)) in outputs.into_iter().enumerate() {
+        let output = lit(&map, output, line)?;
+        aig.add_output(format!("o{}", i), output);
+    }
+
+    while let Some(line) = reader.next_line()? {
+        if line == "c" {
+            break;
+        }
+
+        let symbol = line.split_once(' ')
+            .and_then(|(kind, name)| Some((kind.get(..1)?, kind[1..].parse::<usize>().ok()?, name)));
+        let (slot, name) = match symbol {
+            Some(("i", i, name)) if i < aig.inputs.len() => (&mut aig.inputs[i], name),
+            Some(("l", i, name)) if i < aig.latches.len() => (&mut aig.latches[i].name, name),
+            Some(("o", i, name)) if i < aig.outputs.len() => (&mut aig.outputs[i].0, name),
+            _ => return Err(reader.error(format!("invalid symbol `{}`", line))),
+        };
+        *slot = name.to_string();
+    }
+
+    Ok(aig)
+}
+
+/// Literal of the AIG of variable `var` of the file, if defined yet.
+fn mapped(map: &[Option<Lit>], var: usize) -> Option<Lit> {
+    map.get(var).copied().flatten()
+}
+
+/// Add the AND node of `var` to `aig`, after the nodes it depends on.
+fn resolve(
+    var: usize,
+    definitions: &HashMap<usize, (u32, u32, usize)>,
+    map: &mut [Option<Lit>],
+    aig: &mut Aig,
+    max_lit: u32,
+) -> Result<(), AigerError> {
+    // Nodes being resolved are on the stack twice, below their fanins the
+    // second time: reaching one of them again from its fanins is a cycle.
+    let mut stack = vec![(var, false)];
+    let mut visiting = HashSet::new();
+
+    while let Some((var, expanded)) = stack.pop() {
+        if map[var].is_some() {
+            continue;
+        }
+
+        let (a, b, line) = definitions[&var];
+        let error = |message: String| AigerError { line, message };
+
+        if expanded {
+            let lit = |lit: u32| map[lit as usize / 2].unwrap().complement_if(lit % 2 == 1);
+            map[var] = Some(aig.and(lit(a), lit(b)));
+            visiting.remove(&var);
+            continue;
+        }
+
+        if !visiting.insert(var) {
+            return Err(error(format!("AND node {} depends on itself", 2 * var)));
+        }
+        stack.push((var, true));
+
+        for fanin in [a, b] {
+            if fanin > max_lit {
+                return Err(error(format!("literal {} is out of range", fanin)));
+            }
+
+            let fanin = fanin as usize / 2;
+            if mapped(map, fanin).is_none() {
+                if !definitions.contains_key(&fanin) {
+                    return Err(error(format!("literal {} is not defined", 2 * fanin)));
+                }
+                if visiting.contains(&fanin) {
+                    return Err(error(format!("AND node {} depends on itself", 2 * fanin)));
+                }
+                stack.push((fanin, false));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blif;
+    use crate::simulation::{Simulable, SignalsBuilder};
+
+    #[test]
+    fn test_structural_hashing() {
+        let mut aig = Aig::new("t".into());
+        let a = aig.add_input("a".into());
+        let b = aig.add_input("b".into());
+
+        let ab = aig.and(a, b);
+        assert_eq!(aig.and(b, a), ab);
+        assert_eq!(aig.and(a, !a), Lit::FALSE);
+        assert_eq!(aig.and(a, Lit::TRUE), a);
+        assert_eq!(aig.and(a, a), a);
+        assert_eq!(aig.or(a, Lit::TRUE), Lit::TRUE);
+        assert_eq!(aig.ands().len(), 1);
+        assert_eq!(ab, Lit::new(3, false));
+    }
+
+    #[test]
+    fn test_full_adder() {
+        let blif = blif::parse(include_str!("../fixtures/full_adder.blif")).unwrap();
+        let model = blif.top().unwrap();
+        let aig = Aig::from_model(model).unwrap();
+
+        assert_eq!(aig.inputs, vec!["a", "b", "cin"]);
+
+        for row in 0..8usize {
+            let inputs: Vec<bool> = (0..3).map(|i| row >> i & 1 == 1).collect();
+            let ones = row.count_ones();
+
+            assert_eq!(aig.simulate(&inputs, &[]).0, vec![ones % 2 == 1, ones >= 2]);
+        }
+
+        // Back to BLIF, with the same function.
+        let back = aig.to_model();
+        for row in 0..8usize {
+            let signals = (0..3).fold(SignalsBuilder::new(), |builder, i| {
+                builder.add_signal(&model.inputs[i], if row >> i & 1 == 1 { SignalState::High } else { SignalState::Low })
+            }).build();
+
+            let expected = model.stim(signals.clone());
+            let actual = back.stim(signals);
+            for output in &model.outputs {
+                assert_eq!(actual.get(output), expected.get(output), "{} for row {}", output, row);
+            }
+        }
+    }
+
+    #[test]
+    fn test_counter() {
+        let blif = blif::parse(include_str!("../fixtures/counter.blif")).unwrap();
+        let aig = Aig::from_model(blif.top().unwrap()).unwrap();
+
+        assert_eq!(aig.clock.as_deref(), Some("clk"));
+        assert!(aig.inputs.is_empty());
+
+        let mut aag = Vec::new();
+        write_aag(&mut aag, &aig).unwrap();
+        assert_eq!(String::from_utf8(aag).unwrap(), concat!(
+            "aag 5 0 2 2 3\n",
+            "2 3\n",
+            "4 11\n",
+            "2\n",
+            "4\n",
+            "6 4 3\n",
+            "8 5 2\n",
+            "10 9 7\n",
+            "l0 q[0]\n",
+            "l1 q[1]\n",
+            "o0 q[0]\n",
+            "o1 q[1]\n",
+        ));
+
+        // Counting 0, 1, 2, 3, 0.
+        let mut state = vec![false, false];
+        for count in [1, 2, 3, 0] {
+            state = aig.simulate(&[], &state).1;
+            assert_eq!(state, vec![count & 1 == 1, count & 2 == 2]);
+        }
+
+        let model = aig.to_model();
+        assert_eq!(model.inputs, vec!["clk"]);
+        assert_eq!(blif::write(&blif::Blif::new(vec![model])), concat!(
+            ".model counter\n",
+            ".inputs clk\n",
+            ".outputs q[0] q[1]\n",
+            ".names q[1] q[0] n3\n",
+            "10 1\n",
+            ".names q[1] q[0] n4\n",
+            "01 1\n",
+            ".names n4 n3 n5\n",
+            "00 1\n",
+            ".names q[0] q[0]$next\n",
+            "0 1\n",
+            ".names n5 q[1]$next\n",
+            "0 1\n",
+            ".latch q[0]$next q[0] re clk 0\n",
+            ".latch q[1]$next q[1] re clk 0\n",
+            ".end\n",
+        ));
+    }
+
+    #[test]
+    fn test_aiger_round_trip() {
+        let mut aig = Aig::new("t".into());
+        let a = aig.add_input("a".into());
+        let b = aig.add_input("b".into());
+        let q = aig.add_latch("q".into(), None);
+        let r = aig.add_latch("r".into(), Some(true));
+
+        let ab = aig.and(a, !b);
+        let next = aig.or(ab, q);
+        aig.set_next(0, next);
+        aig.set_next(1, !r);
+        aig.add_output("y".into(), ab);
+        aig.add_output("q".into(), !q);
+        aig.add_output("zero".into(), Lit::FALSE);
+
+        let mut aag = Vec::new();
+        write_aag(&mut aag, &aig).unwrap();
+        assert_eq!(read_aiger(&aag, "t").unwrap(), aig);
+
+        let mut binary = Vec::new();
+        write_aig(&mut binary, &aig).unwrap();
+        assert!(binary.len() < aag.len());
+        assert_eq!(read_aiger(&binary, "t").unwrap(), aig);
+    }
+
+    #[test]
+    fn test_read_aag() {
+        // An AND node defined after its reader, without symbols.
+        let aig = read_aiger(b"aag 4 2 0 1 2\n2\n4\n9\n8 6 2\n6 5 3\nc\ncomment\n", "t").unwrap();
+
+        assert_eq!(aig.inputs, vec!["i0", "i1"]);
+        assert_eq!(aig.outputs[0].0, "o0");
+        // !(a & (!a & !b)) is always true.
+        for inputs in [[false, false], [false, true], [true, false], [true, true]] {
+            assert_eq!(aig.simulate(&inputs, &[]).0, vec![true]);
+        }
+    }
+
+    #[test]
+    fn test_read_errors() {
+        let error = |source: &[u8]| read_aiger(source, "t").unwrap_err().to_string();
+
+        assert_eq!(error(b"aig 1 1 0 0\n"), "line 1: invalid header `aig 1 1 0 0`");
+        assert_eq!(error(b"aag 1 1 0 0 0 1\n2\n"), "line 1: bad state, constraint, justice and fairness properties are not supported");
+        assert_eq!(error(b"aag 1 1 0 1 0\n2\n4\n"), "line 3: literal 4 is not defined");
+        assert_eq!(error(b"aag 2 1 0 0 1\n2\n2 4 2\n"), "line 3: literal 2 is defined twice");
+        assert_eq!(error(b"aag 2 0 0 0 2\n2 4 1\n4 2 1\n"), "line 3: AND node 2 depends on itself");
+        assert_eq!(error(b"aag 1 1 0 0 0\n2\nx1 a\n"), "line 3: invalid symbol `x1 a`");
+
+        // Headers are checked before anything is allocated from them.
+        assert_eq!(error(b"aag 4000000000 0 0 0 0\n"), "line 1: invalid header `aag 4000000000 0 0 0 0`: M is more than I + L + A");
+        assert_eq!(
+            error(b"aag 4000000000 4000000000 0 0 0\n"),
+            "line 1: invalid header `aag 4000000000 4000000000 0 0 0`: M is too large",
+        );
+        assert_eq!(
+            error(b"aag 18446744073709551615 18446744073709551615 1 0 0\n"),
+            "line 1: invalid header `aag 18446744073709551615 18446744073709551615 1 0 0`: M is more than I + L + A",
+        );
+        assert_eq!(error(b"aag 1073741824 1073741824 0 0 0\n2\n"), "line 2: expected input, found the end of the file");
+        // Binary inputs take no room in the file, so their count is capped instead.
+        assert_eq!(
+            error(b"aig 2147483647 2147483647 0 0 0\n"),
+            "line 1: invalid header `aig 2147483647 2147483647 0 0 0`: more than 1048576 inputs",
+        );
+        assert_eq!(read_aiger(b"aig 1048576 1048576 0 0 0\n", "t").unwrap().inputs.len(), MAX_BINARY_INPUTS);
+    }
+
+    #[test]
+    fn test_unsupported_models() {
+        let blif = blif::parse(".model t\n.inputs a e\n.outputs y\n.subckt $_TBUF_ A=a E=e Y=y\n.end\n").unwrap();
+        assert_eq!(Aig::from_model(blif.top().unwrap()).unwrap_err(), AigError::TriState("y".into()));
+
+        let blif = blif::parse(".model t\n.inputs d c\n.outputs q\n.latch d q ah c 0\n.end\n").unwrap();
+        assert_eq!(Aig::from_model(blif.top().unwrap()).unwrap_err(), AigError::Clock("c".into()));
+
+        let blif = blif::parse(".model t\n.inputs c\n.outputs q\n.latch c q re c 0\n.end\n").unwrap();
+        assert_eq!(Aig::from_model(blif.top().unwrap()).unwrap_err(), AigError::Clock("c".into()));
+    }
+}
@@ -0,0 +1,690 @@
+use crate::blif::{CombinationalLoop, Model};
+use crate::netlist::{Cell, Netlist};
+use crate::simulation::SignalState;
+
+use std::collections::HashMap;
+
+/// A function held by a `Manager`: one of the two terminals or a node.
+///
+/// Diagrams are reduced and ordered, so two functions of the same manager
+/// are equal exactly when their `Bdd`s are.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+pub struct Bdd(u32);
+
+impl Bdd {
+    pub const FALSE: Bdd = Bdd(0);
+    pub const TRUE: Bdd = Bdd(1);
+
+    pub fn is_constant(self) -> bool {
+        self == Self::FALSE || self == Self::TRUE
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    var: usize,
+    low: Bdd,
+    high: Bdd,
+}
+
+/// Reduced ordered binary decision diagrams over `var_count` variables.
+///
+/// Nodes are hash-consed in a unique table, and every operation goes
+/// through `ite`, whose results are kept in a computed cache. Nodes are
+/// never freed. The variable order is changed by swapping adjacent levels in
+/// place, so every `Bdd` keeps its function, and the cache stays valid.
+#[derive(Debug, Clone)]
+pub struct Manager {
+    nodes: Vec<Node>,
+    unique: HashMap<(usize, Bdd, Bdd), Bdd>,
+    /// Nodes testing each variable.
+    by_var: Vec<Vec<Bdd>>,
+    cache: HashMap<(Bdd, Bdd, Bdd), Bdd>,
+    /// Variable at each level, the root level first.
+    order: Vec<usize>,
+    /// Level of each variable.
+    levels: Vec<usize>,
+}
+
+impl Manager {
+    /// A manager over `var_count` variables, ordered by index.
+    pub fn new(var_count: usize) -> Self {
+        Self::with_order((0..var_count).collect())
+    }
+
+    /// A manager over the variables of `order`, from the root level down,
+    /// which must hold each of `0..order.len()` once.
+    pub fn with_order(order: Vec<usize>) -> Self {
+        let mut levels = vec![usize::MAX; order.len()];
+        for (level, var) in order.iter().enumerate() {
+            assert!(levels[*var] == usize::MAX, "variable {} appears twice in the order", var);
+            levels[*var] = level;
+        }
+
+        // The terminals, below every variable.
+        let terminal = Node { var: usize::MAX, low: Bdd::FALSE, high: Bdd::FALSE };
+
+        Self {
+            nodes: vec![terminal, terminal],
+            unique: HashMap::new(),
+            by_var: vec![Vec::new(); order.len()],
+            cache: HashMap::new(),
+            order,
+            levels,
+        }
+    }
+
+    pub fn var_count(&self) -> usize {
+        self.order.len()
+    }
+
+    /// Variable at each level, the root level first.
+    pub fn order(&self) -> &[usize] {
+        &self.order
+    }
+
+    /// The function true when variable `var` is.
+    pub fn var(&mut self, var: usize) -> Bdd {
+        self.node(var, Bdd::FALSE, Bdd::TRUE)
+    }
+
+    /// Variable tested at the root of `f`, `None` for the terminals.
+    pub fn top_var(&self, f: Bdd) -> Option<usize> {
+        (!f.is_constant()).then(|| self.nodes[f.0 as usize].var)
+    }
+
+    /// Cofactors of `f` with its root variable low and high.
+    pub fn children(&self, f: Bdd) -> (Bdd, Bdd) {
+        let node = &self.nodes[f.0 as usize];
+        (node.low, node.high)
+    }
+
+    fn level(&self, f: Bdd) -> usize {
+        match self.top_var(f) {
+            Some(var) => self.levels[var],
+            None => usize::MAX,
+        }
+    }
+
+    /// The node testing `var`, with the reduction rules applied.
+    fn node(&mut self, var: usize, low: Bdd, high: Bdd) -> Bdd {
+        if low == high {
+            return low;
+        }
+
+        if let Some(f) = self.unique.get(&(var, low, high)) {
+            return *f;
+        }
+
+        let f = Bdd(self.nodes.len() as u32);
+        self.nodes.push(Node { var, low, high });
+        self.unique.insert((var, low, high), f);
+        self.by_var[var].push(f);
+
+        f
+    }
+
+    /// Cofactors of `f` with respect to the variable at `level`.
+    fn cofactors(&self, f: Bdd, level: usize) -> (Bdd, Bdd) {
+        if self.level(f) == level {
+            self.children(f)
+        } else {
+            (f, f)
+        }
+    }
+
+    /// If `f` then `g` else `h`.
+    pub fn ite(&mut self, f: Bdd, g: Bdd, h: Bdd) -> Bdd {
+        match (f, g, h) {
+            (Bdd::TRUE, _, _) => return g,
+            (Bdd::FALSE, _, _) => return h,
+            _ if g == h => return g,
+            (_, Bdd::TRUE, Bdd::FALSE) => return f,
+            _ => (),
+        }
+
+        if let Some(result) = self.cache.get(&(f, g, h)) {
+            return *result;
+        }
+
+        let level = self.level(f).min(self.level(g)).min(self.level(h));
+        let (f0, f1) = self.cofactors(f, level);
+        let (g0, g1) = self.cofactors(g, level);
+        let (h0, h1) = self.cofactors(h, level);
+
+        let low = self.ite(f0, g0, h0);
+        let high = self.ite(f1, g1, h1);
+        let result = self.node(self.order[level], low, high);
+
+        self.cache.insert((f, g, h), result);
+        result
+    }
+
+    pub fn not(&mut self, f: Bdd) -> Bdd {
+        self.ite(f, Bdd::FALSE, Bdd::TRUE)
+    }
+
+    pub fn and(&mut self, f: Bdd, g: Bdd) -> Bdd {
+        self.ite(f, g, Bdd::FALSE)
+    }
+
+    pub fn or(&mut self, f: Bdd, g: Bdd) -> Bdd {
+        self.ite(f, Bdd::TRUE, g)
+    }
+
+    pub fn xor(&mut self, f: Bdd, g: Bdd) -> Bdd {
+        let not_g = self.not(g);
+        self.ite(f, not_g, g)
+    }
+
+    /// `f` with variable `var` fixed to `value`.
+    pub fn restrict(&mut self, f: Bdd, var: usize, value: bool) -> Bdd {
+        // Only the assignments with `var` at `value` are left, then `var`
+        // is quantified away.
+        let mut literal = self.var(var);
+        if !value {
+            literal = self.not(literal);
+        }
+        let chosen = self.and(literal, f);
+
+        self.exists_var(chosen, var)
+    }
+
+    /// `f` with `var` quantified away: true when either cofactor is.
+    pub fn exists_var(&mut self, f: Bdd, var: usize) -> Bdd {
+        let mut memo = HashMap::new();
+        self.exists_rec(f, self.levels[var], &mut memo)
+    }
+
+    fn exists_rec(&mut self, f: Bdd, level: usize, memo: &mut HashMap<Bdd, Bdd>) -> Bdd {
+        if self.level(f) > level {
+            return f;
+        }
+
+        if let Some(result) = memo.get(&f) {
+            return *result;
+        }
+
+        let (low, high) = self.children(f);
+        let result = if self.level(f) == level {
+            self.or(low, high)
+        } else {
+            let var = self.nodes[f.0 as usize].var;
+            let low = self.exists_rec(low, level, memo);
+            let high = self.exists_rec(high, level, memo);
+            self.node(var, low, high)
+        };
+
+        memo.insert(f, result);
+        result
+    }
+
+    pub fn is_tautology(&self, f: Bdd) -> bool {
+        f == Bdd::TRUE
+    }
+
+    pub fn is_satisfiable(&self, f: Bdd) -> bool {
+        f != Bdd::FALSE
+    }
+
+    /// Number of assignments of the `var_count` variables satisfying `f`.
+    pub fn sat_count(&self, f: Bdd) -> f64 {
+        // Fraction of the assignments satisfying each node.
+        fn density(manager: &Manager, f: Bdd, memo: &mut HashMap<Bdd, f64>) -> f64 {
+            match f {
+                Bdd::FALSE => 0.0,
+                Bdd::TRUE => 1.0,
+                _ => {
+                    if let Some(density) = memo.get(&f) {
+                        return *density;
+                    }
+
+                    let (low, high) = manager.children(f);
+                    let result = (density(manager, low, memo) + density(manager, high, memo)) / 2.0;
+
+                    memo.insert(f, result);
+                    result
+                }
+            }
+        }
+
+        density(self, f, &mut HashMap::new()) * 2f64.powi(self.var_count() as i32)
+    }
+
+    /// A satisfying assignment of `f`, as `(variable, value)` pairs: the
+    /// variables left out can take any value.
+    pub fn any_sat(&self, f: Bdd) -> Option<Vec<(usize, bool)>> {
+        if f == Bdd::FALSE {
+            return None;
+        }
+
+        let mut assignment = Vec::new();
+        let mut f = f;
+
+        // Every node but the false terminal has a path to the true one.
+        while !f.is_constant() {
+            let (low, high) = self.children(f);
+            let var = self.nodes[f.0 as usize].var;
+
+            if low != Bdd::FALSE {
+                assignment.push((var, false));
+                f = low;
+            } else {
+                assignment.push((var, true));
+                f = high;
+            }
+        }
+
+        Some(assignment)
+    }
+
+    /// Value of `f` when each variable `i` is `values[i]`.
+    pub fn eval(&self, f: Bdd, values: &[bool]) -> bool {
+        let mut f = f;
+
+        while !f.is_constant() {
+            let (low, high) = self.children(f);
+            f = if values[self.nodes[f.0 as usize].var] { high } else { low };
+        }
+
+        f == Bdd::TRUE
+    }
+
+    /// Number of nodes reachable from `roots`, the terminals not included.
+    pub fn size(&self, roots: &[Bdd]) -> usize {
+        let mut seen = std::collections::HashSet::new();
+        let mut stack: Vec<Bdd> = roots.to_vec();
+
+        while let Some(f) = stack.pop() {
+            if f.is_constant() || !seen.insert(f) {
+                continue;
+            }
+
+            let (low, high) = self.children(f);
+            stack.push(low);
+            stack.push(high);
+        }
+
+        seen.len()
+    }
+
+    /// Exchange the variables at `level` and `level + 1`.
+    ///
+    /// Only the nodes testing the upper variable `x` with a child testing the
+    /// lower one `y` change: each is rewritten in place to test `y`, over new
+    /// nodes testing `x`, so it keeps its function and its handle.
+    fn swap(&mut self, level: usize) {
+        let (x, y) = (self.order[level], self.order[level + 1]);
+        let cofactors = |manager: &Self, f: Bdd| match manager.top_var(f) {
+            Some(var) if var == y => manager.children(f),
+            _ => (f, f),
+        };
+
+        let (moved, kept): (Vec<Bdd>, Vec<Bdd>) = std::mem::take(&mut self.by_var[x]).into_iter()
+            .partition(|f| {
+                let (low, high) = self.children(*f);
+                self.top_var(low) == Some(y) || self.top_var(high) == Some(y)
+            });
+        self.by_var[x] = kept;
+
+        self.order.swap(level, level + 1);
+        self.levels[x] = level + 1;
+        self.levels[y] = level;
+
+        for f in moved {
+            let (f0, f1) = self.children(f);
+            let ((f00, f01), (f10, f11)) = (cofactors(self, f0), cofactors(self, f1));
+
+            // Either cofactor tests `x`, as `f` depends on it: no node
+            // testing `y` over the same children exists yet.
+            let low = self.node(x, f00, f10);
+            let high = self.node(x, f01, f11);
+
+            self.unique.remove(&(x, f0, f1));
+            self.unique.insert((y, low, high), f);
+            self.nodes[f.0 as usize] = Node { var: y, low, high };
+            self.by_var[y].push(f);
+        }
+    }
+
+    /// Reorder the variables as `order`, from the root level down, which
+    /// must hold each of `0..var_count` once.
+    pub fn set_order(&mut self, order: &[usize]) {
+        let mut sorted = order.to_vec();
+        sorted.sort_unstable();
+        assert!(sorted.iter().copied().eq(0..self.var_count()), "{:?} is not an order of the variables", order);
+
+        // Bring each variable up to its level, above the ones left to place.
+        for (level, var) in order.iter().enumerate() {
+            for above in (level..self.levels[*var]).rev() {
+                self.swap(above);
+            }
+        }
+    }
+
+    /// Reorder the variables to make `roots` smaller, by sifting: each
+    /// variable in turn, from the one with the most nodes, is swapped down to
+    /// the bottom level then up to the top one, and left at the level where
+    /// the diagrams were the smallest.
+    pub fn sift(&mut self, roots: &[Bdd]) {
+        let mut per_var = vec![0; self.var_count()];
+        let mut seen = std::collections::HashSet::new();
+        let mut stack: Vec<Bdd> = roots.to_vec();
+        while let Some(f) = stack.pop() {
+            if !f.is_constant() && seen.insert(f) {
+                per_var[self.nodes[f.0 as usize].var] += 1;
+                stack.extend([self.children(f).0, self.children(f).1]);
+            }
+        }
+
+        let mut vars: Vec<usize> = (0..self.var_count()).collect();
+        vars.sort_by_key(|var| std::cmp::Reverse(per_var[*var]));
+
+        for var in vars {
+            let mut best = (self.size(roots), self.levels[var]);
+
+            for level in self.levels[var]..self.var_count() - 1 {
+                self.swap(level);
+                best = best.min((self.size(roots), level + 1));
+            }
+            for level in (0..self.var_count() - 1).rev() {
+                self.swap(level);
+                best = best.min((self.size(roots), level));
+            }
+            for level in 0..best.1 {
+                self.swap(level);
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum BddError {
+    Loop(CombinationalLoop),
+    /// A net driven by a tri-state buffer, which may be high impedance.
+    TriState(String),
+    MultipleDrivers(String),
+    /// A net read without being driven, or driven by an unknown model.
+    Unknown(String),
+}
+
+impl std::fmt::Display for BddError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Loop(err) => write!(f, "{}", err),
+            Self::TriState(net) => write!(f, "net `{}` is driven by a tri-state buffer", net),
+            Self::MultipleDrivers(net) => write!(f, "net `{}` has several drivers", net),
+            Self::Unknown(net) => write!(f, "net `{}` has no known driver", net),
+        }
+    }
+}
+
+impl std::error::Error for BddError {}
+
+/// The BDD of every output of a model, over its inputs and latch outputs.
+///
+/// Instances are flattened, and latches are cut: the variables are the
+/// primary inputs then the outputs of every latch of the hierarchy, and each
+/// function is the one of the combinational logic. Values are two-valued, so
+/// models which may compute unknown or high-impedance values from known
+/// inputs are refused.
+#[derive(Debug, Clone)]
+pub struct ModelBdd {
+    pub manager: Manager,
+    /// Name of each variable.
+    pub variables: Vec<String>,
+    /// `(output, function)` in `.outputs` order.
+    pub outputs: Vec<(String, Bdd)>,
+}
+
+impl ModelBdd {
+    pub fn new(model: &Model) -> Result<Self, BddError> {
+        let netlist = Netlist::new(model).map_err(BddError::Loop)?;
+        let name = |net: usize| netlist.net_name(net).to_string();
+
+        let variables: Vec<usize> = netlist.inputs.iter().copied()
+            .chain(netlist.latches.iter().map(|latch| latch.output))
+            .collect();
+
+        let mut manager = Manager::new(variables.len());
+        let mut values: Vec<Option<Bdd>> = vec![None; netlist.net_count()];
+
+        for (var, net) in variables.iter().enumerate() {
+            values[*net] = Some(manager.var(var));
+        }
+
+        for node in &netlist.nodes {
+            if node.resolve || values[node.output].is_some() {
+                return Err(BddError::MultipleDrivers(name(node.output)));
+            }
+
+            let get = |net: usize| values[net].ok_or_else(|| BddError::Unknown(name(net)));
+
+            let value = match &node.cell {
+                Cell::Cover(cover) => {
+                    let fanin = netlist.fanin(cover);
+                    let mut sum = Bdd::FALSE;
+
+                    for (care, bits) in netlist.rows(cover) {
+                        let mut cube = Bdd::TRUE;
+
+                        for (i, net) in fanin.iter().enumerate() {
+                            if care[i / 64] >> (i % 64) & 1 == 1 {
+                                let input = get(*net)?;
+                                let literal = if bits[i / 64] >> (i % 64) & 1 == 1 { input } else { manager.not(input) };
+                                cube = manager.and(cube, literal);
+                            }
+                        }
+
+                        sum = manager.or(sum, cube);
+                    }
+
+                    if cover.matched == SignalState::High { sum } else { manager.not(sum) }
+                }
+                Cell::Tbuf { .. } => return Err(BddError::TriState(name(node.output))),
+                Cell::Buffer(input) => get(*input)?,
+                Cell::Const(SignalState::High) => Bdd::TRUE,
+                Cell::Const(SignalState::Low) => Bdd::FALSE,
+                Cell::Const(_) => return Err(BddError::Unknown(name(node.output))),
+            };
+
+            values[node.output] = Some(value);
+        }
+
+        let outputs = netlist.outputs.iter()
+            .map(|net| values[*net].map(|value| (name(*net), value)).ok_or_else(|| BddError::Unknown(name(*net))))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { manager, variables: variables.into_iter().map(name).collect(), outputs })
+    }
+
+    pub fn output(&self, name: &str) -> Option<Bdd> {
+        self.outputs.iter().find(|(output, _)| output == name).map(|(_, f)| *f)
+    }
+
+    /// Number of nodes of all the outputs together.
+    pub fn size(&self) -> usize {
+        self.manager.size(&self.roots())
+    }
+
+    fn roots(&self) -> Vec<Bdd> {
+        self.outputs.iter().map(|(_, f)| *f).collect()
+    }
+
+    /// Reorder the variables as `order`, from the root down.
+    pub fn set_order(&mut self, order: &[usize]) {
+        self.manager.set_order(order);
+    }
+
+    /// Reorder the variables by sifting the outputs, see `Manager::sift`.
+    pub fn sift(&mut self) {
+        let roots = self.roots();
+        self.manager.sift(&roots);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blif;
+
+    #[test]
+    fn test_canonical() {
+        let mut m = Manager::new(3);
+        let (a, b, c) = (m.var(0), m.var(1), m.var(2));
+
+        // ab + ac = a(b + c)
+        let ab = m.and(a, b);
+        let ac = m.and(a, c);
+        let left = m.or(ab, ac);
+        let b_or_c = m.or(b, c);
+        let right = m.and(a, b_or_c);
+        assert_eq!(left, right);
+
+        let not_a = m.not(a);
+        let tautology = m.or(a, not_a);
+        assert!(m.is_tautology(tautology));
+        let contradiction = m.and(a, not_a);
+        assert!(!m.is_satisfiable(contradiction));
+
+        let x = m.xor(a, b);
+        let y = m.xor(x, b);
+        assert_eq!(y, a);
+    }
+
+    #[test]
+    fn test_queries() {
+        let mut m = Manager::new(3);
+        let (a, b, c) = (m.var(0), m.var(1), m.var(2));
+        let ab = m.and(a, b);
+        let abc = m.and(ab, c);
+        let or = m.or(ab, c);
+
+        assert_eq!(m.sat_count(abc), 1.0);
+        assert_eq!(m.sat_count(or), 5.0);
+        assert_eq!(m.sat_count(Bdd::TRUE), 8.0);
+        assert_eq!(m.any_sat(abc), Some(vec![(0, true), (1, true), (2, true)]));
+        assert_eq!(m.any_sat(Bdd::FALSE), None);
+
+        let assignment = m.any_sat(or).unwrap();
+        let mut values = [false; 3];
+        for (var, value) in assignment {
+            values[var] = value;
+        }
+        assert!(m.eval(or, &values));
+
+        assert_eq!(m.restrict(or, 2, false), ab);
+        assert_eq!(m.restrict(or, 2, true), Bdd::TRUE);
+        let bc = m.and(b, c);
+        assert_eq!(m.exists_var(abc, 0), bc);
+    }
+
+    #[test]
+    fn test_full_adder() {
+        let blif = blif::parse(include_str!("../fixtures/full_adder.blif")).unwrap();
+        let bdd = ModelBdd::new(blif.top().unwrap()).unwrap();
+
+        assert_eq!(bdd.variables, vec!["a", "b", "cin"]);
+
+        let (sum, cout) = (bdd.output("sum").unwrap(), bdd.output("cout").unwrap());
+        assert_eq!(bdd.manager.sat_count(sum), 4.0);
+        assert_eq!(bdd.manager.sat_count(cout), 4.0);
+
+        for row in 0..8usize {
+            let values: Vec<bool> = (0..3).map(|i| row >> i & 1 == 1).collect();
+            let ones = row.count_ones();
+
+            assert_eq!(bdd.manager.eval(sum, &values), ones % 2 == 1);
+            assert_eq!(bdd.manager.eval(cout, &values), ones >= 2);
+        }
+    }
+
+    #[test]
+    fn test_hierarchy_and_latches() {
+        let blif = blif::parse(include_str!("../fixtures/med.blif")).unwrap();
+        let bdd = ModelBdd::new(blif.top().unwrap()).unwrap();
+
+        // o_m1 = A & !B, o_m2 = o_m1 & !B.
+        assert_eq!(bdd.output("o_m1"), bdd.output("o_m2"));
+        assert_eq!(bdd.manager.any_sat(bdd.output("o_m1").unwrap()), Some(vec![(0, true), (1, false)]));
+
+        let blif = blif::parse(include_str!("../fixtures/counter.blif")).unwrap();
+        let bdd = ModelBdd::new(blif.top().unwrap()).unwrap();
+
+        assert_eq!(bdd.variables, vec!["clk", "q[0]", "q[1]"]);
+        assert_eq!(bdd.output("q[1]"), Some(bdd.manager.clone().var(2)));
+    }
+
+    #[test]
+    fn test_tri_state() {
+        let blif = blif::parse(".model t\n.inputs a e\n.outputs y\n.subckt $_TBUF_ A=a E=e Y=y\n.end\n").unwrap();
+
+        assert_eq!(ModelBdd::new(blif.top().unwrap()).unwrap_err(), BddError::TriState("y".into()));
+    }
+
+    #[test]
+    fn test_swap() {
+        let mut m = Manager::new(4);
+        let vars: Vec<Bdd> = (0..4).map(|var| m.var(var)).collect();
+
+        // (x0 ^ x1) & (x2 | !x3), and x1 & x3.
+        let x = m.xor(vars[0], vars[1]);
+        let not_x3 = m.not(vars[3]);
+        let or = m.or(vars[2], not_x3);
+        let f = m.and(x, or);
+        let g = m.and(vars[1], vars[3]);
+
+        let truth = |m: &Manager, f: Bdd| (0..16usize)
+            .map(|row| m.eval(f, &(0..4).map(|i| row >> i & 1 == 1).collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+        let (f_truth, g_truth) = (truth(&m, f), truth(&m, g));
+
+        for order in [[3, 2, 1, 0], [1, 3, 0, 2], [0, 1, 2, 3]] {
+            m.set_order(&order);
+            assert_eq!(m.order(), order);
+            assert_eq!((truth(&m, f), truth(&m, g)), (f_truth.clone(), g_truth.clone()));
+
+            // Still canonical: building the functions again gives the same nodes.
+            let x = m.xor(vars[0], vars[1]);
+            let not_x3 = m.not(vars[3]);
+            let or = m.or(vars[2], not_x3);
+            assert_eq!(m.and(x, or), f);
+            assert_eq!(m.and(vars[3], vars[1]), g);
+        }
+    }
+
+    #[test]
+    fn test_sifting() {
+        // x0 x1 + x2 x3 + x4 x5 is linear in the size of an order keeping
+        // the pairs together, and exponential when the pairs are split.
+        let mut rows = String::new();
+        for pair in 0..3 {
+            let mut row = ['-'; 6];
+            row[2 * pair] = '1';
+            row[2 * pair + 1] = '1';
+            rows.push_str(&format!("{} 1\n", row.iter().collect::<String>()));
+        }
+        let source = format!(".model pairs\n.inputs x0 x1 x2 x3 x4 x5\n.outputs y\n.names x0 x1 x2 x3 x4 x5 y\n{}.end\n", rows);
+        let blif = blif::parse(&source).unwrap();
+
+        let mut bdd = ModelBdd::new(blif.top().unwrap()).unwrap();
+        let before = bdd.output("y");
+        assert_eq!(bdd.size(), 6);
+
+        bdd.set_order(&[0, 2, 4, 1, 3, 5]);
+        assert_eq!(bdd.manager.order(), &[0, 2, 4, 1, 3, 5]);
+        assert_eq!(bdd.size(), 14);
+
+        bdd.sift();
+        assert_eq!(bdd.size(), 6);
+        assert_eq!(bdd.output("y"), before);
+
+        let y = bdd.output("y").unwrap();
+        for assignment in 0..64usize {
+            let values: Vec<bool> = (0..6).map(|i| assignment >> i & 1 == 1).collect();
+            let expected = (0..3).any(|pair| values[2 * pair] && values[2 * pair + 1]);
+
+            assert_eq!(bdd.manager.eval(y, &values), expected);
+        }
+    }
+}
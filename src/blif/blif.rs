@@ -1,27 +1,92 @@
 use super::Model;
+use super::CombinationalLoop;
 
-use crate::simulation::Simulable;
+use crate::simulation::{Simulable, Sequential, Signals};
 
 use std::collections::HashSet;
 
+#[derive(PartialEq, Debug)]
 pub struct Blif {
     models: Vec<Model>,
 }
 
 impl Blif {
     pub fn new(models: Vec<Model>) -> Self {
-        Self { models }
+        let resolved = models.iter().map(|model| {
+            resolve(model, &models, &mut vec![model.name.clone()])
+        }).collect();
+
+        Self { models: resolved }
+    }
+
+    pub fn models(&self) -> &[Model] {
+        &self.models
+    }
+
+    pub fn model(&self, name: &str) -> Option<&Model> {
+        self.models.iter().find(|model| model.name == name)
+    }
+
+    pub fn check(&self) -> Result<(), CombinationalLoop> {
+        self.models.iter().try_for_each(|model| model.check())
+    }
+
+    /// The root of the hierarchy: the only model that no `.subckt` instantiates,
+    /// falling back to the first model of the file as the BLIF spec says.
+    pub fn top(&self) -> Option<&Model> {
+        let instantiated: HashSet<&str> = self.models.iter()
+            .flat_map(|model| model.subckts.iter().map(|subckt| subckt.model.as_str()))
+            .collect();
+
+        let mut roots = self.models.iter().filter(|model| !instantiated.contains(model.name.as_str()));
+
+        match (roots.next(), roots.next()) {
+            (Some(root), None) => Some(root),
+            _ => self.models.first(),
+        }
     }
 }
 
+/// Attach the definition of every `.subckt` of `model`, recursively.
+///
+/// `stack` holds the models currently being resolved, so that a recursive
+/// hierarchy leaves the offending instance unresolved instead of looping.
+fn resolve(model: &Model, models: &[Model], stack: &mut Vec<String>) -> Model {
+    let mut model = model.clone();
+
+    for subckt in model.subckts.iter_mut() {
+        if stack.contains(&subckt.model) {
+            continue;
+        }
+
+        if let Some(definition) = models.iter().find(|m| m.name == subckt.model) {
+            stack.push(definition.name.clone());
+            subckt.definition = Some(Box::new(resolve(definition, models, stack)));
+            stack.pop();
+        }
+    }
+
+    model
+}
+
 impl Simulable for Blif {
     fn get_inputs(&self) -> HashSet<String> {
-        unimplemented!()
+        self.top().map_or(HashSet::new(), |top| top.get_inputs())
     }
 
     fn children(&self) -> Vec<Box<dyn Simulable>> {
-        vec![
-            Box::new(self.models.last().unwrap().clone()) as Box<dyn Simulable>
-        ]
+        self.top().into_iter().map(|top| {
+            Box::new(top.clone()) as Box<dyn Simulable>
+        }).collect()
+    }
+}
+
+impl Sequential for Blif {
+    fn initial_state(&self) -> Signals {
+        self.top().map_or(Signals::new(), |top| top.initial_state())
+    }
+
+    fn next_state(&self, state: &Signals, previous: &Signals, current: &Signals, global_clock: bool) -> Signals {
+        self.top().map_or(Signals::new(), |top| top.next_state(state, previous, current, global_clock))
     }
 }
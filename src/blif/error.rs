@@ -0,0 +1,159 @@
+use nom::error::{ErrorKind, VerboseError, VerboseErrorKind};
+
+/// A parse error located in the BLIF source.
+#[derive(Debug, PartialEq, Clone)]
+pub struct BlifError {
+    /// File the source was read from, when known.
+    pub path: Option<String>,
+    /// 1-based line of the error.
+    pub line: usize,
+    /// 1-based column of the error, in characters.
+    pub column: usize,
+    /// The offending line, without its line terminator.
+    pub line_text: String,
+    pub message: String,
+    /// Parser contexts, outermost first (`"blif"`, `"model"`, `"logic-gate"`, ...).
+    pub context: Vec<&'static str>,
+}
+
+impl BlifError {
+    /// Locate `error` in `parsed`, the complete input given to the parser, and
+    /// report it in `source` with `locate` mapping offsets from one to the other.
+    pub fn new(source: &str, parsed: &str, error: VerboseError<&str>, locate: impl Fn(usize) -> usize) -> Self {
+        // The innermost error, skipping the contexts wrapped around it.
+        let (remaining, kind) = error.errors.iter()
+            .find(|(_, kind)| !matches!(kind, VerboseErrorKind::Context(_)))
+            .or_else(|| error.errors.first())
+            .map_or(("", None), |(remaining, kind)| (*remaining, Some(kind)));
+
+        let context: Vec<&'static str> = error.errors.iter().rev().filter_map(|(_, kind)| match kind {
+            VerboseErrorKind::Context(context) => Some(*context),
+            _ => None,
+        }).collect();
+
+        let message = match kind {
+            Some(VerboseErrorKind::Char(c)) => format!("expected {}", describe_char(*c)),
+            Some(VerboseErrorKind::Nom(ErrorKind::MapOpt)) | Some(VerboseErrorKind::Nom(ErrorKind::Verify)) => {
+                match context.last() {
+                    Some(context) => describe_context(context),
+                    None => describe_token(remaining),
+                }
+            }
+            _ => describe_token(remaining),
+        };
+
+        Self::at(source, locate(parsed.len() - remaining.len()), message, context)
+    }
+
+    /// An error at byte `offset` of `source`.
+    pub fn at(source: &str, offset: usize, message: String, context: Vec<&'static str>) -> Self {
+        let before = &source[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[offset..].find('\n').map_or(source.len(), |i| offset + i);
+
+        Self {
+            path: None,
+            line: before.matches('\n').count() + 1,
+            column: source[line_start..offset].chars().count() + 1,
+            line_text: source[line_start..line_end].trim_end_matches('\r').to_string(),
+            message,
+            context,
+        }
+    }
+
+    pub fn with_path(mut self, path: &str) -> Self {
+        self.path = Some(path.to_string());
+        self
+    }
+}
+
+fn describe_char(c: char) -> String {
+    match c {
+        '\n' => "end of line".into(),
+        c => format!("`{}`", c),
+    }
+}
+
+/// What was wrong with a construct rejected after being parsed.
+fn describe_context(context: &str) -> String {
+    match context {
+        "cover-polarity" => "cover mixes ON-set (`1`) and OFF-set (`0`) rows".into(),
+        "cover-width" => "cover row does not have one column per input of `.names`".into(),
+        context => format!("invalid {}", context),
+    }
+}
+
+fn describe_token(remaining: &str) -> String {
+    match remaining.split_whitespace().next() {
+        Some(token) if remaining.starts_with(token) => format!("unexpected `{}`", token),
+        _ if remaining.is_empty() => "unexpected end of file".into(),
+        _ if remaining.starts_with('\n') => "unexpected end of line".into(),
+        _ => "unexpected whitespace".into(),
+    }
+}
+
+impl std::fmt::Display for BlifError {
+    /// Rendered as
+    ///
+    /// ```text
+    /// error: expected end of line
+    ///  --> fixtures/smol.blif:8:6
+    ///   |
+    /// 8 | 11 1 x
+    ///   |     ^
+    ///   = note: in blif > model > logic-gate > single-output-cover
+    /// ```
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let number = self.line.to_string();
+        let gutter = " ".repeat(number.len());
+
+        writeln!(f, "error: {}", self.message)?;
+        writeln!(f, "{}--> {}:{}:{}", gutter, self.path.as_deref().unwrap_or("<input>"), self.line, self.column)?;
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", number, self.line_text)?;
+        write!(f, "{} | {}^", gutter, " ".repeat(self.column - 1))?;
+
+        if !self.context.is_empty() {
+            write!(f, "\n{} = note: in {}", gutter, self.context.join(" > "))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for BlifError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_position() {
+        let source = ".model a\n.inputs a b\n.outputs\n";
+        let offset = source.find(".outputs").unwrap() + ".outputs".len();
+
+        let error = BlifError::at(source, offset, "expected a name".into(), vec!["model"]);
+
+        assert_eq!(error.line, 3);
+        assert_eq!(error.column, 9);
+        assert_eq!(error.line_text, ".outputs");
+    }
+
+    #[test]
+    fn test_error_display() {
+        let source = ".model a\n.names a b\n2 1\n";
+        let offset = source.find('2').unwrap();
+
+        let error = BlifError::at(source, offset, "unexpected `2`".into(), vec!["blif", "model"])
+            .with_path("a.blif");
+
+        assert_eq!(error.to_string(), concat!(
+            "error: unexpected `2`\n",
+            " --> a.blif:3:1\n",
+            "  |\n",
+            "3 | 2 1\n",
+            "  | ^\n",
+            "  = note: in blif > model",
+        ));
+    }
+}
@@ -0,0 +1,186 @@
+use super::{InputValue, LogicGate};
+
+/// Gates with more inputs are not classified, their truth table being too large.
+pub const MAX_CLASSIFIED_INPUTS: usize = 16;
+
+/// The function computed by the cover of a `LogicGate`, as found by
+/// `LogicGate::function`.
+///
+/// `And` to `Xnor` are functions of every input of the gate, none of them
+/// complemented; `Buf` and `Not` are gates with a single input.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum GateFunction {
+    Zero,
+    One,
+    Buf,
+    Not,
+    And,
+    Nand,
+    Or,
+    Nor,
+    Xor,
+    Xnor,
+    /// `inputs[high]` when `inputs[select]` is high, `inputs[low]` otherwise.
+    Mux { select: usize, low: usize, high: usize },
+    /// Any other function, only known from its cover.
+    Cover,
+}
+
+impl std::fmt::Display for GateFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Zero => write!(f, "0"),
+            Self::One => write!(f, "1"),
+            Self::Buf => write!(f, "BUF"),
+            Self::Not => write!(f, "NOT"),
+            Self::And => write!(f, "AND"),
+            Self::Nand => write!(f, "NAND"),
+            Self::Or => write!(f, "OR"),
+            Self::Nor => write!(f, "NOR"),
+            Self::Xor => write!(f, "XOR"),
+            Self::Xnor => write!(f, "XNOR"),
+            Self::Mux { .. } => write!(f, "MUX"),
+            Self::Cover => write!(f, "cover"),
+        }
+    }
+}
+
+impl LogicGate {
+    /// Recognise the function of the cover, whatever the order of its rows
+    /// and the way they are split by don't-cares: `1- 1` and `-1 1` is an
+    /// `Or` just as `00 0` is.
+    ///
+    /// Gates with more than `MAX_CLASSIFIED_INPUTS` inputs, or with rows of
+    /// the wrong width, are a `Cover` unless they are constant.
+    pub fn function(&self) -> GateFunction {
+        let n = self.inputs.len();
+        let polarity = self.polarity();
+
+        let rows: Vec<&Vec<InputValue>> = self.single_output_cover.iter()
+            .filter(|(_, output)| *output == polarity)
+            .map(|(inputs, _)| inputs)
+            .collect();
+
+        let complemented = polarity == InputValue::Complemented;
+        let constant = |value: bool| if value != complemented { GateFunction::One } else { GateFunction::Zero };
+
+        if rows.is_empty() {
+            return constant(false);
+        }
+
+        if rows.iter().any(|row| row.iter().all(|value| *value == InputValue::NotUsed)) {
+            return constant(true);
+        }
+
+        if n > MAX_CLASSIFIED_INPUTS || rows.iter().any(|row| row.len() != n) {
+            return GateFunction::Cover;
+        }
+
+        // `(care, value)` masks of the rows, input `i` being bit `i` of a minterm.
+        let cubes: Vec<(u32, u32)> = rows.iter().map(|row| {
+            row.iter().enumerate().fold((0, 0), |(care, value), (i, input)| match input {
+                InputValue::Uncomplemented => (care | 1 << i, value | 1 << i),
+                InputValue::Complemented => (care | 1 << i, value),
+                InputValue::NotUsed => (care, value),
+            })
+        }).collect();
+
+        let table: Vec<bool> = (0..1u32 << n)
+            .map(|minterm| cubes.iter().any(|(care, value)| minterm & care == *value) != complemented)
+            .collect();
+
+        let is = |function: &dyn Fn(u32) -> bool| table.iter().enumerate().all(|(minterm, value)| function(minterm as u32) == *value);
+        let bit = |minterm: u32, i: usize| minterm >> i & 1 == 1;
+        let all = (1u32 << n) - 1;
+
+        if table.iter().all(|value| !value) {
+            return GateFunction::Zero;
+        }
+
+        if table.iter().all(|value| *value) {
+            return GateFunction::One;
+        }
+
+        if n == 1 {
+            return if table[1] { GateFunction::Buf } else { GateFunction::Not };
+        }
+
+        let candidates: [(GateFunction, &dyn Fn(u32) -> bool); 6] = [
+            (GateFunction::And, &|m| m == all),
+            (GateFunction::Nand, &|m| m != all),
+            (GateFunction::Or, &|m| m != 0),
+            (GateFunction::Nor, &|m| m == 0),
+            (GateFunction::Xor, &|m| m.count_ones() % 2 == 1),
+            (GateFunction::Xnor, &|m| m.count_ones() % 2 == 0),
+        ];
+
+        if let Some((function, _)) = candidates.iter().find(|(_, function)| is(*function)) {
+            return *function;
+        }
+
+        if n == 3 {
+            for select in 0..3 {
+                let (a, b) = ((select + 1) % 3, (select + 2) % 3);
+
+                for (low, high) in [(a.min(b), a.max(b)), (a.max(b), a.min(b))] {
+                    if is(&|m| if bit(m, select) { bit(m, high) } else { bit(m, low) }) {
+                        return GateFunction::Mux { select, low, high };
+                    }
+                }
+            }
+        }
+
+        GateFunction::Cover
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A gate over `i0`, `i1`, ... from rows such as `1- 1`.
+    fn gate(rows: &[&str]) -> LogicGate {
+        let cover: Vec<(Vec<InputValue>, InputValue)> = rows.iter().map(|row| {
+            let (inputs, output) = row.rsplit_once(' ').unwrap_or(("", row));
+            let inputs = inputs.chars().map(|c| InputValue::try_from(c).unwrap()).collect();
+
+            (inputs, InputValue::try_from(output.chars().next().unwrap()).unwrap())
+        }).collect();
+        let n = cover.first().map_or(0, |(inputs, _)| inputs.len());
+
+        LogicGate::new((0..n).map(|i| format!("i{}", i)).collect(), "y".into(), cover)
+    }
+
+    #[test]
+    fn test_functions() {
+        assert_eq!(gate(&[]).function(), GateFunction::Zero);
+        assert_eq!(gate(&["1"]).function(), GateFunction::One);
+        assert_eq!(gate(&["0"]).function(), GateFunction::Zero);
+        assert_eq!(gate(&["1 1"]).function(), GateFunction::Buf);
+        assert_eq!(gate(&["0 1"]).function(), GateFunction::Not);
+        assert_eq!(gate(&["1 0"]).function(), GateFunction::Not);
+        assert_eq!(gate(&["11 1"]).function(), GateFunction::And);
+        assert_eq!(gate(&["111 0"]).function(), GateFunction::Nand);
+        assert_eq!(gate(&["-1- 1", "1-- 1", "--1 1"]).function(), GateFunction::Or);
+        assert_eq!(gate(&["00 1"]).function(), GateFunction::Nor);
+        assert_eq!(gate(&["1- 0", "-1 0"]).function(), GateFunction::Nor);
+        assert_eq!(gate(&["10 1", "01 1"]).function(), GateFunction::Xor);
+        assert_eq!(gate(&["100 1", "010 1", "001 1", "111 1"]).function(), GateFunction::Xor);
+        assert_eq!(gate(&["11 1", "00 1"]).function(), GateFunction::Xnor);
+        assert_eq!(gate(&["10 0", "01 0"]).function(), GateFunction::Xnor);
+        // $_MUX_: Y = S ? B : A, over A B S.
+        assert_eq!(gate(&["1-0 1", "-11 1"]).function(), GateFunction::Mux { select: 2, low: 0, high: 1 });
+        assert_eq!(gate(&["01- 1", "1-1 1"]).function(), GateFunction::Mux { select: 0, low: 1, high: 2 });
+        // Redundant rows and a tautology.
+        assert_eq!(gate(&["11 1", "1- 1", "-1 1"]).function(), GateFunction::Or);
+        assert_eq!(gate(&["0 1", "1 1"]).function(), GateFunction::One);
+    }
+
+    #[test]
+    fn test_covers() {
+        assert_eq!(gate(&["10 1"]).function(), GateFunction::Cover);
+        assert_eq!(gate(&["1- 1"]).function(), GateFunction::Cover);
+        assert_eq!(gate(&["11- 1", "1-1 1", "-11 1"]).function(), GateFunction::Cover);
+        assert_eq!(gate(&["11 1", "1 1"]).function(), GateFunction::Cover);
+    }
+}
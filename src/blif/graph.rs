@@ -0,0 +1,223 @@
+use super::Model;
+
+use std::collections::{HashMap, VecDeque};
+
+/// An element of a `Model` driving nets: `.names` gates and `.subckt`
+/// instances, by index in `Model::gates` and `Model::subckts`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Node {
+    Gate(usize),
+    Subckt(usize),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct CombinationalLoop {
+    pub model: String,
+    /// Nets along the loop, each one driving the next and the last driving the first.
+    pub nets: Vec<String>,
+}
+
+impl std::fmt::Display for CombinationalLoop {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "combinational loop in model `{}`: ", self.model)?;
+
+        for net in &self.nets {
+            write!(f, "{} -> ", net)?;
+        }
+
+        write!(f, "{}", self.nets.first().map_or("", |net| net.as_str()))
+    }
+}
+
+impl std::error::Error for CombinationalLoop {}
+
+/// Net-level dependencies between the nodes of a `Model`.
+///
+/// Instances are seen as a single node whose outputs depend on all of its
+/// inputs, so a path through two independent ports may be reported as a loop.
+pub struct DependencyGraph {
+    model: String,
+    nodes: Vec<Node>,
+    inputs: Vec<Vec<String>>,
+    outputs: Vec<Vec<String>>,
+    /// Every node driving each net: tri-state buses have several.
+    drivers: HashMap<String, Vec<usize>>,
+}
+
+impl DependencyGraph {
+    pub fn new(model: &Model) -> Self {
+        let mut nodes = Vec::new();
+        let mut inputs = Vec::new();
+        let mut outputs = Vec::new();
+
+        for (i, gate) in model.gates.iter().enumerate() {
+            nodes.push(Node::Gate(i));
+            inputs.push(gate.inputs.clone());
+            outputs.push(vec![gate.output.clone()]);
+        }
+
+        for (i, subckt) in model.subckts.iter().enumerate() {
+            nodes.push(Node::Subckt(i));
+            inputs.push(subckt.input_nets());
+            outputs.push(subckt.output_nets());
+        }
+
+        let mut drivers = HashMap::new();
+        for (node, nets) in outputs.iter().enumerate() {
+            for net in nets {
+                drivers.entry(net.clone()).or_insert_with(Vec::new).push(node);
+            }
+        }
+
+        Self { model: model.name.clone(), nodes, inputs, outputs, drivers }
+    }
+
+    /// Nodes driving the inputs of `node`. Primary inputs and latch outputs have no driver.
+    fn predecessors(&self, node: usize) -> impl Iterator<Item=usize> + '_ {
+        self.inputs[node].iter().filter_map(|net| self.drivers.get(net)).flatten().copied()
+    }
+
+    /// Every node after the ones driving its inputs, keeping file order where possible.
+    pub fn topological_order(&self) -> Result<Vec<Node>, CombinationalLoop> {
+        let mut fanout = vec![Vec::new(); self.nodes.len()];
+        let mut pending = vec![0; self.nodes.len()];
+
+        for (node, count) in pending.iter_mut().enumerate() {
+            for predecessor in self.predecessors(node) {
+                fanout[predecessor].push(node);
+                *count += 1;
+            }
+        }
+
+        let mut ready: VecDeque<usize> = (0..self.nodes.len()).filter(|&node| pending[node] == 0).collect();
+        let mut order = Vec::with_capacity(self.nodes.len());
+
+        while let Some(node) = ready.pop_front() {
+            order.push(self.nodes[node]);
+
+            for &next in &fanout[node] {
+                pending[next] -= 1;
+                if pending[next] == 0 {
+                    ready.push_back(next);
+                }
+            }
+        }
+
+        if order.len() == self.nodes.len() {
+            Ok(order)
+        } else {
+            let start = (0..self.nodes.len()).find(|&node| pending[node] > 0).unwrap();
+            Err(self.find_loop(start, &pending))
+        }
+    }
+
+    /// Walk back through unsorted predecessors from `start` until a node repeats.
+    fn find_loop(&self, start: usize, pending: &[usize]) -> CombinationalLoop {
+        let mut path = vec![start];
+        let mut node = start;
+
+        loop {
+            // An unsorted node always has at least one unsorted predecessor.
+            let predecessor = self.predecessors(node).find(|&p| pending[p] > 0).unwrap();
+
+            if let Some(position) = path.iter().position(|&n| n == predecessor) {
+                let mut cycle = path.split_off(position);
+                cycle.reverse();
+                cycle.rotate_right(1);
+
+                // Name each node of the loop by the net it drives into the next one.
+                let nets = cycle.iter().zip(cycle.iter().cycle().skip(1)).map(|(&n, &next)| {
+                    self.outputs[n].iter()
+                        .find(|net| self.inputs[next].contains(net))
+                        .unwrap()
+                        .clone()
+                }).collect();
+                return CombinationalLoop { model: self.model.clone(), nets };
+            }
+
+            path.push(predecessor);
+            node = predecessor;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blif::*;
+
+    fn buffer(input: &str, output: &str) -> LogicGate {
+        LogicGate::new(
+            vec![input.into()],
+            output.into(),
+            vec![
+                (vec![InputValue::Uncomplemented], InputValue::Uncomplemented)
+            ]
+        )
+    }
+
+    #[test]
+    fn test_topological_order() {
+        let model = Model::new(
+            "chain".into(),
+            vec!["a".into()],
+            vec!["c".into()],
+            vec![buffer("b", "c"), buffer("a", "b")],
+        );
+
+        let order = DependencyGraph::new(&model).topological_order();
+
+        assert_eq!(order, Ok(vec![Node::Gate(1), Node::Gate(0)]));
+    }
+
+    #[test]
+    fn test_topological_order_keeps_file_order() {
+        let model = Model::new(
+            "parallel".into(),
+            vec!["a".into()],
+            vec!["b".into(), "c".into()],
+            vec![buffer("a", "b"), buffer("a", "c")],
+        );
+
+        let order = DependencyGraph::new(&model).topological_order();
+
+        assert_eq!(order, Ok(vec![Node::Gate(0), Node::Gate(1)]));
+    }
+
+    #[test]
+    fn test_combinational_loop() {
+        let model = Model::new(
+            "ring".into(),
+            vec!["a".into()],
+            vec!["d".into()],
+            vec![buffer("a", "d"), buffer("b", "c"), buffer("c", "e"), buffer("e", "b")],
+        );
+
+        let order = DependencyGraph::new(&model).topological_order();
+
+        let expected = CombinationalLoop {
+            model: "ring".into(),
+            nets: vec!["c".into(), "e".into(), "b".into()],
+        };
+
+        assert_eq!(order, Err(expected));
+        assert_eq!(
+            order.unwrap_err().to_string(),
+            "combinational loop in model `ring`: c -> e -> b -> c"
+        );
+    }
+
+    #[test]
+    fn test_topological_order_multiple_drivers() {
+        let model = Model::new(
+            "bus".into(),
+            vec!["a".into(), "b".into()],
+            vec!["c".into()],
+            vec![buffer("a", "bus"), buffer("bus", "c"), buffer("b", "bus")],
+        );
+
+        let order = DependencyGraph::new(&model).topological_order();
+
+        assert_eq!(order, Ok(vec![Node::Gate(0), Node::Gate(2), Node::Gate(1)]));
+    }
+}
@@ -0,0 +1,242 @@
+use crate::simulation::{SignalState, Signal, Signals};
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum LatchType {
+    /// "fe"
+    FallingEdge,
+    /// "re"
+    RisingEdge,
+    /// "ah"
+    ActiveHigh,
+    /// "al"
+    ActiveLow,
+    /// "as"
+    Asynchronous,
+}
+
+impl std::convert::TryFrom<&str> for LatchType {
+    type Error = &'static str;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "fe" => Ok(Self::FallingEdge),
+            "re" => Ok(Self::RisingEdge),
+            "ah" => Ok(Self::ActiveHigh),
+            "al" => Ok(Self::ActiveLow),
+            "as" => Ok(Self::Asynchronous),
+            _ => Err("expected [fe|re|ah|al|as] to create a LatchType"),
+        }
+    }
+}
+
+impl std::fmt::Display for LatchType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::FallingEdge => write!(f, "fe"),
+            Self::RisingEdge => write!(f, "re"),
+            Self::ActiveHigh => write!(f, "ah"),
+            Self::ActiveLow => write!(f, "al"),
+            Self::Asynchronous => write!(f, "as"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum LatchInit {
+    /// "0"
+    Low,
+    /// "1"
+    High,
+    /// "2"
+    DontCare,
+    /// "3"
+    Unknown,
+}
+
+impl std::convert::TryFrom<&str> for LatchInit {
+    type Error = &'static str;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "0" => Ok(Self::Low),
+            "1" => Ok(Self::High),
+            "2" => Ok(Self::DontCare),
+            "3" => Ok(Self::Unknown),
+            _ => Err("expected [0|1|2|3] to create a LatchInit"),
+        }
+    }
+}
+
+impl std::fmt::Display for LatchInit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Low => write!(f, "0"),
+            Self::High => write!(f, "1"),
+            Self::DontCare => write!(f, "2"),
+            Self::Unknown => write!(f, "3"),
+        }
+    }
+}
+
+impl From<LatchInit> for SignalState {
+    /// Don't-care initial values are simulated as low.
+    fn from(init: LatchInit) -> Self {
+        match init {
+            LatchInit::Low | LatchInit::DontCare => SignalState::Low,
+            LatchInit::High => SignalState::High,
+            LatchInit::Unknown => SignalState::Unknown,
+        }
+    }
+}
+
+/// A `.latch input output [type control] [init-val]` element.
+///
+/// A latch without `type control` (or controlled by `NIL`) is clocked by the
+/// implicit global clock of the model.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Latch {
+    pub input: String,
+    pub output: String,
+    pub control: Option<(LatchType, String)>,
+    pub init: LatchInit,
+}
+
+impl Latch {
+    pub fn new(input: String, output: String, control: Option<(LatchType, String)>, init: LatchInit) -> Self {
+        Self { input, output, control, init }
+    }
+
+    pub fn initial_state(&self) -> Signal {
+        let mut signal = Signal::new(&self.output);
+        signal.set(self.init.into());
+
+        signal
+    }
+
+    /// Whether the latch samples its input, given the settled nets before
+    /// (`previous`) and after (`current`) the last evaluation: `High` when it
+    /// does, `Low` when it holds and `Unknown` when the control is unknown.
+    ///
+    /// `global_clock` is the edge of the implicit clock driving uncontrolled latches.
+    pub fn is_enabled(&self, previous: &Signals, current: &Signals, global_clock: bool) -> SignalState {
+        let global = if global_clock { SignalState::High } else { SignalState::Low };
+
+        let (kind, control) = match &self.control {
+            Some((_, control)) if control == "NIL" => return global,
+            Some((kind, control)) => (kind, control),
+            None => return global,
+        };
+
+        let before = previous.get(control);
+        let now = current.get(control);
+
+        // An unknown control may or may not have the level the latch is waiting for.
+        let level = |state: SignalState, expected: SignalState| match state {
+            state if state == expected => SignalState::High,
+            state if state.is_known() => SignalState::Low,
+            _ => SignalState::Unknown,
+        };
+        let edge = |from: SignalState, to: SignalState| {
+            match (level(before, from), level(now, to)) {
+                (SignalState::Low, _) | (_, SignalState::Low) => SignalState::Low,
+                (SignalState::High, SignalState::High) => SignalState::High,
+                _ => SignalState::Unknown,
+            }
+        };
+
+        match kind {
+            LatchType::RisingEdge => edge(SignalState::Low, SignalState::High),
+            LatchType::FallingEdge => edge(SignalState::High, SignalState::Low),
+            LatchType::ActiveHigh => level(now, SignalState::High),
+            LatchType::ActiveLow => level(now, SignalState::Low),
+            LatchType::Asynchronous => SignalState::High,
+        }
+    }
+
+    pub fn next_state(&self, state: &Signals, previous: &Signals, current: &Signals, global_clock: bool) -> Signal {
+        let mut signal = Signal::new(&self.output);
+
+        let held = state.get(&self.output);
+        let sampled = current.get(&self.input);
+
+        signal.set(match self.is_enabled(previous, current, global_clock) {
+            SignalState::High => sampled,
+            SignalState::Low => held,
+            // Maybe sampled: only known if both outcomes agree.
+            _ if held == sampled && held.is_known() => held,
+            _ => SignalState::Unknown,
+        });
+
+        signal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::*;
+
+    #[test]
+    fn test_latch_rising_edge() {
+        let latch = Latch::new("d".into(), "q".into(), Some((LatchType::RisingEdge, "clk".into())), LatchInit::Low);
+
+        let state = SignalsBuilder::new().add_signal("q", SignalState::Low).build();
+        let low = SignalsBuilder::new()
+            .add_signal("d", SignalState::High)
+            .add_signal("clk", SignalState::Low)
+            .build();
+        let high = SignalsBuilder::new()
+            .add_signal("d", SignalState::High)
+            .add_signal("clk", SignalState::High)
+            .build();
+
+        assert_eq!(latch.next_state(&state, &low, &low, false).state(), SignalState::Low);
+        assert_eq!(latch.next_state(&state, &high, &high, false).state(), SignalState::Low);
+        assert_eq!(latch.next_state(&state, &low, &high, false).state(), SignalState::High);
+        assert_eq!(latch.next_state(&state, &high, &low, false).state(), SignalState::Low);
+    }
+
+    #[test]
+    fn test_latch_active_low() {
+        let latch = Latch::new("d".into(), "q".into(), Some((LatchType::ActiveLow, "en".into())), LatchInit::Unknown);
+
+        let state = Signals::new();
+        let enabled = SignalsBuilder::new()
+            .add_signal("d", SignalState::High)
+            .add_signal("en", SignalState::Low)
+            .build();
+
+        assert_eq!(latch.next_state(&state, &enabled, &enabled, false).state(), SignalState::High);
+    }
+
+    #[test]
+    fn test_latch_global_clock() {
+        let latch = Latch::new("d".into(), "q".into(), None, LatchInit::High);
+
+        let state = Signals::new();
+        let nets = SignalsBuilder::new().add_signal("d", SignalState::Low).build();
+
+        assert_eq!(latch.initial_state().state(), SignalState::High);
+        assert_eq!(latch.next_state(&state, &nets, &nets, false).state(), SignalState::Unknown);
+        assert_eq!(latch.next_state(&state, &nets, &nets, true).state(), SignalState::Low);
+    }
+
+    #[test]
+    fn test_latch_unknown_clock() {
+        let latch = Latch::new("d".into(), "q".into(), Some((LatchType::RisingEdge, "clk".into())), LatchInit::Low);
+
+        let state = SignalsBuilder::new().add_signal("q", SignalState::Low).build();
+        let previous = SignalsBuilder::new().add_signal("clk", SignalState::Low).build();
+        let same = SignalsBuilder::new()
+            .add_signal("d", SignalState::Low)
+            .add_signal("clk", SignalState::Unknown)
+            .build();
+        let different = SignalsBuilder::new()
+            .add_signal("d", SignalState::High)
+            .add_signal("clk", SignalState::Unknown)
+            .build();
+
+        assert_eq!(latch.next_state(&state, &previous, &same, false).state(), SignalState::Low);
+        assert_eq!(latch.next_state(&state, &previous, &different, false).state(), SignalState::Unknown);
+    }
+}
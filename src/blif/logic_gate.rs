@@ -23,6 +23,16 @@ impl std::convert::TryFrom<char> for InputValue {
     }
 }
 
+impl std::fmt::Display for InputValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Uncomplemented => write!(f, "1"),
+            Self::Complemented => write!(f, "0"),
+            Self::NotUsed => write!(f, "-"),
+        }
+    }
+}
+
 impl std::convert::TryFrom<&char> for InputValue {
     type Error = &'static str;
 
@@ -44,17 +54,94 @@ impl LogicGate {
     pub fn new(inputs: Vec<String>, output: String, single_output_cover: Vec<(InputVariables, InputValue)>) -> Self {
         Self { inputs, output, single_output_cover }
     }
-}
 
-fn apply(input_values: &Vec<InputValue>, signals: &Vec<SignalState>) -> bool {
-    for (i, s) in std::iter::zip(input_values, signals) {
-        if i == &InputValue::Complemented && s == &SignalState::High ||
-            i == &InputValue::Uncomplemented && s == &SignalState::Low {
-            return false;
+    /// Whether the rows of the cover list the ON-set (`Uncomplemented`, rows
+    /// ending in `1`) or the OFF-set (`Complemented`, rows ending in `0`).
+    ///
+    /// Decided by the first row; an empty cover is an empty ON-set, i.e. the
+    /// constant 0 of `.names $false`.
+    pub fn polarity(&self) -> InputValue {
+        self.single_output_cover
+            .first()
+            .map_or(InputValue::Uncomplemented, |(_, output)| *output)
+    }
+
+    /// Value of the gate for the states of its inputs, in `inputs` order.
+    ///
+    /// Unknown and high-impedance inputs only make the output unknown when it
+    /// actually depends on them: `a | !a` stays high whatever `a` is.
+    ///
+    /// Rows whose output does not match the `polarity` of the cover are ignored.
+    pub fn evaluate(&self, states: &[SignalState]) -> SignalState {
+        let polarity = self.polarity();
+
+        // Rows not ruled out by a known input, with the known inputs replaced by `-`.
+        let mut undecided = Vec::new();
+
+        for (row_inputs, row_output) in &self.single_output_cover {
+            if row_output != &polarity {
+                continue;
+            }
+
+            let mut cube = row_inputs.clone();
+            let mut matches = true;
+
+            for (value, state) in cube.iter_mut().zip(states) {
+                match (*value, state) {
+                    (InputValue::Uncomplemented, SignalState::Low) | (InputValue::Complemented, SignalState::High) => {
+                        matches = false;
+                        break;
+                    }
+                    (_, SignalState::High) | (_, SignalState::Low) => *value = InputValue::NotUsed,
+                    _ => (),
+                }
+            }
+
+            if matches {
+                undecided.push(cube);
+            }
+        }
+
+        let (matched, unmatched) = match polarity {
+            InputValue::Complemented => (SignalState::Low, SignalState::High),
+            _ => (SignalState::High, SignalState::Low),
+        };
+
+        if undecided.is_empty() {
+            unmatched
+        } else if is_tautology(&undecided) {
+            matched
+        } else {
+            SignalState::Unknown
         }
     }
+}
+
+/// Whether `cubes` cover every assignment of their variables.
+///
+/// Shannon expansion on the first variable some cube depends on.
+pub fn is_tautology(cubes: &[InputVariables]) -> bool {
+    if cubes.iter().any(|cube| cube.iter().all(|value| value == &InputValue::NotUsed)) {
+        return true;
+    }
 
-    return true;
+    let var = match cubes.iter().find_map(|cube| cube.iter().position(|value| value != &InputValue::NotUsed)) {
+        Some(var) => var,
+        None => return false,
+    };
+
+    [InputValue::Complemented, InputValue::Uncomplemented].iter().all(|literal| {
+        let cofactor: Vec<InputVariables> = cubes.iter()
+            .filter(|cube| cube[var] == InputValue::NotUsed || &cube[var] == literal)
+            .map(|cube| {
+                let mut cube = cube.clone();
+                cube[var] = InputValue::NotUsed;
+                cube
+            })
+            .collect();
+
+        is_tautology(&cofactor)
+    })
 }
 
 use std::collections::HashSet;
@@ -78,15 +165,7 @@ impl Simulable for LogicGate {
         });
 
         let mut output = Signal::new(&self.output);
-        output.set_low();
-
-        for (row_inputs, row_output) in &self.single_output_cover {
-            if apply(&row_inputs, &bound_signals) {
-                if row_output == &InputValue::Uncomplemented  {
-                    output.set_high();
-                }
-            }
-        }
+        output.set(self.evaluate(&bound_signals));
 
         let mut outputs = Signals::new();
         outputs.add_signal(output);
@@ -183,4 +262,88 @@ mod tests {
         assert_eq!(simulation.get("y"), SignalState::High);
     }
 
+    #[test]
+    fn test_and_gate_x0() {
+        let simulation = AND_GATE.stim(
+            SignalsBuilder::new()
+                .add_signal("a", SignalState::Unknown)
+                .add_signal("b", SignalState::Low)
+                .build()
+        );
+        assert_eq!(simulation.get("y"), SignalState::Low);
+    }
+
+    #[test]
+    fn test_and_gate_x1() {
+        let simulation = AND_GATE.stim(
+            SignalsBuilder::new()
+                .add_signal("a", SignalState::Unknown)
+                .add_signal("b", SignalState::High)
+                .build()
+        );
+        assert_eq!(simulation.get("y"), SignalState::Unknown);
+    }
+
+    #[test]
+    fn test_not_z() {
+        let simulation = NOT_GATE.stim(
+            SignalsBuilder::new()
+                .add_signal("a", SignalState::HighImpedance)
+                .build()
+        );
+        assert_eq!(simulation.get("y"), SignalState::Unknown);
+    }
+
+    #[test]
+    fn test_x_masked_by_redundant_cover() {
+        // a | !a, split over three rows.
+        let gate = LogicGate::new(
+            vec!["a".into(), "b".into()],
+            "y".into(),
+            vec![
+                (vec![InputValue::Complemented, InputValue::Uncomplemented], InputValue::Uncomplemented),
+                (vec![InputValue::Uncomplemented, InputValue::NotUsed], InputValue::Uncomplemented),
+                (vec![InputValue::Complemented, InputValue::Complemented], InputValue::Uncomplemented),
+            ]
+        );
+
+        assert_eq!(gate.evaluate(&[SignalState::Unknown, SignalState::Unknown]), SignalState::High);
+
+        // !a b | a only masks `a` when `b` is high.
+        let gate = LogicGate::new(gate.inputs.clone(), gate.output.clone(), gate.single_output_cover[..2].to_vec());
+
+        assert_eq!(gate.evaluate(&[SignalState::Unknown, SignalState::High]), SignalState::High);
+        assert_eq!(gate.evaluate(&[SignalState::Unknown, SignalState::Low]), SignalState::Unknown);
+    }
+
+    #[test]
+    fn test_off_set_nand() {
+        let nand = LogicGate::new(
+            vec!["a".into(), "b".into()],
+            "y".into(),
+            vec![
+                (vec![InputValue::Uncomplemented, InputValue::Uncomplemented], InputValue::Complemented)
+            ]
+        );
+
+        assert_eq!(nand.evaluate(&[SignalState::Low, SignalState::Low]), SignalState::High);
+        assert_eq!(nand.evaluate(&[SignalState::High, SignalState::Low]), SignalState::High);
+        assert_eq!(nand.evaluate(&[SignalState::High, SignalState::High]), SignalState::Low);
+        assert_eq!(nand.evaluate(&[SignalState::Unknown, SignalState::Low]), SignalState::High);
+        assert_eq!(nand.evaluate(&[SignalState::Unknown, SignalState::High]), SignalState::Unknown);
+    }
+
+    #[test]
+    fn test_constants() {
+        let constant = |cover| LogicGate::new(vec![], "y".into(), cover).evaluate(&[]);
+
+        // .names $false
+        assert_eq!(constant(vec![]), SignalState::Low);
+        // .names $true
+        // 1
+        assert_eq!(constant(vec![(vec![], InputValue::Uncomplemented)]), SignalState::High);
+        // .names $false
+        // 0
+        assert_eq!(constant(vec![(vec![], InputValue::Complemented)]), SignalState::Low);
+    }
 }
@@ -0,0 +1,241 @@
+use super::{is_tautology, Blif, InputValue, LogicGate, Model};
+
+type Cube = Vec<InputValue>;
+
+/// A minimal sum of products of the function covered by `cubes`.
+///
+/// Espresso-style: every cube is expanded into a prime implicant by
+/// dropping each literal the function does not need, largest cubes first,
+/// then the cubes covered by the others are removed, smallest cubes first.
+/// The result is prime and irredundant, though not always the smallest such
+/// cover. A tautology becomes a single cube of don't-cares. Containment is
+/// decided with `is_tautology`, so there is no limit on the number of inputs.
+pub fn minimise_cover(cubes: &[Cube]) -> Vec<Cube> {
+    let width = cubes.first().map_or(0, Vec::len);
+
+    if is_tautology(cubes) {
+        return vec![vec![InputValue::NotUsed; width]];
+    }
+
+    let mut order = cubes.to_vec();
+    order.sort_by_key(literals);
+
+    let mut primes: Vec<Cube> = Vec::new();
+    for mut cube in order {
+        if primes.iter().any(|prime| contains(prime, &cube)) {
+            continue;
+        }
+
+        for i in 0..width {
+            if cube[i] != InputValue::NotUsed {
+                let mut raised = cube.clone();
+                raised[i] = InputValue::NotUsed;
+
+                if covers(cubes, &raised) {
+                    cube = raised;
+                }
+            }
+        }
+
+        primes.retain(|prime| !contains(&cube, prime));
+        primes.push(cube);
+    }
+
+    primes.sort_by_key(|cube| std::cmp::Reverse(literals(cube)));
+
+    let mut i = 0;
+    while i < primes.len() {
+        let others: Vec<Cube> = primes.iter().enumerate().filter(|(j, _)| *j != i).map(|(_, cube)| cube.clone()).collect();
+
+        if covers(&others, &primes[i]) {
+            primes.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+
+    primes.sort_by_key(literals);
+    primes
+}
+
+fn literals(cube: &Cube) -> usize {
+    cube.iter().filter(|value| **value != InputValue::NotUsed).count()
+}
+
+/// Whether every assignment matching `inner` matches `outer`.
+fn contains(outer: &Cube, inner: &Cube) -> bool {
+    std::iter::zip(outer, inner).all(|(outer, inner)| *outer == InputValue::NotUsed || outer == inner)
+}
+
+/// Whether `cube` lies within the union of `cover`: the cover restricted to
+/// the assignments matching `cube` is a tautology.
+fn covers(cover: &[Cube], cube: &Cube) -> bool {
+    let cofactor: Vec<Cube> = cover.iter().filter_map(|other| {
+        std::iter::zip(other, cube).map(|(value, literal)| match (value, literal) {
+            (InputValue::Uncomplemented, InputValue::Complemented) | (InputValue::Complemented, InputValue::Uncomplemented) => None,
+            (_, InputValue::NotUsed) => Some(*value),
+            _ => Some(InputValue::NotUsed),
+        }).collect()
+    }).collect();
+
+    is_tautology(&cofactor)
+}
+
+impl LogicGate {
+    /// The same gate with its cover replaced by `minimise_cover` of its rows,
+    /// keeping their polarity. Rows of the other polarity are dropped, being
+    /// ignored anyway, and gates with rows of the wrong width are left as
+    /// they are.
+    pub fn minimise(&self) -> Self {
+        if self.single_output_cover.iter().any(|(inputs, _)| inputs.len() != self.inputs.len()) {
+            return self.clone();
+        }
+
+        let polarity = self.polarity();
+        let cubes: Vec<Cube> = self.single_output_cover.iter()
+            .filter(|(_, output)| *output == polarity)
+            .map(|(inputs, _)| inputs.clone())
+            .collect();
+
+        let cover = minimise_cover(&cubes).into_iter().map(|cube| (cube, polarity)).collect();
+
+        Self::new(self.inputs.clone(), self.output.clone(), cover)
+    }
+}
+
+impl Model {
+    /// Minimise every gate of the model and of the models it instantiates.
+    pub fn minimise(&mut self) {
+        for gate in self.gates.iter_mut() {
+            *gate = gate.minimise();
+        }
+
+        for subckt in self.subckts.iter_mut() {
+            if let Some(definition) = subckt.definition.as_mut() {
+                definition.minimise();
+            }
+        }
+    }
+}
+
+impl Blif {
+    /// A copy of the file with every gate minimised.
+    pub fn minimise(&self) -> Self {
+        let models = self.models().iter().map(|model| {
+            let mut model = model.clone();
+            model.minimise();
+
+            model
+        }).collect();
+
+        Self::new(models)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+    use crate::simulation::SignalState;
+
+    fn cubes(rows: &[&str]) -> Vec<Cube> {
+        rows.iter().map(|row| row.chars().map(|c| InputValue::try_from(c).unwrap()).collect()).collect()
+    }
+
+    #[test]
+    fn test_tautology() {
+        // The rows of `test_parse_logic_gate`.
+        assert_eq!(minimise_cover(&cubes(&["0-", "1-", "--", "01"])), cubes(&["--"]));
+        assert_eq!(minimise_cover(&cubes(&["0-", "1-"])), cubes(&["--"]));
+        assert_eq!(minimise_cover(&[]), Vec::<Cube>::new());
+    }
+
+    #[test]
+    fn test_minimise_cover() {
+        // Adjacent minterms merge.
+        assert_eq!(minimise_cover(&cubes(&["110", "111", "011"])), cubes(&["11-", "-11"]));
+        // The consensus term `bc` of `ab + !ac` is redundant.
+        assert_eq!(minimise_cover(&cubes(&["11-", "0-1", "-11"])), cubes(&["11-", "0-1"]));
+        // XOR has nothing to merge.
+        assert_eq!(minimise_cover(&cubes(&["10", "01"])), cubes(&["10", "01"]));
+        // Contained cubes are dropped.
+        assert_eq!(minimise_cover(&cubes(&["1-1", "101", "1--"])), cubes(&["1--"]));
+    }
+
+    #[test]
+    fn test_off_set() {
+        let gate = LogicGate::new(vec!["a".into(), "b".into()], "y".into(), vec![
+            (cubes(&["11"])[0].clone(), InputValue::Complemented),
+            (cubes(&["10"])[0].clone(), InputValue::Complemented),
+        ]);
+
+        assert_eq!(gate.minimise().single_output_cover, vec![(cubes(&["1-"])[0].clone(), InputValue::Complemented)]);
+    }
+
+    #[test]
+    fn test_malformed_cover() {
+        // Only a gate built in code can have a row of the wrong width.
+        let gate = LogicGate::new(vec!["a".into(), "b".into()], "y".into(), vec![
+            (cubes(&["1"])[0].clone(), InputValue::Uncomplemented),
+            (cubes(&["11"])[0].clone(), InputValue::Uncomplemented),
+        ]);
+
+        assert_eq!(gate.minimise(), gate);
+    }
+
+    #[test]
+    fn test_random_covers_keep_their_function() {
+        let mut rng = Rng::new(0x2545_f491_4f6c_dd1d);
+        let values = [InputValue::Uncomplemented, InputValue::Complemented, InputValue::NotUsed];
+        let states = [SignalState::Low, SignalState::High, SignalState::Unknown, SignalState::HighImpedance];
+
+        for _ in 0..200 {
+            let n = 1 + rng.below(4);
+            let polarity = if rng.below(2) == 0 { InputValue::Uncomplemented } else { InputValue::Complemented };
+            let cover = (0..1 + rng.below(6))
+                .map(|_| ((0..n).map(|_| values[rng.below(3)]).collect(), polarity))
+                .collect();
+
+            let gate = LogicGate::new((0..n).map(|i| format!("i{}", i)).collect(), "y".into(), cover);
+            let minimised = gate.minimise();
+
+            assert!(minimised.single_output_cover.len() <= gate.single_output_cover.len());
+
+            // Unknown inputs included, `evaluate` being exact.
+            for combination in 0..4usize.pow(n as u32) {
+                let inputs: Vec<SignalState> = (0..n).map(|i| states[combination / 4usize.pow(i as u32) % 4]).collect();
+
+                assert_eq!(minimised.evaluate(&inputs), gate.evaluate(&inputs), "{:?} of\n{}as\n{}", inputs, gate, minimised);
+            }
+        }
+    }
+
+    #[test]
+    fn test_blif() {
+        let blif = crate::blif::parse(concat!(
+            ".model top\n",
+            ".inputs a b\n",
+            ".outputs y\n",
+            ".subckt redundant a=a b=b y=y\n",
+            ".end\n",
+            "\n",
+            ".model redundant\n",
+            ".inputs a b\n",
+            ".outputs y\n",
+            ".names a b y\n",
+            "0- 1\n",
+            "1- 1\n",
+            "-- 1\n",
+            "01 1\n",
+            ".end\n",
+        )).unwrap();
+
+        let minimised = blif.minimise();
+        let expected = vec![(cubes(&["--"])[0].clone(), InputValue::Uncomplemented)];
+
+        assert_eq!(minimised.model("redundant").unwrap().gates[0].single_output_cover, expected);
+        // Instances are resolved to the minimised models.
+        let definition = minimised.top().unwrap().subckts[0].definition.as_ref().unwrap();
+        assert_eq!(definition.gates[0].single_output_cover, expected);
+    }
+}
@@ -1,13 +1,36 @@
 mod logic_gate;
-pub use logic_gate::LogicGate;
-#[cfg(test)]
-pub use logic_gate::InputValue;
+pub use logic_gate::{LogicGate, InputValue, is_tautology};
+
+mod gate_function;
+pub use gate_function::{GateFunction, MAX_CLASSIFIED_INPUTS};
+
+mod latch;
+pub use latch::{Latch, LatchType, LatchInit};
+
+mod subckt;
+pub use subckt::Subckt;
+
+mod graph;
+pub use graph::CombinationalLoop;
 
 mod model;
 pub use model::Model;
 
+#[allow(clippy::module_inception)]
 mod blif;
 pub use blif::Blif;
 
+mod error;
+pub use error::BlifError;
+
 mod parser;
 pub use parser::parse;
+
+mod writer;
+pub use writer::write;
+
+mod minimise;
+pub use minimise::minimise_cover;
+
+mod sweep;
+pub use sweep::Sweep;
@@ -1,5 +1,7 @@
-use super::LogicGate;
-use crate::simulation::Simulable;
+use super::{LogicGate, Latch, Subckt};
+use super::graph::{CombinationalLoop, DependencyGraph, Node};
+use super::subckt::Instance;
+use crate::simulation::{Simulable, Sequential, SignalState, Signals, SignalsBuilder};
 
 use std::collections::HashSet;
 
@@ -10,26 +12,162 @@ pub struct Model {
     pub outputs: Vec<String>,
 
     pub gates: Vec<LogicGate>,
+    pub subckts: Vec<Subckt>,
+    pub latches: Vec<Latch>,
 }
 
 impl Model {
     pub fn new(name: String, inputs: Vec<String>, outputs: Vec<String>, gates: Vec<LogicGate>) -> Self {
-        Self { name, inputs, outputs, gates }
+        Self { name, inputs, outputs, gates, subckts: Vec::new(), latches: Vec::new() }
+    }
+
+    /// Every net of the model, primary inputs and outputs first, then in
+    /// order of appearance.
+    pub fn nets(&self) -> Vec<String> {
+        let mut seen = HashSet::new();
+
+        let gates = self.gates.iter().flat_map(|gate| gate.inputs.iter().chain(std::iter::once(&gate.output)));
+        let latches = self.latches.iter().flat_map(|latch| {
+            let control = latch.control.as_ref().map(|(_, control)| control).filter(|control| *control != "NIL");
+
+            std::iter::once(&latch.input).chain(control).chain(std::iter::once(&latch.output))
+        });
+        let subckts = self.subckts.iter().flat_map(|subckt| subckt.connections.iter().map(|(_, actual)| actual));
+
+        self.inputs.iter()
+            .chain(self.outputs.iter())
+            .chain(gates)
+            .chain(latches)
+            .chain(subckts)
+            .filter(|net| seen.insert(net.as_str()))
+            .cloned()
+            .collect()
+    }
+
+    /// Gates and instances, each one after the ones driving its inputs.
+    pub fn evaluation_order(&self) -> Result<Vec<Node>, CombinationalLoop> {
+        DependencyGraph::new(self).topological_order()
+    }
+
+    /// Look for combinational loops in the model and every instantiated model.
+    pub fn check(&self) -> Result<(), CombinationalLoop> {
+        self.evaluation_order()?;
+
+        for subckt in &self.subckts {
+            if let Some(definition) = &subckt.definition {
+                definition.check()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Name of instance `i`: instances have no name in BLIF, so they are
+    /// named after their model and their position in the parent.
+    pub fn instance_name(&self, i: usize) -> String {
+        format!("{}_{}", self.subckts[i].model, i)
+    }
+
+    /// Nets holding the state of the model: the outputs of its latches, and
+    /// the state nets of each instance as `{instance}/{net}`.
+    pub fn state_nets(&self) -> Vec<String> {
+        let instances = self.subckts.iter().enumerate().flat_map(|(i, subckt)| {
+            let name = self.instance_name(i);
+            subckt.definition.iter().flat_map(|definition| definition.state_nets()).map(move |net| format!("{}/{}", name, net))
+        });
+
+        self.latches.iter().map(|latch| latch.output.clone()).chain(instances).collect()
+    }
+
+    pub fn try_stim(&self, inputs: Signals) -> Result<Signals, CombinationalLoop> {
+        self.check()?;
+
+        Ok(self.stim(inputs))
     }
 }
 
 impl Simulable for Model {
+    /// Primary inputs, plus the state nets fed back in.
     fn get_inputs(&self) -> HashSet<String> {
-        self.inputs.clone().into_iter().collect()
+        self.inputs.iter().cloned().chain(self.state_nets()).collect()
     }
 
+    /// On a combinational loop, the nets driven by the gates and instances
+    /// are all unknown: use `try_stim` to get the loop as an error.
     fn children(&self) -> Vec<Box<dyn Simulable>> {
-        self.gates.iter().map(|x| {
-            Box::new(x.clone()) as Box<dyn Simulable>
+        let order = match self.evaluation_order() {
+            Ok(order) => order,
+            Err(_) => {
+                let gates = self.gates.iter().map(|gate| gate.output.clone());
+                let subckts = self.subckts.iter().flat_map(|subckt| subckt.output_nets());
+
+                return vec![Box::new(Unresolved(gates.chain(subckts).collect()))];
+            }
+        };
+
+        order.into_iter().map(|node| match node {
+            Node::Gate(i) => Box::new(self.gates[i].clone()) as Box<dyn Simulable>,
+            Node::Subckt(i) => Box::new(Instance { name: self.instance_name(i), subckt: self.subckts[i].clone() }) as Box<dyn Simulable>,
         }).collect()
     }
 }
 
+/// Nets whose value cannot be computed, always unknown.
+struct Unresolved(Vec<String>);
+
+impl Simulable for Unresolved {
+    fn get_inputs(&self) -> HashSet<String> {
+        HashSet::new()
+    }
+
+    fn children(&self) -> Vec<Box<dyn Simulable>> {
+        Vec::new()
+    }
+
+    fn stim(&self, _: Signals) -> Signals {
+        self.0.iter().fold(SignalsBuilder::new(), |builder, net| builder.add_signal(net, SignalState::Unknown)).build()
+    }
+}
+
+/// The latches of `.subckt` instances hold their state per instance, under
+/// the nets `{instance}/{latch}` of `Model::state_nets`.
+impl Sequential for Model {
+    fn initial_state(&self) -> Signals {
+        let mut state = Signals::new();
+
+        for latch in &self.latches {
+            state.add_signal(latch.initial_state());
+        }
+
+        for (i, subckt) in self.subckts.iter().enumerate() {
+            if let Some(definition) = &subckt.definition {
+                state.update_with(definition.initial_state().in_scope(&self.instance_name(i)));
+            }
+        }
+
+        state
+    }
+
+    fn next_state(&self, state: &Signals, previous: &Signals, current: &Signals, global_clock: bool) -> Signals {
+        let mut next = Signals::new();
+
+        for latch in &self.latches {
+            next.add_signal(latch.next_state(state, previous, current, global_clock));
+        }
+
+        for (i, subckt) in self.subckts.iter().enumerate() {
+            if let Some(definition) = &subckt.definition {
+                let name = self.instance_name(i);
+                let (state, previous, current) = (state.scope(&name), previous.scope(&name), current.scope(&name));
+
+                next.update_with(definition.next_state(&state, &previous, &current, global_clock).in_scope(&name));
+            }
+        }
+
+        next
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::blif::*;
@@ -69,4 +207,33 @@ mod tests {
         assert_eq!(res.get("o_a"), SignalState::High);
         assert_eq!(res.get("o_b"), SignalState::Low);
     }
+
+    #[test]
+    fn test_model_loop() {
+        let ring = Model::new(
+            "ring".into(),
+            vec!["a".into()],
+            vec!["b".into()],
+            vec![
+                LogicGate::new(
+                    vec!["a".into(), "b".into()],
+                    "b".into(),
+                    vec![
+                        (vec![InputValue::Uncomplemented, InputValue::Complemented], InputValue::Uncomplemented)
+                    ]
+                ),
+            ]
+        );
+
+        let inputs = SignalsBuilder::new()
+            .add_signal("a", SignalState::High)
+            .build();
+
+        assert!(ring.try_stim(inputs.clone()).is_err());
+
+        // Files with a loop parse fine, and simulating them must not panic.
+        let res = ring.stim(inputs);
+        assert_eq!(res.get("a"), SignalState::High);
+        assert_eq!(res.get("b"), SignalState::Unknown);
+    }
 }
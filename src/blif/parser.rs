@@ -1,12 +1,21 @@
 use super::logic_gate::{InputValue, LogicGate};
 use super::Model;
+use super::Subckt;
+use super::{Latch, LatchType, LatchInit};
 use super::Blif;
+use super::BlifError;
+
+mod lexer;
+use lexer::Source;
 
 
 use nom::{
+    Err,
     IResult,
     error::{
+        ErrorKind,
         VerboseError,
+        VerboseErrorKind,
         context,
     },
     sequence::{
@@ -20,6 +29,7 @@ use nom::{
         many0,
         many1,
     },
+    branch::alt,
     bytes::complete::{
         take_while1,
     },
@@ -30,19 +40,26 @@ use nom::{
         one_of,
         char,
     },
-    combinator::opt,
+    combinator::{
+        opt,
+        map_opt,
+        cut,
+        peek,
+        verify,
+        all_consuming,
+    },
     bytes::complete::tag,
 };
 
 fn is_valid_name_char(c: char) -> bool {
-    is_alphanumeric(c as u8) || c == '_' || c == '.' || c == '$' || c == '/' || c == ':'
+    is_alphanumeric(c as u8) || c == '_' || c == '.' || c == '$' || c == '/' || c == ':' || c == '[' || c == ']'
 }
 
 fn parse_name(input: &str) -> IResult<&str, String, VerboseError<&str>> {
     context(
         "name",
         terminated(
-            take_while1(|c| is_valid_name_char(c)),
+            take_while1(is_valid_name_char),
             space0
         )
     )(input)
@@ -54,7 +71,7 @@ fn parse_names(input: &str) -> IResult<&str, (Vec<String>, String), VerboseError
         "names",
         preceded(
             tuple((tag(".names"), space1)),
-            many1(parse_name)
+            cut(many1(parse_name))
         )
     )(input)
         .map(|(next_input, mut res)| {
@@ -94,26 +111,145 @@ fn parse_single_output_cover(input: &str) -> IResult<&str, (Vec<InputValue>, Inp
         })
 }
 
+type CoverRow = (Vec<InputValue>, InputValue);
+
+fn parse_cover_row(input: &str) -> IResult<&str, CoverRow, VerboseError<&str>> {
+    preceded(
+        peek(one_of("01-")),
+        cut(terminated(parse_single_output_cover, char('\n')))
+    )(input)
+}
+
+/// Run `row`, then reject the row unless it has one column per input of its gate.
+fn with_width<'a>(
+    width: usize,
+    mut row: impl FnMut(&'a str) -> IResult<&'a str, CoverRow, VerboseError<&'a str>>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, CoverRow, VerboseError<&'a str>> {
+    move |input| {
+        let (next_input, parsed) = row(input)?;
+
+        if parsed.0.len() == width {
+            Ok((next_input, parsed))
+        } else {
+            Err(Err::Failure(VerboseError { errors: vec![
+                (input, VerboseErrorKind::Nom(ErrorKind::Verify)),
+                (input, VerboseErrorKind::Context("cover-width")),
+            ]}))
+        }
+    }
+}
+
+/// Rows of the cover of a gate with `width` inputs, all listing either the
+/// ON-set or the OFF-set.
+fn parse_cover<'a>(width: usize) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<CoverRow>, VerboseError<&'a str>> {
+    move |input| {
+        let (input, first) = opt(with_width(width, parse_cover_row))(input)?;
+
+        let polarity = match &first {
+            Some((_, output)) => *output,
+            None => return Ok((input, Vec::new())),
+        };
+
+        many0(
+            with_width(width, preceded(
+                peek(one_of("01-")),
+                cut(context(
+                    "cover-polarity",
+                    verify(parse_cover_row, move |(_, output)| *output == polarity)
+                ))
+            ))
+        )(input)
+            .map(|(next_input, mut rows)| {
+                rows.insert(0, first.unwrap());
+
+                (next_input, rows)
+            })
+    }
+}
+
 fn parse_logic_gate(input: &str) -> IResult<&str, LogicGate, VerboseError<&str>> {
     context(
         "logic-gate",
-        pair(
-            context("logic-gate-names",
+        |input| {
+            let (input, (input_names, output_name)) = context("logic-gate-names",
                 terminated(parse_names, opt(char('\n')))
-            ),
-            many0(
-                terminated(parse_single_output_cover, char('\n'))
-            ),
+            )(input)?;
+            let (input, single_output_cover) = parse_cover(input_names.len())(input)?;
+
+            Ok((input, LogicGate::new(input_names, output_name, single_output_cover)))
+        }
+    )(input)
+}
+
+fn parse_connection(input: &str) -> IResult<&str, (String, String), VerboseError<&str>> {
+    context(
+        "connection",
+        pair(
+            terminated(take_while1(is_valid_name_char), char('=')),
+            parse_name
         )
     )(input)
-        .map(|(next_input, (names, single_output_cover))| {
-            let (input_names, output_name) = names;
+        .map(|(next_input, (formal, actual))| (next_input, (formal.into(), actual)))
+}
 
-            let gate = LogicGate::new(input_names, output_name, single_output_cover);
-            (next_input, gate)
+fn parse_subckt(input: &str) -> IResult<&str, Subckt, VerboseError<&str>> {
+    context(
+        "subckt",
+        terminated(
+            preceded(
+                tuple((tag(".subckt"), space1)),
+                cut(pair(parse_name, many1(parse_connection)))
+            ),
+            cut(char('\n'))
+        )
+    )(input)
+        .map(|(next_input, (model, connections))| {
+            (next_input, Subckt::new(model, connections))
         })
 }
 
+fn parse_latch(input: &str) -> IResult<&str, Latch, VerboseError<&str>> {
+    context(
+        "latch",
+        preceded(
+            tuple((tag(".latch"), space1)),
+            cut(map_opt(
+                terminated(many1(parse_name), char('\n')),
+                |tokens: Vec<String>| {
+                    let (control, init) = match tokens.as_slice() {
+                        [_, _] => (None, LatchInit::Unknown),
+                        [_, _, init] => (None, LatchInit::try_from(init.as_str()).ok()?),
+                        [_, _, kind, control] => (Some((kind, control)), LatchInit::Unknown),
+                        [_, _, kind, control, init] => (Some((kind, control)), LatchInit::try_from(init.as_str()).ok()?),
+                        _ => return None,
+                    };
+
+                    let control = match control {
+                        Some((kind, control)) => Some((LatchType::try_from(kind.as_str()).ok()?, control.clone())),
+                        None => None,
+                    };
+
+                    Some(Latch::new(tokens[0].clone(), tokens[1].clone(), control, init))
+                }
+            ))
+        )
+    )(input)
+}
+
+enum ModelElement {
+    Gate(LogicGate),
+    Subckt(Subckt),
+    Latch(Latch),
+}
+
+fn parse_model_element(input: &str) -> IResult<&str, ModelElement, VerboseError<&str>> {
+    alt((
+        |input| parse_logic_gate(input).map(|(next_input, gate)| (next_input, ModelElement::Gate(gate))),
+        |input| parse_subckt(input).map(|(next_input, subckt)| (next_input, ModelElement::Subckt(subckt))),
+        |input| parse_latch(input).map(|(next_input, latch)| (next_input, ModelElement::Latch(latch))),
+    ))(input)
+}
+
 fn parse_model_name(input: &str) -> IResult<&str, String, VerboseError<&str>> {
     context(
         "model-name",
@@ -135,7 +271,7 @@ fn parse_decl_list(input: &str) -> IResult<&str, Vec<String>, VerboseError<&str>
 
 fn parse_model_inputs(input: &str) -> IResult<&str, Vec<String>, VerboseError<&str>> {
     context(
-        "model-name",
+        "model-inputs",
         preceded(
             terminated(tag(".inputs"), space1),
             parse_decl_list)
@@ -144,7 +280,7 @@ fn parse_model_inputs(input: &str) -> IResult<&str, Vec<String>, VerboseError<&s
 
 fn parse_model_outputs(input: &str) -> IResult<&str, Vec<String>, VerboseError<&str>> {
     context(
-        "model-name",
+        "model-outputs",
         preceded(
             terminated(tag(".outputs"), space1),
             parse_decl_list
@@ -155,22 +291,38 @@ fn parse_model_outputs(input: &str) -> IResult<&str, Vec<String>, VerboseError<&
 fn parse_model(input: &str) -> IResult<&str, Model, VerboseError<&str>> {
     context(
         "model",
-        terminated(
-            tuple((
-                parse_model_name,
-                parse_model_inputs,
-                parse_model_outputs,
-                many0(parse_logic_gate),
-            )),
-            terminated(tag(".end"), char('\n'))
+        pair(
+            parse_model_name,
+            cut(terminated(
+                tuple((
+                    many0(parse_model_inputs),
+                    many0(parse_model_outputs),
+                    many0(parse_model_element),
+                )),
+                terminated(tag(".end"), char('\n'))
+            ))
         )
     )(input)
-        .map(|(next_input, (name, inputs, outputs, gates))| {
+        .map(|(next_input, (name, (inputs, outputs, elements)))| {
+            let mut gates = Vec::new();
+            let mut subckts = Vec::new();
+            let mut latches = Vec::new();
+
+            for element in elements {
+                match element {
+                    ModelElement::Gate(gate) => gates.push(gate),
+                    ModelElement::Subckt(subckt) => subckts.push(subckt),
+                    ModelElement::Latch(latch) => latches.push(latch),
+                }
+            }
+
             (next_input, Model {
                     name,
-                    inputs,
-                    outputs,
+                    inputs: inputs.concat(),
+                    outputs: outputs.concat(),
                     gates,
+                    subckts,
+                    latches,
             })
         })
 }
@@ -185,10 +337,18 @@ fn parse_blif(input: &str) -> IResult<&str, Blif, VerboseError<&str>> {
         })
 }
 
-pub fn parse(input: &str) -> Blif {
-    let (_, blif) = parse_blif(input).unwrap();
+pub fn parse(input: &str) -> Result<Blif, BlifError> {
+    let source = Source::new(input);
+
+    let res = all_consuming(parse_blif)(source.text());
 
-    blif
+    match res {
+        Ok((_, blif)) => Ok(blif),
+        Err(Err::Error(e)) | Err(Err::Failure(e)) => {
+            Err(BlifError::new(input, source.text(), e, |offset| source.original_offset(offset)))
+        }
+        Err(Err::Incomplete(_)) => Err(BlifError::at(input, input.len(), "unexpected end of file".into(), Vec::new())),
+    }
 }
 
 #[cfg(test)]
@@ -372,8 +532,278 @@ mod tests {
                     (vec![InputValue::Uncomplemented, InputValue::Uncomplemented], InputValue::Uncomplemented),
                 ]},
             ],
+            subckts: Vec::new(),
+            latches: Vec::new(),
         };
 
         assert_eq!(model, Ok(("", expected)));
     }
+
+    #[test]
+    fn test_parse_subckt() {
+        let subckt = parse_subckt(".subckt a_not_b $1=A $2=B $3=m2_A\n");
+
+        let expected = Subckt::new(
+            "a_not_b".into(),
+            vec![
+                ("$1".into(), "A".into()),
+                ("$2".into(), "B".into()),
+                ("$3".into(), "m2_A".into()),
+            ],
+        );
+
+        assert_eq!(subckt, Ok(("", expected)));
+    }
+
+    #[test]
+    fn test_parse_subckt_no_connections() {
+        let subckt = parse_subckt(".subckt a_not_b\n");
+
+        assert!(subckt.is_err());
+    }
+
+    #[test]
+    fn test_parse_model_subckt() {
+        let model = parse_model(concat!(
+            ".model top\n",
+            ".inputs a\n",
+            ".outputs o\n",
+            ".subckt buf in=a out=n\n",
+            ".names n o\n",
+            "1 1\n",
+            ".end\n",
+        ));
+
+        let (_, model) = model.unwrap();
+
+        assert_eq!(model.gates.len(), 1);
+        assert_eq!(model.subckts, vec![
+            Subckt::new("buf".into(), vec![("in".into(), "a".into()), ("out".into(), "n".into())]),
+        ]);
+    }
+
+    #[test]
+    fn test_parse_latch_minimal() {
+        let latch = parse_latch(".latch d q\n");
+
+        let expected = Latch::new("d".into(), "q".into(), None, LatchInit::Unknown);
+
+        assert_eq!(latch, Ok(("", expected)));
+    }
+
+    #[test]
+    fn test_parse_latch_init() {
+        let latch = parse_latch(".latch d q 1\n");
+
+        let expected = Latch::new("d".into(), "q".into(), None, LatchInit::High);
+
+        assert_eq!(latch, Ok(("", expected)));
+    }
+
+    #[test]
+    fn test_parse_latch_full() {
+        let latch = parse_latch(".latch $0$q[0] q[0] re clk 0\n");
+
+        let expected = Latch::new(
+            "$0$q[0]".into(),
+            "q[0]".into(),
+            Some((LatchType::RisingEdge, "clk".into())),
+            LatchInit::Low,
+        );
+
+        assert_eq!(latch, Ok(("", expected)));
+    }
+
+    #[test]
+    fn test_parse_latch_invalid_type() {
+        let latch = parse_latch(".latch d q xx clk 0\n");
+
+        assert!(latch.is_err());
+    }
+
+    #[test]
+    fn test_parse_latch_invalid_init() {
+        let latch = parse_latch(".latch d q 4\n");
+
+        assert!(latch.is_err());
+    }
+
+    #[test]
+    fn test_parse_error_cover() {
+        let error = parse(concat!(
+            ".model test\n",
+            ".inputs a b\n",
+            ".outputs o\n",
+            ".names a b o\n",
+            "11 1 x\n",
+            ".end\n",
+        )).err().unwrap();
+
+        assert_eq!((error.line, error.column), (5, 5));
+        assert_eq!(error.line_text, "11 1 x");
+        assert_eq!(error.message, "expected end of line");
+        assert_eq!(error.context, vec!["blif", "model", "logic-gate"]);
+    }
+
+    #[test]
+    fn test_parse_error_latch() {
+        let error = parse(concat!(
+            ".model test\n",
+            ".inputs d clk\n",
+            ".outputs q\n",
+            ".latch d q xx clk\n",
+            ".end\n",
+        )).err().unwrap();
+
+        assert_eq!((error.line, error.column), (4, 8));
+        assert_eq!(error.message, "invalid latch");
+        assert_eq!(error.context, vec!["blif", "model", "latch"]);
+    }
+
+    #[test]
+    fn test_parse_error_missing_end() {
+        let error = parse(concat!(
+            ".model test\n",
+            ".inputs a\n",
+            ".outputs o\n",
+            ".names a o\n",
+            "1 1\n",
+            ".wire o\n",
+        )).err().unwrap();
+
+        assert_eq!((error.line, error.column), (6, 1));
+        assert_eq!(error.message, "unexpected `.wire`");
+        assert_eq!(error.context, vec!["blif", "model"]);
+    }
+
+    #[test]
+    fn test_parse_error_trailing_input() {
+        let error = parse(concat!(
+            ".model test\n",
+            ".inputs a\n",
+            ".outputs o\n",
+            ".end\n",
+            "garbage\n",
+        )).err().unwrap();
+
+        assert_eq!((error.line, error.column), (5, 1));
+        assert_eq!(error.message, "unexpected `garbage`");
+    }
+
+    #[test]
+    fn test_parse_fixture_comments_and_continuations() {
+        let blif = parse(include_str!("../../fixtures/full_adder.blif")).unwrap();
+
+        let model = blif.model("full_adder").unwrap();
+        assert_eq!(model.inputs, vec![String::from("a"), String::from("b"), String::from("cin")]);
+        assert_eq!(model.outputs, vec![String::from("sum"), String::from("cout")]);
+        assert_eq!(model.gates.len(), 2);
+        assert_eq!(model.gates[1].single_output_cover.len(), 3);
+    }
+
+    #[test]
+    fn test_parse_fixture_blank_lines_between_models() {
+        let blif = parse(include_str!("../../fixtures/med.blif")).unwrap();
+
+        let names: Vec<&str> = blif.models().iter().map(|model| model.name.as_str()).collect();
+        assert_eq!(names, vec!["a_not_b", "top"]);
+    }
+
+    #[test]
+    fn test_parse_fixture_crlf() {
+        let crlf = parse(include_str!("../../fixtures/smol_crlf.blif")).unwrap();
+        let lf = parse(include_str!("../../fixtures/smol.blif")).unwrap();
+
+        assert_eq!(crlf, lf);
+    }
+
+    #[test]
+    fn test_parse_error_located_in_original_input() {
+        let error = parse(concat!(
+            "# a comment\n",
+            ".model test\n",
+            "\n",
+            ".inputs a \\\n",
+            "  b\n",
+            ".outputs o\n",
+            ".names a b o\n",
+            "1x 1\n",
+            ".end\n",
+        )).err().unwrap();
+
+        assert_eq!((error.line, error.column), (8, 2));
+        assert_eq!(error.line_text, "1x 1");
+    }
+
+    #[test]
+    fn test_parse_logic_gate_off_set() {
+        let logic_gate = parse_logic_gate(".names a b o\n11 0\n00 0\n");
+
+        let expected = LogicGate::new(
+            vec!["a".into(), "b".into()],
+            "o".into(),
+            vec![
+                (vec![InputValue::Uncomplemented, InputValue::Uncomplemented], InputValue::Complemented),
+                (vec![InputValue::Complemented, InputValue::Complemented], InputValue::Complemented),
+            ],
+        );
+
+        assert_eq!(logic_gate, Ok(("", expected)));
+    }
+
+    #[test]
+    fn test_parse_error_mixed_cover() {
+        let error = parse(concat!(
+            ".model test\n",
+            ".inputs a b\n",
+            ".outputs o\n",
+            ".names a b o\n",
+            "11 1\n",
+            "00 0\n",
+            ".end\n",
+        )).err().unwrap();
+
+        assert_eq!((error.line, error.column), (6, 1));
+        assert_eq!(error.message, "cover mixes ON-set (`1`) and OFF-set (`0`) rows");
+        assert_eq!(error.context, vec!["blif", "model", "logic-gate", "cover-polarity"]);
+    }
+
+    #[test]
+    fn test_parse_error_cover_width() {
+        let error = parse(concat!(
+            ".model test\n",
+            ".inputs a b\n",
+            ".outputs o\n",
+            ".names a b o\n",
+            "11 1\n",
+            "1 1\n",
+            ".end\n",
+        )).err().unwrap();
+
+        assert_eq!((error.line, error.column), (6, 1));
+        assert_eq!(error.message, "cover row does not have one column per input of `.names`");
+        assert_eq!(error.context, vec!["blif", "model", "logic-gate", "cover-width"]);
+
+        let error = parse(".model test\n.outputs o\n.names o\n01 1\n.end\n").err().unwrap();
+        assert_eq!((error.line, error.column), (4, 1));
+    }
+
+    #[test]
+    fn test_parse_model_declarations() {
+        let model = parse_model(concat!(
+            ".model test\n",
+            ".inputs a\n",
+            ".inputs b\n",
+            ".end\n",
+        ));
+
+        let expected = Model::new(
+            "test".into(),
+            vec!["a".into(), "b".into()],
+            Vec::new(),
+            Vec::new(),
+        );
+
+        assert_eq!(model, Ok(("", expected)));
+    }
 }
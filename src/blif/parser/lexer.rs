@@ -0,0 +1,124 @@
+/// BLIF source normalised for the parser.
+///
+/// Following the Berkeley BLIF spec, `#` starts a comment running to the end
+/// of the line and a `\` ending a line continues it on the next one. On top of
+/// that, CRLF line endings are accepted and blank lines and surrounding
+/// whitespace are dropped, so that the parser only sees one construct per
+/// `'\n'`-terminated line.
+pub struct Source {
+    text: String,
+    /// `(offset in text, offset in the original input)` at the start of each
+    /// run of bytes copied verbatim, sorted by offset.
+    segments: Vec<(usize, usize)>,
+}
+
+impl Source {
+    pub fn new(input: &str) -> Self {
+        let mut source = Self { text: String::with_capacity(input.len()), segments: Vec::new() };
+
+        // Offset in the original input of the start of the current line.
+        let mut offset = 0;
+        let mut continued = false;
+
+        for raw in input.split_inclusive('\n') {
+            let start = offset;
+            offset += raw.len();
+
+            let mut line = raw.trim_end_matches('\n').trim_end_matches('\r');
+            if let Some(comment) = line.find('#') {
+                line = &line[..comment];
+            }
+
+            let continues = line.trim_end().ends_with('\\');
+            if continues {
+                line = line.trim_end().strip_suffix('\\').unwrap();
+            }
+
+            let trimmed = line.trim();
+            if !trimmed.is_empty() {
+                if continued && !source.text.is_empty() && !source.text.ends_with('\n') {
+                    source.text.push(' ');
+                }
+
+                let leading = line.len() - line.trim_start().len();
+                source.push(trimmed, start + leading);
+            }
+
+            continued = continues;
+            if !continued && !source.text.is_empty() && !source.text.ends_with('\n') {
+                source.text.push('\n');
+            }
+        }
+
+        if !source.text.is_empty() && !source.text.ends_with('\n') {
+            source.text.push('\n');
+        }
+
+        source
+    }
+
+    fn push(&mut self, s: &str, original: usize) {
+        self.segments.push((self.text.len(), original));
+        self.text.push_str(s);
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Offset in the original input of the byte at `offset` in `text`.
+    ///
+    /// Offsets falling on inserted separators map right after the preceding run.
+    pub fn original_offset(&self, offset: usize) -> usize {
+        let i = self.segments.partition_point(|&(start, _)| start <= offset);
+
+        match i.checked_sub(1) {
+            Some(i) => {
+                let (start, original) = self.segments[i];
+                let end = self.segments.get(i + 1).map_or(self.text.len(), |&(next, _)| next);
+                let run = self.text[start..end].trim_end_matches(['\n', ' ']).len();
+
+                original + (offset - start).min(run)
+            }
+            None => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comments_and_blank_lines() {
+        let source = Source::new("# header\n\n.model a # trailing\n   \n.end\n");
+
+        assert_eq!(source.text(), ".model a\n.end\n");
+    }
+
+    #[test]
+    fn test_continuation() {
+        let source = Source::new(".inputs a \\\n  b\\\nc\n");
+
+        assert_eq!(source.text(), ".inputs a b c\n");
+    }
+
+    #[test]
+    fn test_crlf_and_missing_final_newline() {
+        let source = Source::new(".model a\r\n\t.end");
+
+        assert_eq!(source.text(), ".model a\n.end\n");
+    }
+
+    #[test]
+    fn test_original_offset() {
+        let input = "# header\n.inputs a \\\n  b\n";
+        let source = Source::new(input);
+
+        let b = source.text().find('b').unwrap();
+        assert_eq!(source.original_offset(b), input.find('b').unwrap());
+
+        let inputs = source.text().find(".inputs").unwrap();
+        assert_eq!(source.original_offset(inputs), input.find(".inputs").unwrap());
+    }
+}
@@ -0,0 +1,290 @@
+use super::Model;
+use crate::simulation::{Simulable, SignalState, Signal, Signals, SignalsBuilder};
+
+use std::collections::HashSet;
+
+/// A `.subckt model formal=actual ...` instance.
+///
+/// `definition` is left empty by the parser and filled in by `Blif::new`
+/// once every model of the file is known. Tri-state buffers (`$_TBUF_` from
+/// the Yosys internal cell library) are simulated without a definition.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Subckt {
+    pub model: String,
+    pub connections: Vec<(String, String)>,
+
+    pub definition: Option<Box<Model>>,
+}
+
+/// Ports of the cells of the Yosys internal library simulated without a
+/// `.model`, as `(inputs, outputs)`.
+fn builtin_ports(model: &str) -> Option<(&'static [&'static str], &'static [&'static str])> {
+    match model {
+        // Y = E ? A : z
+        "$_TBUF_" => Some((&["A", "E"], &["Y"])),
+        _ => None,
+    }
+}
+
+/// `(port, actual net)` pairs.
+pub type Bindings<'a> = Vec<(&'a str, &'a str)>;
+
+impl Subckt {
+    pub fn new(model: String, connections: Vec<(String, String)>) -> Self {
+        Self { model, connections, definition: None }
+    }
+
+    /// Input and output ports of the instantiated model, if it is known.
+    fn ports(&self) -> Option<(Vec<&str>, Vec<&str>)> {
+        match &self.definition {
+            Some(definition) => Some((
+                definition.inputs.iter().map(String::as_str).collect(),
+                definition.outputs.iter().map(String::as_str).collect(),
+            )),
+            None => builtin_ports(&self.model).map(|(inputs, outputs)| (inputs.to_vec(), outputs.to_vec())),
+        }
+    }
+
+    /// Whether the instantiated model is defined in the file or built in.
+    pub fn is_resolved(&self) -> bool {
+        self.ports().is_some()
+    }
+
+    /// Resolve a formal name to a port among `ports`.
+    ///
+    /// Besides the port names themselves, formals may be written `$1`, `$2`,
+    /// ... to refer to the ports by position (inputs first, then outputs).
+    fn port<'a>(ports: &[&'a str], formal: &str) -> Option<&'a str> {
+        if let Some(port) = ports.iter().find(|port| **port == formal) {
+            return Some(port);
+        }
+
+        let index: usize = formal.strip_prefix('$')?.parse().ok()?;
+        ports.get(index.checked_sub(1)?).copied()
+    }
+
+    /// Connections to the inputs and to the outputs of the instantiated
+    /// model, or `None` if it is unknown.
+    pub fn bindings(&self) -> Option<(Bindings<'_>, Bindings<'_>)> {
+        let (inputs, outputs) = self.ports()?;
+        let ports: Vec<&str> = inputs.iter().chain(outputs.iter()).copied().collect();
+
+        let (bound_inputs, bound_outputs) = self.connections.iter()
+            .filter_map(|(formal, actual)| Self::port(&ports, formal).map(|port| (port, actual.as_str())))
+            .partition(|(port, _)| inputs.contains(port));
+
+        Some((bound_inputs, bound_outputs))
+    }
+
+    /// Actual nets bound to an input port of the instantiated model.
+    pub fn input_nets(&self) -> Vec<String> {
+        match self.bindings() {
+            Some((inputs, _)) => inputs.into_iter().map(|(_, actual)| actual.to_string()).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Actual nets driven by the instance: every connection when unresolved.
+    pub fn output_nets(&self) -> Vec<String> {
+        match self.bindings() {
+            Some((_, outputs)) => outputs.into_iter().map(|(_, actual)| actual.to_string()).collect(),
+            None => self.connections.iter().map(|(_, actual)| actual.clone()).collect(),
+        }
+    }
+
+    /// Values of the input ports of the instantiated model, read from the
+    /// actual nets in `signals`.
+    pub fn formal_inputs(&self, signals: &Signals) -> Signals {
+        let inputs = self.bindings().map_or(Vec::new(), |(inputs, _)| inputs);
+
+        inputs.iter().fold(SignalsBuilder::new(), |builder, (port, actual)| {
+            builder.add_signal(port, signals.get(actual))
+        }).build()
+    }
+
+    fn stim_builtin(&self, formals: &Signals) -> Signals {
+        match self.model.as_str() {
+            "$_TBUF_" => {
+                let y = match formals.get("E") {
+                    SignalState::High => formals.get("A"),
+                    SignalState::Low => SignalState::HighImpedance,
+                    _ => SignalState::Unknown,
+                };
+
+                SignalsBuilder::new().add_signal("Y", y).build()
+            }
+            _ => Signals::new(),
+        }
+    }
+}
+
+impl Simulable for Subckt {
+    fn get_inputs(&self) -> HashSet<String> {
+        self.input_nets().into_iter().collect()
+    }
+
+    fn children(&self) -> Vec<Box<dyn Simulable>> {
+        match &self.definition {
+            Some(definition) => vec![Box::new((**definition).clone()) as Box<dyn Simulable>],
+            None => Vec::new(),
+        }
+    }
+
+    fn stim(&self, signals: Signals) -> Signals {
+        self.evaluate(signals, None)
+    }
+}
+
+impl Subckt {
+    /// Outputs of the instance for `signals`. As `instance`, the latches of
+    /// the instantiated model read their state from `signals` as
+    /// `{instance}/{latch}`, and every net of the instantiated model is
+    /// returned as `{instance}/{net}` too.
+    pub(super) fn evaluate(&self, signals: Signals, instance: Option<&str>) -> Signals {
+        let mut outputs = Signals::new();
+
+        let bound_outputs = match self.bindings() {
+            Some((_, outputs)) => outputs,
+            None => {
+                // Unresolved instance: every actual is left unknown.
+                for (_, actual) in &self.connections {
+                    outputs.add_signal(Signal::new(actual));
+                }
+                return outputs;
+            }
+        };
+
+        let mut formals = self.formal_inputs(&signals);
+
+        let res = match (&self.definition, instance) {
+            (Some(definition), Some(instance)) => {
+                formals.update_with(signals.scope(instance));
+                definition.stim(formals)
+            }
+            (Some(definition), None) => definition.stim(formals),
+            (None, _) => self.stim_builtin(&formals),
+        };
+
+        for (port, actual) in bound_outputs {
+            let mut signal = Signal::new(actual);
+            signal.set(res.get(port));
+            outputs.add_signal(signal);
+        }
+
+        if let (Some(_), Some(instance)) = (&self.definition, instance) {
+            outputs.update_with(res.in_scope(instance));
+        }
+
+        outputs
+    }
+}
+
+/// A `.subckt` seen from the model instantiating it, as instance `name`:
+/// see `Subckt::evaluate`.
+pub(super) struct Instance {
+    pub name: String,
+    pub subckt: Subckt,
+}
+
+impl Simulable for Instance {
+    /// Inputs of the instance, plus the state of the latches below it.
+    fn get_inputs(&self) -> HashSet<String> {
+        let state = self.subckt.definition.iter().flat_map(|definition| definition.state_nets());
+
+        self.subckt.get_inputs().into_iter()
+            .chain(state.map(|net| format!("{}/{}", self.name, net)))
+            .collect()
+    }
+
+    fn children(&self) -> Vec<Box<dyn Simulable>> {
+        self.subckt.children()
+    }
+
+    fn stim(&self, signals: Signals) -> Signals {
+        self.subckt.evaluate(signals, Some(&self.name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::blif::*;
+    use crate::simulation::*;
+
+    fn buffer() -> Model {
+        Model::new(
+            "buf".into(),
+            vec!["a".into()],
+            vec!["y".into()],
+            vec![
+                LogicGate::new(
+                    vec!["a".into()],
+                    "y".into(),
+                    vec![
+                        (vec![InputValue::Uncomplemented], InputValue::Uncomplemented)
+                    ]
+                ),
+            ]
+        )
+    }
+
+    #[test]
+    fn test_subckt_named_ports() {
+        let mut subckt = Subckt::new("buf".into(), vec![
+            ("a".into(), "n1".into()),
+            ("y".into(), "n2".into()),
+        ]);
+        subckt.definition = Some(Box::new(buffer()));
+
+        let res = subckt.stim(
+            SignalsBuilder::new()
+                .add_signal("n1", SignalState::High)
+                .build()
+        );
+
+        assert_eq!(res.get("n2"), SignalState::High);
+        assert_eq!(res.get("y"), SignalState::Unknown);
+    }
+
+    #[test]
+    fn test_subckt_positional_ports() {
+        let mut subckt = Subckt::new("buf".into(), vec![
+            ("$1".into(), "n1".into()),
+            ("$2".into(), "n2".into()),
+        ]);
+        subckt.definition = Some(Box::new(buffer()));
+
+        let res = subckt.stim(
+            SignalsBuilder::new()
+                .add_signal("n1", SignalState::Low)
+                .build()
+        );
+
+        assert_eq!(res.get("n2"), SignalState::Low);
+    }
+
+    #[test]
+    fn test_subckt_tristate_bus() {
+        let tbuf = |a: &str, e: &str| Subckt::new("$_TBUF_".into(), vec![
+            ("A".into(), a.into()),
+            ("E".into(), e.into()),
+            ("Y".into(), "bus".into()),
+        ]);
+
+        let mut bus = Model::new("bus".into(), vec!["a".into(), "ea".into(), "b".into(), "eb".into()], vec!["bus".into()], Vec::new());
+        bus.subckts = vec![tbuf("a", "ea"), tbuf("b", "eb")];
+
+        let drive = |ea: SignalState, eb: SignalState| bus.stim(
+            SignalsBuilder::new()
+                .add_signal("a", SignalState::High)
+                .add_signal("ea", ea)
+                .add_signal("b", SignalState::Low)
+                .add_signal("eb", eb)
+                .build()
+        ).get("bus");
+
+        assert_eq!(drive(SignalState::Low, SignalState::Low), SignalState::HighImpedance);
+        assert_eq!(drive(SignalState::High, SignalState::Low), SignalState::High);
+        assert_eq!(drive(SignalState::Low, SignalState::High), SignalState::Low);
+        assert_eq!(drive(SignalState::High, SignalState::High), SignalState::Unknown);
+    }
+}
@@ -0,0 +1,627 @@
+use super::{Blif, GateFunction, InputValue, LogicGate, Model};
+
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+/// What `Model::sweep` removed.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Sweep {
+    /// Constant nets propagated into the covers reading them.
+    pub constants: Vec<String>,
+    /// `(removed net, net replacing it)` for each buffer removed.
+    pub buffers: Vec<(String, String)>,
+    /// Outputs of the inverters folded into the covers reading them.
+    pub inverters: Vec<String>,
+    /// Outputs of the gates removed for reaching no primary output.
+    pub dead: Vec<String>,
+}
+
+impl Sweep {
+    pub fn is_empty(&self) -> bool {
+        self.constants.is_empty() && self.buffers.is_empty() && self.inverters.is_empty() && self.dead.is_empty()
+    }
+}
+
+impl std::fmt::Display for Sweep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let plural = |count: usize, what: &str| format!("{} {}{}", count, what, if count == 1 { "" } else { "s" });
+
+        write!(
+            f,
+            "propagated {}, removed {}, {} and {}",
+            plural(self.constants.len(), "constant"),
+            plural(self.buffers.len(), "buffer"),
+            plural(self.inverters.len(), "inverter"),
+            plural(self.dead.len(), "dead gate"),
+        )
+    }
+}
+
+/// Drivers and readers of one net of a model.
+#[derive(Default)]
+struct Uses {
+    /// Gates, latches and instances driving the net, primary inputs included.
+    drivers: usize,
+    /// Drivers which may leave the net high-impedance, as primary inputs and
+    /// instances can.
+    floating: usize,
+    gate_readers: usize,
+    /// Latches, instances and primary outputs reading the net.
+    other_readers: usize,
+}
+
+/// A model being swept: its gates, by slot so that removing one leaves the
+/// others in place, and the uses of every net, kept up to date as gates are
+/// rewritten, renamed and removed.
+struct Sweeper<'a> {
+    model: &'a mut Model,
+    gates: Vec<Option<LogicGate>>,
+    uses: HashMap<String, Uses>,
+    /// Slots of the gates reading and driving each net.
+    readers: HashMap<String, BTreeSet<usize>>,
+    drivers: HashMap<String, BTreeSet<usize>>,
+    /// Latches and instances connected to each net, by index.
+    latches: HashMap<String, BTreeSet<usize>>,
+    subckts: HashMap<String, BTreeSet<usize>>,
+    /// Gates to look at again, as what they read or drive changed.
+    queue: VecDeque<usize>,
+    queued: Vec<bool>,
+}
+
+impl<'a> Sweeper<'a> {
+    fn new(model: &'a mut Model) -> Self {
+        let gates: Vec<Option<LogicGate>> = std::mem::take(&mut model.gates).into_iter().map(Some).collect();
+        let count = gates.len();
+
+        let mut sweeper = Self {
+            model,
+            gates,
+            uses: HashMap::new(),
+            readers: HashMap::new(),
+            drivers: HashMap::new(),
+            latches: HashMap::new(),
+            subckts: HashMap::new(),
+            queue: (0..count).collect(),
+            queued: vec![true; count],
+        };
+
+        for input in &sweeper.model.inputs {
+            let uses = sweeper.uses.entry(input.clone()).or_default();
+            uses.drivers += 1;
+            uses.floating += 1;
+        }
+        for output in &sweeper.model.outputs {
+            sweeper.uses.entry(output.clone()).or_default().other_readers += 1;
+        }
+
+        for slot in 0..count {
+            sweeper.connect_gate(slot, true);
+        }
+        for i in 0..sweeper.model.latches.len() {
+            sweeper.connect_latch(i, true);
+        }
+        for i in 0..sweeper.model.subckts.len() {
+            sweeper.connect_subckt(i, true);
+        }
+
+        sweeper
+    }
+
+    fn uses(&self, net: &str) -> &Uses {
+        static UNUSED: Uses = Uses { drivers: 0, floating: 0, gate_readers: 0, other_readers: 0 };
+        self.uses.get(net).unwrap_or(&UNUSED)
+    }
+
+    fn uses_mut(&mut self, net: &str) -> &mut Uses {
+        self.uses.entry(net.to_string()).or_default()
+    }
+
+    /// Add (or with `connected` false, remove) the uses of the nets of the
+    /// gate in `slot`.
+    fn connect_gate(&mut self, slot: usize, connected: bool) {
+        let gate = self.gates[slot].take().expect("a gate in the slot");
+
+        for input in &gate.inputs {
+            update(&mut self.uses_mut(input).gate_readers, connected);
+            update_set(&mut self.readers, input, slot, connected);
+        }
+        update(&mut self.uses_mut(&gate.output).drivers, connected);
+        update_set(&mut self.drivers, &gate.output, slot, connected);
+
+        self.gates[slot] = Some(gate);
+    }
+
+    fn connect_latch(&mut self, i: usize, connected: bool) {
+        let latch = self.model.latches[i].clone();
+        let control = latch.control.as_ref().map(|(_, control)| control);
+
+        for net in std::iter::once(&latch.input).chain(control) {
+            update(&mut self.uses_mut(net).other_readers, connected);
+        }
+        update(&mut self.uses_mut(&latch.output).drivers, connected);
+
+        for net in [&latch.input, &latch.output].into_iter().chain(control) {
+            update_set(&mut self.latches, net, i, connected);
+        }
+    }
+
+    fn connect_subckt(&mut self, i: usize, connected: bool) {
+        let subckt = self.model.subckts[i].clone();
+
+        match subckt.bindings() {
+            Some((inputs, outputs)) => {
+                for (_, actual) in inputs {
+                    update(&mut self.uses_mut(actual).other_readers, connected);
+                }
+                for (_, actual) in outputs {
+                    let uses = self.uses_mut(actual);
+                    update(&mut uses.drivers, connected);
+                    update(&mut uses.floating, connected);
+                }
+            }
+            // Unknown model: its connections may be inputs or outputs.
+            None => {
+                for (_, actual) in &subckt.connections {
+                    let uses = self.uses_mut(actual);
+                    update(&mut uses.other_readers, connected);
+                    update(&mut uses.drivers, connected);
+                    update(&mut uses.floating, connected);
+                }
+            }
+        }
+
+        for (_, actual) in &subckt.connections {
+            update_set(&mut self.subckts, actual, i, connected);
+        }
+    }
+
+    /// Look at the gates reading or driving `net` again.
+    fn enqueue(&mut self, net: &str) {
+        let slots = self.readers.get(net).into_iter().chain(self.drivers.get(net)).flatten();
+
+        for slot in slots {
+            if !self.queued[*slot] {
+                self.queued[*slot] = true;
+                self.queue.push_back(*slot);
+            }
+        }
+    }
+
+    fn remove_gate(&mut self, slot: usize) -> LogicGate {
+        self.connect_gate(slot, false);
+        self.gates[slot].take().expect("a gate in the slot")
+    }
+
+    /// Rewrite the gate in `slot` with `rewrite`, keeping the uses up to date.
+    fn rewrite_gate(&mut self, slot: usize, rewrite: impl FnOnce(&mut LogicGate)) {
+        self.connect_gate(slot, false);
+        rewrite(self.gates[slot].as_mut().expect("a gate in the slot"));
+        self.connect_gate(slot, true);
+    }
+
+    fn is_port(&self, net: &str) -> bool {
+        self.model.inputs.iter().chain(&self.model.outputs).any(|port| port == net)
+    }
+
+    /// Replace `from`, which is not a port, with `to` wherever it appears.
+    fn rename(&mut self, from: &str, to: &str) {
+        let rename = |net: &mut String| if net == from { *net = to.to_string() };
+
+        let gates: BTreeSet<usize> = self.readers.get(from).into_iter().chain(self.drivers.get(from)).flatten().copied().collect();
+        for slot in gates {
+            self.rewrite_gate(slot, |gate| {
+                gate.inputs.iter_mut().for_each(rename);
+                rename(&mut gate.output);
+            });
+        }
+
+        for i in self.latches.get(from).cloned().unwrap_or_default() {
+            self.connect_latch(i, false);
+            let latch = &mut self.model.latches[i];
+            rename(&mut latch.input);
+            rename(&mut latch.output);
+            if let Some((_, control)) = latch.control.as_mut() {
+                rename(control);
+            }
+            self.connect_latch(i, true);
+        }
+
+        for i in self.subckts.get(from).cloned().unwrap_or_default() {
+            self.connect_subckt(i, false);
+            self.model.subckts[i].connections.iter_mut().for_each(|(_, actual)| rename(actual));
+            self.connect_subckt(i, true);
+        }
+    }
+
+    fn run(&mut self, sweep: &mut Sweep) {
+        while let Some(slot) = self.queue.pop_front() {
+            self.queued[slot] = false;
+
+            if self.gates[slot].is_some() {
+                let _ = self.propagate_constant(slot, sweep) || self.remove_buffer(slot, sweep) || self.fold_inverter(slot, sweep);
+            }
+        }
+
+        self.model.gates = self.gates.drain(..).flatten().collect();
+    }
+
+    /// Propagate the gate in `slot` into its readers if it is a constant.
+    fn propagate_constant(&mut self, slot: usize, sweep: &mut Sweep) -> bool {
+        let gate = self.gates[slot].as_ref().expect("a gate in the slot");
+        let value = match gate.function() {
+            GateFunction::Zero => InputValue::Complemented,
+            GateFunction::One => InputValue::Uncomplemented,
+            _ => return false,
+        };
+        let net = gate.output.clone();
+
+        let readers: Vec<usize> = self.readers.get(&net).into_iter().flatten().copied()
+            .filter(|reader| *reader != slot && well_formed(self.gates[*reader].as_ref().expect("a gate in the slot")))
+            .collect();
+        if readers.is_empty() || self.uses(&net).drivers != 1 {
+            return false;
+        }
+
+        for reader in readers {
+            self.rewrite_gate(reader, |gate| {
+                while let Some(i) = gate.inputs.iter().position(|input| *input == net) {
+                    *gate = cofactor(gate, i, value);
+                }
+            });
+
+            let output = self.gates[reader].as_ref().expect("a gate in the slot").output.clone();
+            self.enqueue(&output);
+        }
+
+        sweep.constants.push(net);
+        true
+    }
+
+    /// Remove the gate in `slot` if it is a buffer whose output or input
+    /// can be renamed.
+    fn remove_buffer(&mut self, slot: usize, sweep: &mut Sweep) -> bool {
+        let gate = self.gates[slot].as_ref().expect("a gate in the slot");
+        let (input, output) = match (gate.function(), gate.inputs.as_slice()) {
+            (GateFunction::Buf, [input]) if *input != gate.output => (input.clone(), gate.output.clone()),
+            _ => return false,
+        };
+        let uses = self.uses(&output);
+
+        // The buffer turns high impedance into unknown, which only gates read the same.
+        let keeps_z = uses.other_readers == 0 || self.uses(&input).floating == 0;
+
+        if uses.drivers != 1 || !keeps_z || (self.is_port(&output) && self.is_port(&input)) {
+            return false;
+        }
+
+        self.remove_gate(slot);
+
+        let (from, to) = if self.is_port(&output) { (input, output) } else { (output, input) };
+        self.rename(&from, &to);
+        self.enqueue(&to);
+
+        sweep.buffers.push((from, to));
+        true
+    }
+
+    /// Fold the gate in `slot` into the covers reading it if it is an
+    /// inverter read by gates only.
+    fn fold_inverter(&mut self, slot: usize, sweep: &mut Sweep) -> bool {
+        let gate = self.gates[slot].as_ref().expect("a gate in the slot");
+        let output = gate.output.clone();
+        let uses = self.uses(&output);
+
+        let foldable = gate.function() == GateFunction::Not && gate.inputs.len() == 1 && gate.inputs[0] != output
+            && !self.is_port(&output) && uses.drivers == 1 && uses.other_readers == 0 && uses.gate_readers > 0
+            && self.readers.get(&output).into_iter().flatten().all(|reader| well_formed(self.gates[*reader].as_ref().expect("a gate in the slot")));
+        if !foldable {
+            return false;
+        }
+
+        let input = self.remove_gate(slot).inputs.remove(0);
+
+        for reader in self.readers.get(&output).cloned().unwrap_or_default() {
+            self.rewrite_gate(reader, |reader| {
+                for (i, net) in reader.inputs.iter_mut().enumerate() {
+                    if *net == output {
+                        *net = input.clone();
+
+                        for (row, _) in reader.single_output_cover.iter_mut() {
+                            row[i] = match row[i] {
+                                InputValue::Uncomplemented => InputValue::Complemented,
+                                InputValue::Complemented => InputValue::Uncomplemented,
+                                InputValue::NotUsed => InputValue::NotUsed,
+                            };
+                        }
+                    }
+                }
+            });
+
+            let output = self.gates[reader].as_ref().expect("a gate in the slot").output.clone();
+            self.enqueue(&output);
+        }
+        self.enqueue(&input);
+
+        sweep.inverters.push(output);
+        true
+    }
+}
+
+/// Count one more or one less use.
+fn update(count: &mut usize, connected: bool) {
+    if connected {
+        *count += 1;
+    } else {
+        *count -= 1;
+    }
+}
+
+fn update_set(sets: &mut HashMap<String, BTreeSet<usize>>, net: &str, index: usize, connected: bool) {
+    if connected {
+        sets.entry(net.to_string()).or_default().insert(index);
+    } else if let Some(set) = sets.get_mut(net) {
+        set.remove(&index);
+    }
+}
+
+impl Model {
+    /// Simplify the gates of the model without changing what it computes,
+    /// unknown and high-impedance values included:
+    ///
+    /// - constant gates, such as the `$false` and `$true` of Yosys, are
+    ///   propagated into the covers reading them;
+    /// - buffers are removed by renaming their output or input net, primary
+    ///   inputs and outputs keeping their names;
+    /// - inverters read by gates only are folded into their covers;
+    /// - gates whose outputs reach no primary output, even through latches
+    ///   and instances, are removed.
+    ///
+    /// Latches and instances are left as they are, and so are the models
+    /// they instantiate and the covers with rows of the wrong width. Gates are looked at in turn, and again whenever a
+    /// net they read or drive changes, so the sweep is linear in the size of
+    /// the model for the usual fan-in and fan-out.
+    pub fn sweep(&mut self) -> Sweep {
+        let mut sweep = Sweep::default();
+
+        Sweeper::new(self).run(&mut sweep);
+        self.remove_dead_gates(&mut sweep);
+
+        sweep
+    }
+
+    fn remove_dead_gates(&mut self, sweep: &mut Sweep) {
+        // Nets read to compute each net.
+        let mut fanins: HashMap<&str, Vec<&str>> = HashMap::new();
+
+        for gate in &self.gates {
+            fanins.entry(&gate.output).or_default().extend(gate.inputs.iter().map(String::as_str));
+        }
+
+        for latch in &self.latches {
+            let fanin = fanins.entry(&latch.output).or_default();
+            fanin.push(&latch.input);
+            fanin.extend(latch.control.as_ref().map(|(_, control)| control.as_str()));
+        }
+
+        let mut pending: Vec<&str> = self.outputs.iter().map(String::as_str).collect();
+
+        for subckt in &self.subckts {
+            match subckt.bindings() {
+                Some((inputs, outputs)) => {
+                    for (_, output) in outputs {
+                        fanins.entry(output).or_default().extend(inputs.iter().map(|(_, actual)| *actual));
+                    }
+                }
+                // Instances of unknown models may read any of their connections.
+                None => pending.extend(subckt.connections.iter().map(|(_, actual)| actual.as_str())),
+            }
+        }
+
+        let mut live: HashSet<&str> = HashSet::new();
+        while let Some(net) = pending.pop() {
+            if live.insert(net) {
+                pending.extend(fanins.get(net).into_iter().flatten());
+            }
+        }
+
+        let live: HashSet<String> = live.into_iter().map(String::from).collect();
+        let (kept, dead): (Vec<LogicGate>, Vec<LogicGate>) = self.gates.drain(..).partition(|gate| live.contains(&gate.output));
+
+        self.gates = kept;
+        sweep.dead.extend(dead.into_iter().map(|gate| gate.output));
+    }
+}
+
+/// Whether every row of the cover of `gate` has one column per input.
+fn well_formed(gate: &LogicGate) -> bool {
+    gate.single_output_cover.iter().all(|(inputs, _)| inputs.len() == gate.inputs.len())
+}
+
+/// `gate` with input `i` fixed to `value` (`Uncomplemented` for high) and removed.
+fn cofactor(gate: &LogicGate, i: usize, value: InputValue) -> LogicGate {
+    let polarity = gate.polarity();
+
+    let mut inputs = gate.inputs.clone();
+    inputs.remove(i);
+
+    let mut cover: Vec<(Vec<InputValue>, InputValue)> = gate.single_output_cover.iter()
+        .filter(|(row, output)| *output == polarity && (row[i] == InputValue::NotUsed || row[i] == value))
+        .map(|(row, output)| {
+            let mut row = row.clone();
+            row.remove(i);
+            (row, *output)
+        })
+        .collect();
+
+    // An empty OFF-set is the constant 1, which an empty cover is not.
+    if cover.is_empty() && polarity == InputValue::Complemented {
+        cover.push((vec![InputValue::NotUsed; inputs.len()], InputValue::Uncomplemented));
+    }
+
+    LogicGate::new(inputs, gate.output.clone(), cover)
+}
+
+impl Blif {
+    /// A copy of the file with every model swept, and what was removed from each.
+    pub fn sweep(&self) -> (Self, Vec<(String, Sweep)>) {
+        let mut sweeps = Vec::new();
+
+        let models = self.models().iter().map(|model| {
+            let mut model = model.clone();
+            sweeps.push((model.name.clone(), model.sweep()));
+
+            model
+        }).collect();
+
+        (Self::new(models), sweeps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blif::parse;
+    use crate::simulation::{Simulable, SignalState, SignalsBuilder};
+
+    /// Every combination of four-valued inputs gives the same outputs.
+    fn assert_same_outputs(a: &Model, b: &Model) {
+        let states = [SignalState::Low, SignalState::High, SignalState::Unknown, SignalState::HighImpedance];
+        let n = a.inputs.len();
+
+        for combination in 0..4usize.pow(n as u32) {
+            let signals = a.inputs.iter().enumerate().fold(SignalsBuilder::new(), |builder, (i, input)| {
+                builder.add_signal(input, states[combination / 4usize.pow(i as u32) % 4])
+            }).build();
+
+            let (res_a, res_b) = (a.stim(signals.clone()), b.stim(signals));
+
+            for output in &a.outputs {
+                assert_eq!(res_a.get(output), res_b.get(output), "`{}` for combination {}", output, combination);
+            }
+        }
+    }
+
+    #[test]
+    fn test_smol() {
+        let blif = parse(include_str!("../../fixtures/smol.blif")).unwrap();
+        let mut model = blif.top().unwrap().clone();
+
+        let sweep = model.sweep();
+
+        assert_eq!(sweep.to_string(), "propagated 0 constants, removed 0 buffers, 1 inverter and 3 dead gates");
+        assert_eq!(sweep.dead, vec!["$false".to_string(), "$true".into(), "$undef".into()]);
+        assert_eq!(model.to_string(), ".model blinky\n.inputs i_A i_B\n.outputs o_led\n.names i_A i_B o_led\n10 1\n.end\n");
+        assert_same_outputs(blif.top().unwrap(), &model);
+    }
+
+    #[test]
+    fn test_buffer_chains() {
+        let blif = parse(include_str!("../../fixtures/med.blif")).unwrap();
+        let mut model = blif.model("a_not_b").unwrap().clone();
+
+        let sweep = model.sweep();
+
+        // `i_A` is read through a buffer, and the AND gate drives `o_led` through another.
+        assert_eq!(sweep.buffers, vec![
+            ("$logic_and$fixtures/med.v:2$3_Y".to_string(), "o_led".to_string()),
+            ("$eq$fixtures/med.v:2$1_Y".into(), "i_A".into()),
+        ]);
+        assert_eq!(sweep.inverters, vec!["$eq$fixtures/med.v:2$2_Y".to_string()]);
+        assert_eq!(model.gates, vec![LogicGate::new(vec!["i_A".into(), "i_B".into()], "o_led".into(), vec![
+            (vec![InputValue::Uncomplemented, InputValue::Complemented], InputValue::Uncomplemented),
+        ])]);
+        assert_same_outputs(blif.model("a_not_b").unwrap(), &model);
+    }
+
+    #[test]
+    fn test_constants() {
+        let source = concat!(
+            ".model constants\n",
+            ".inputs a b\n",
+            ".outputs y z w\n",
+            ".names zero\n",
+            ".names one\n",
+            "1\n",
+            ".names a zero b y\n",
+            "1-1 1\n",
+            "-1- 1\n",
+            ".names one b z\n",
+            "11 0\n",
+            ".names zero a w\n",
+            "1- 0\n",
+            "-1 0\n",
+            ".end\n",
+        );
+        let blif = parse(source).unwrap();
+        let mut model = blif.top().unwrap().clone();
+
+        let sweep = model.sweep();
+
+        assert_eq!(sweep.constants, vec!["zero".to_string(), "one".into()]);
+        assert_eq!(sweep.dead, vec!["zero".to_string(), "one".into()]);
+        assert_same_outputs(blif.top().unwrap(), &model);
+    }
+
+    #[test]
+    fn test_floating_inputs_keep_their_buffer() {
+        // The buffer turns a high-impedance input into an unknown output.
+        let blif = parse(".model feed\n.inputs a\n.outputs y\n.names a n\n1 1\n.names n y\n1 1\n.end\n").unwrap();
+        let mut model = blif.top().unwrap().clone();
+
+        assert_eq!(model.sweep().buffers, vec![("n".to_string(), "a".to_string())]);
+        assert_eq!(model.gates.len(), 1);
+        assert_same_outputs(blif.top().unwrap(), &model);
+    }
+
+    #[test]
+    fn test_long_chains() {
+        // Inverters and buffers alternating, listed from the output back:
+        // swept one gate at a time, but without scanning the model for each.
+        let n = 20_000;
+        let mut source = String::from(".model chain\n.inputs a\n.outputs y\n");
+        for i in (0..n).rev() {
+            let input = if i == 0 { "a".to_string() } else { format!("n{}", i) };
+            let output = if i == n - 1 { "y".to_string() } else { format!("n{}", i + 1) };
+            source.push_str(&format!(".names {} {}\n{} 1\n", input, output, i % 2));
+        }
+        source.push_str(".end\n");
+
+        let blif = parse(&source).unwrap();
+        let mut model = blif.top().unwrap().clone();
+        let sweep = model.sweep();
+
+        assert_eq!(sweep.buffers.len() + sweep.inverters.len(), n - 1);
+        // An even number of inverters, down to the buffer between the ports.
+        assert_eq!(model.gates, vec![LogicGate::new(vec!["a".into()], "y".into(), vec![
+            (vec![InputValue::Uncomplemented], InputValue::Uncomplemented),
+        ])]);
+    }
+
+    #[test]
+    fn test_latches_keep_logic_alive() {
+        let blif = parse(include_str!("../../fixtures/counter.blif")).unwrap();
+        let mut model = blif.top().unwrap().clone();
+
+        let sweep = model.sweep();
+
+        assert_eq!(sweep.dead.len(), 3);
+        assert_eq!(model.gates.len(), 2);
+        assert_eq!(model.latches, blif.top().unwrap().latches);
+    }
+
+    #[test]
+    fn test_malformed_readers() {
+        let blif = parse(".model bad\n.inputs a\n.outputs y\n.names zero\n.names a n\n0 1\n.end\n").unwrap();
+        let mut model = blif.top().unwrap().clone();
+        // A row of one column for two inputs, which only a model built in code can have.
+        let reader = LogicGate::new(vec!["n".into(), "zero".into()], "y".into(), vec![
+            (vec![InputValue::Uncomplemented], InputValue::Uncomplemented),
+        ]);
+        model.gates.push(reader.clone());
+
+        let sweep = model.sweep();
+
+        // Neither the constant nor the inverter is folded into the reader.
+        assert!(sweep.is_empty());
+        assert_eq!(model.gates[2], reader);
+    }
+}
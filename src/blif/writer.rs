@@ -0,0 +1,230 @@
+use super::{Blif, Model, LogicGate, Latch, Subckt};
+
+use std::fmt::{Display, Formatter, Result};
+
+/// Serialise `blif` to canonical BLIF text, which `parse` reads back to an
+/// equal `Blif`.
+///
+/// Every model is written as `.model`, `.inputs`, `.outputs` (both left out
+/// when empty), its `.names` covers, its `.latch`es and its `.subckt`s, and
+/// `.end`. Comments, continuations and the original order of the elements
+/// are not kept.
+pub fn write(blif: &Blif) -> String {
+    blif.to_string()
+}
+
+impl Display for Blif {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        for (i, model) in self.models().iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+
+            write!(f, "{}", model)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for Model {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        writeln!(f, ".model {}", self.name)?;
+
+        if !self.inputs.is_empty() {
+            writeln!(f, ".inputs {}", self.inputs.join(" "))?;
+        }
+
+        if !self.outputs.is_empty() {
+            writeln!(f, ".outputs {}", self.outputs.join(" "))?;
+        }
+
+        for gate in &self.gates {
+            write!(f, "{}", gate)?;
+        }
+
+        for latch in &self.latches {
+            write!(f, "{}", latch)?;
+        }
+
+        for subckt in &self.subckts {
+            write!(f, "{}", subckt)?;
+        }
+
+        writeln!(f, ".end")
+    }
+}
+
+impl Display for LogicGate {
+    /// The `.names` line followed by one line per row of the cover.
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, ".names")?;
+        for name in self.inputs.iter().chain(std::iter::once(&self.output)) {
+            write!(f, " {}", name)?;
+        }
+        writeln!(f)?;
+
+        for (inputs, output) in &self.single_output_cover {
+            // Constant gates have no input plane: the row is the output alone.
+            if !inputs.is_empty() {
+                for input in inputs {
+                    write!(f, "{}", input)?;
+                }
+                write!(f, " ")?;
+            }
+
+            writeln!(f, "{}", output)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for Latch {
+    /// The initial value is always written, so that it reads back the same.
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, ".latch {} {}", self.input, self.output)?;
+
+        if let Some((kind, control)) = &self.control {
+            write!(f, " {} {}", kind, control)?;
+        }
+
+        writeln!(f, " {}", self.init)
+    }
+}
+
+impl Display for Subckt {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, ".subckt {}", self.model)?;
+
+        for (formal, actual) in &self.connections {
+            write!(f, " {}={}", formal, actual)?;
+        }
+
+        writeln!(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blif::{parse, InputValue, LatchType, LatchInit};
+    use crate::rng::Rng;
+
+    fn assert_round_trip(source: &str) {
+        let blif = parse(source).unwrap();
+        let written = write(&blif);
+
+        assert_eq!(parse(&written), Ok(blif), "written as:\n{}", written);
+    }
+
+    #[test]
+    fn test_write_smol() {
+        let blif = parse(include_str!("../../fixtures/smol.blif")).unwrap();
+
+        assert_eq!(write(&blif), concat!(
+            ".model blinky\n",
+            ".inputs i_A i_B\n",
+            ".outputs o_led\n",
+            ".names $false\n",
+            ".names $true\n",
+            "1\n",
+            ".names $undef\n",
+            ".names i_A Y o_led\n",
+            "11 1\n",
+            ".names i_B Y\n",
+            "0 1\n",
+            ".end\n",
+        ));
+    }
+
+    #[test]
+    fn test_write_latch_and_subckt() {
+        let mut model = Model::new("top".into(), vec!["d".into()], vec!["q".into()], Vec::new());
+        model.latches.push(Latch::new("d".into(), "n".into(), None, LatchInit::DontCare));
+        model.latches.push(Latch::new("n".into(), "q".into(), Some((LatchType::RisingEdge, "clk".into())), LatchInit::Low));
+        model.subckts.push(Subckt::new("$_TBUF_".into(), vec![("A".into(), "d".into()), ("E".into(), "n".into()), ("Y".into(), "q".into())]));
+
+        assert_eq!(model.to_string(), concat!(
+            ".model top\n",
+            ".inputs d\n",
+            ".outputs q\n",
+            ".latch d n 2\n",
+            ".latch n q re clk 0\n",
+            ".subckt $_TBUF_ A=d E=n Y=q\n",
+            ".end\n",
+        ));
+    }
+
+    #[test]
+    fn test_round_trip_fixtures() {
+        assert_round_trip(include_str!("../../fixtures/smol.blif"));
+        assert_round_trip(include_str!("../../fixtures/smol_crlf.blif"));
+        assert_round_trip(include_str!("../../fixtures/med.blif"));
+        assert_round_trip(include_str!("../../fixtures/counter.blif"));
+        assert_round_trip(include_str!("../../fixtures/full_adder.blif"));
+    }
+
+    fn net(rng: &mut Rng) -> String {
+        format!("n{}", rng.below(8))
+    }
+
+    fn random_model(rng: &mut Rng, name: String, leaf: Option<&Model>) -> Model {
+        let inputs = (0..rng.below(4)).map(|i| format!("i{}", i)).collect();
+        let outputs = (0..rng.below(3)).map(|i| format!("o{}", i)).collect();
+        let mut model = Model::new(name, inputs, outputs, Vec::new());
+
+        for _ in 0..rng.below(6) {
+            let inputs: Vec<String> = (0..rng.below(4)).map(|_| net(rng)).collect();
+            let polarity = [InputValue::Uncomplemented, InputValue::Complemented][rng.below(2)];
+            let cover = (0..rng.below(4)).map(|_| {
+                let row = inputs.iter().map(|_| {
+                    [InputValue::Uncomplemented, InputValue::Complemented, InputValue::NotUsed][rng.below(3)]
+                }).collect();
+
+                (row, polarity)
+            }).collect();
+
+            model.gates.push(LogicGate::new(inputs, net(rng), cover));
+        }
+
+        for _ in 0..rng.below(3) {
+            let control = match rng.below(3) {
+                0 => None,
+                1 => Some((LatchType::ActiveHigh, "NIL".into())),
+                _ => Some((LatchType::FallingEdge, net(rng))),
+            };
+            let init = [LatchInit::Low, LatchInit::High, LatchInit::DontCare, LatchInit::Unknown][rng.below(4)];
+
+            model.latches.push(Latch::new(net(rng), net(rng), control, init));
+        }
+
+        if let Some(leaf) = leaf {
+            for _ in 0..rng.below(3) {
+                let connections = leaf.inputs.iter().chain(&leaf.outputs)
+                    .map(|formal| (formal.clone(), net(rng)))
+                    .collect::<Vec<_>>();
+
+                if !connections.is_empty() {
+                    model.subckts.push(Subckt::new(leaf.name.clone(), connections));
+                }
+            }
+        }
+
+        model
+    }
+
+    #[test]
+    fn test_round_trip_random() {
+        let mut rng = Rng::new(0x2545_f491_4f6c_dd1d);
+
+        for _ in 0..200 {
+            let leaf = random_model(&mut rng, "leaf".into(), None);
+            let top = random_model(&mut rng, "top".into(), Some(&leaf));
+            let blif = Blif::new(vec![top, leaf]);
+
+            let written = write(&blif);
+            assert_eq!(parse(&written), Ok(blif), "written as:\n{}", written);
+        }
+    }
+}
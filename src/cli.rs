@@ -0,0 +1,785 @@
+use crate::blif::{self, Blif, BlifError, CombinationalLoop, Model};
+use crate::simulation::{Simulable, SignalState, Signals, SignalsBuilder, Stepper};
+use crate::vcd::TraceRecorder;
+use crate::verilog::write_verilog;
+use crate::dot::write_dot;
+use crate::tseitin::{Encoding, QueryError};
+use crate::aig::{read_aiger, write_aag, write_aig, Aig, AigError, AigerError};
+use crate::stats::{lint, Stats};
+use crate::testbench::{Stimulus, StimulusError};
+use crate::truth_table::{TruthTable, TruthTableError, DEFAULT_MAX_INPUTS};
+
+use std::io::{BufRead, Write};
+
+pub const USAGE: &str = "\
+usage: garnierisator <command> [options] <file.blif> [vectors...|stimulus|constraints...]
+
+commands:
+  check, parse     validate a BLIF file and print a summary of its models
+  sim              apply input vectors and print the outputs
+  truth-table      print the outputs of a model for every input combination
+  test             run a stimulus file against a model and report mismatches
+  write            print the file back as canonical BLIF
+  verilog          print the file as structural Verilog
+  dot              draw a model as a Graphviz graph, with the values of a vector
+  stats            print the size and depth of every model, and warnings about them
+  sweep            print the file with constants, buffers and dead gates removed
+  aiger            print a model as an AIGER and-inverter graph
+  cnf              print the logic of a model as DIMACS CNF
+  sat              find input and latch values meeting constraints on a model
+
+options:
+  -m, --model <name>    model to use instead of the top-level one
+  -c, --clock <net>     (sim) clock input, ticked once per vector
+  --vcd <file>          (sim) write the waveforms of every net to a VCD file
+  --max-inputs <n>      (truth-table) refuse models with more inputs [default: 16, at most 20]
+  --format <format>     (truth-table) `table`, `csv` or `pla` [default: table]
+  --binary              (aiger) write binary AIGER instead of ASCII
+  --sweep               sweep every model before anything else
+  --minimise            minimise the cover of every gate before anything else
+  -h, --help            print this message
+
+Input vectors are written as one 0/1/x/z character per input of the model, in
+`.inputs` order (leaving out the clock). Without vectors on the command line,
+`sim` reads them from stdin, one per line.
+
+A stimulus file for `test` holds one vector per line, optionally followed by
+`|` and the expected outputs (`-` for don't-care). Lines starting with `@` tick
+the clock named by a `.clock <net>` line, or the global clock.
+
+Constraints for `sat` are written `<net>=0` or `<net>=1`, on the inputs, latch
+outputs and outputs of the model.
+
+Files ending in `.aag` or `.aig` are read as AIGER instead of BLIF.";
+
+#[derive(Debug)]
+pub enum Error {
+    Usage(String),
+    Io(String, std::io::Error),
+    Parse(BlifError),
+    Aiger(String, AigerError),
+    Aig(AigError),
+    Query(QueryError),
+    InvalidConstraint(String),
+    Loop(CombinationalLoop),
+    UnknownModel(String),
+    UnresolvedSubckt { model: String, subckt: String },
+    InvalidVector(String),
+    TooManyInputs { model: String, inputs: usize, max: usize },
+    Stimulus(String, StimulusError),
+    TestFailed,
+}
+
+impl Error {
+    /// 2 for command-line misuse, 1 for everything else.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Usage(_) => 2,
+            _ => 1,
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Usage(message) => write!(f, "error: {}\n\n{}", message, USAGE),
+            Self::Io(path, err) => write!(f, "error: {}: {}", path, err),
+            Self::Parse(err) => write!(f, "{}", err),
+            Self::Aiger(path, err) => write!(f, "error: {}:{}", path, err),
+            Self::Aig(err) => write!(f, "error: {}", err),
+            Self::Query(err) => write!(f, "error: {}", err),
+            Self::InvalidConstraint(constraint) => write!(f, "error: invalid constraint `{}`", constraint),
+            Self::Loop(err) => write!(f, "error: {}", err),
+            Self::UnknownModel(name) => write!(f, "error: no model named `{}`", name),
+            Self::UnresolvedSubckt { model, subckt } => {
+                write!(f, "error: model `{}` instantiates unknown model `{}`", model, subckt)
+            }
+            Self::InvalidVector(vector) => write!(f, "error: invalid input vector `{}`", vector),
+            Self::TooManyInputs { model, inputs, max } => {
+                write!(f, "error: model `{}` has {} inputs, more than the maximum of {} (see --max-inputs)", model, inputs, max)
+            }
+            Self::Stimulus(path, err) => write!(f, "error: {}:{}", path, err),
+            Self::TestFailed => write!(f, "error: outputs did not match the stimulus"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io("<stdout>".into(), err)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Command {
+    Help,
+    Check,
+    Sim,
+    TruthTable,
+    Write,
+    Verilog,
+    Dot,
+    Stats,
+    Sweep,
+    Aiger,
+    Cnf,
+    Sat,
+    Test,
+}
+
+/// Commands by name, `check` also being `parse`.
+const COMMANDS: &[(&str, Command)] = &[
+    ("check", Command::Check),
+    ("parse", Command::Check),
+    ("sim", Command::Sim),
+    ("truth-table", Command::TruthTable),
+    ("write", Command::Write),
+    ("verilog", Command::Verilog),
+    ("dot", Command::Dot),
+    ("stats", Command::Stats),
+    ("sweep", Command::Sweep),
+    ("aiger", Command::Aiger),
+    ("cnf", Command::Cnf),
+    ("sat", Command::Sat),
+    ("test", Command::Test),
+];
+
+impl Command {
+    /// What the command takes after the BLIF file, if anything.
+    fn operands(&self) -> Option<&'static str> {
+        match self {
+            Self::Sim => Some("input vectors"),
+            Self::Dot => Some("an input vector"),
+            Self::Test => Some("a stimulus file"),
+            Self::Sat => Some("constraints"),
+            _ => None,
+        }
+    }
+}
+
+enum Format {
+    Table,
+    Csv,
+    Pla,
+}
+
+struct Options {
+    command: Command,
+    path: String,
+    model: Option<String>,
+    clock: Option<String>,
+    vcd: Option<String>,
+    max_inputs: usize,
+    format: Format,
+    sweep: bool,
+    minimise: bool,
+    binary: bool,
+    vectors: Vec<String>,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Self, Error> {
+        let mut args = args.iter();
+
+        let command = match args.next().map(|arg| arg.as_str()) {
+            Some("-h") | Some("--help") | Some("help") => Command::Help,
+            Some(command) => COMMANDS.iter()
+                .find(|(name, _)| *name == command)
+                .map(|(_, command)| *command)
+                .ok_or_else(|| Error::Usage(format!("unknown command `{}`", command)))?,
+            None => return Err(Error::Usage("missing command".into())),
+        };
+
+        let mut options = Self {
+            command,
+            path: String::new(),
+            model: None,
+            clock: None,
+            vcd: None,
+            max_inputs: DEFAULT_MAX_INPUTS,
+            format: Format::Table,
+            sweep: false,
+            minimise: false,
+            binary: false,
+            vectors: Vec::new(),
+        };
+
+        let mut positionals = Vec::new();
+
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next().cloned().ok_or_else(|| Error::Usage(format!("missing value for `{}`", arg)))
+            };
+
+            match arg.as_str() {
+                "-m" | "--model" => options.model = Some(value()?),
+                "-c" | "--clock" => options.clock = Some(value()?),
+                "--vcd" => options.vcd = Some(value()?),
+                "--max-inputs" => {
+                    let max = value()?;
+                    options.max_inputs = max.parse()
+                        .map_err(|_| Error::Usage(format!("invalid value `{}` for `--max-inputs`", max)))?;
+                }
+                "--format" => {
+                    options.format = match value()?.as_str() {
+                        "table" => Format::Table,
+                        "csv" => Format::Csv,
+                        "pla" => Format::Pla,
+                        format => return Err(Error::Usage(format!("invalid value `{}` for `--format`", format))),
+                    };
+                }
+                "--sweep" => options.sweep = true,
+                "--minimise" => options.minimise = true,
+                "--binary" => options.binary = true,
+                "-h" | "--help" => options.command = Command::Help,
+                _ if arg.starts_with('-') && arg.len() > 1 => {
+                    return Err(Error::Usage(format!("unknown option `{}`", arg)));
+                }
+                _ => positionals.push(arg.clone()),
+            }
+        }
+
+        if let Command::Help = options.command {
+            return Ok(options);
+        }
+
+        let mut positionals = positionals.into_iter();
+        options.path = positionals.next().ok_or_else(|| Error::Usage("missing BLIF file".into()))?;
+        options.vectors = positionals.collect();
+
+        match options.command {
+            Command::Sim => (),
+            Command::Test if options.vectors.len() != 1 => {
+                return Err(Error::Usage("`test` expects a single stimulus file".into()));
+            }
+            Command::Test => (),
+            Command::Dot if options.vectors.len() > 1 => {
+                return Err(Error::Usage("`dot` accepts a single input vector".into()));
+            }
+            Command::Dot => (),
+            Command::Sat => (),
+            _ if !options.vectors.is_empty() => {
+                let accepted: Vec<String> = COMMANDS.iter()
+                    .filter_map(|(name, command)| Some(format!("`{}` ({})", name, command.operands()?)))
+                    .collect();
+                let (last, rest) = accepted.split_last().expect("commands taking operands");
+
+                return Err(Error::Usage(format!("only {} and {} take anything after the BLIF file", rest.join(", "), last)));
+            }
+            _ => (),
+        }
+
+        Ok(options)
+    }
+}
+
+fn load(path: &str) -> Result<Blif, Error> {
+    if path.ends_with(".aag") || path.ends_with(".aig") {
+        let bytes = std::fs::read(path).map_err(|err| Error::Io(path.into(), err))?;
+        let name = std::path::Path::new(path).file_stem().map_or("aiger".into(), |stem| stem.to_string_lossy());
+        let aig = read_aiger(&bytes, &name).map_err(|err| Error::Aiger(path.into(), err))?;
+
+        return Ok(Blif::new(vec![aig.to_model()]));
+    }
+
+    let source = std::fs::read_to_string(path).map_err(|err| Error::Io(path.into(), err))?;
+
+    blif::parse(&source).map_err(|err| Error::Parse(err.with_path(path)))
+}
+
+fn select<'a>(blif: &'a Blif, name: &Option<String>) -> Result<&'a Model, Error> {
+    match name {
+        Some(name) => blif.model(name).ok_or_else(|| Error::UnknownModel(name.clone())),
+        None => blif.top().ok_or_else(|| Error::UnknownModel("<top>".into())),
+    }
+}
+
+fn check(blif: &Blif, out: &mut dyn Write) -> Result<(), Error> {
+    blif.check().map_err(Error::Loop)?;
+
+    let top = blif.top().map(|top| top.name.clone());
+
+    for model in blif.models() {
+        if let Some(subckt) = model.subckts.iter().find(|subckt| !subckt.is_resolved()) {
+            return Err(Error::UnresolvedSubckt { model: model.name.clone(), subckt: subckt.model.clone() });
+        }
+
+        writeln!(
+            out,
+            "model {}{}: {} inputs, {} outputs, {} gates, {} subckts, {} latches",
+            model.name,
+            if Some(&model.name) == top.as_ref() { " (top)" } else { "" },
+            model.inputs.len(),
+            model.outputs.len(),
+            model.gates.len(),
+            model.subckts.len(),
+            model.latches.len(),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Bind a vector such as `10xz` to `inputs`.
+fn parse_vector(vector: &str, inputs: &[&String]) -> Result<Signals, Error> {
+    let states = vector.chars()
+        .map(SignalState::try_from)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| Error::InvalidVector(vector.into()))?;
+
+    if states.len() != inputs.len() {
+        return Err(Error::InvalidVector(vector.into()));
+    }
+
+    Ok(std::iter::zip(inputs, states).fold(SignalsBuilder::new(), |builder, (input, state)| {
+        builder.add_signal(input, state)
+    }).build())
+}
+
+fn format_outputs(model: &Model, signals: &Signals) -> String {
+    model.outputs.iter().map(|output| signals.get(output).to_string()).collect()
+}
+
+fn sim(model: &Model, options: &Options, input: &mut dyn BufRead, out: &mut dyn Write) -> Result<(), Error> {
+    model.check().map_err(Error::Loop)?;
+
+    let vectors = if options.vectors.is_empty() {
+        input.lines()
+            .map(|line| line.map(|line| line.trim().to_string()))
+            .filter(|line| !matches!(line, Ok(line) if line.is_empty()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| Error::Io("<stdin>".into(), err))?
+    } else {
+        options.vectors.clone()
+    };
+
+    let inputs: Vec<&String> = model.inputs.iter()
+        .filter(|input| Some(*input) != options.clock.as_ref())
+        .collect();
+
+    let mut stepper = Stepper::new(model);
+    let mut recorder = TraceRecorder::new(model);
+
+    for (time, vector) in vectors.iter().enumerate() {
+        let signals = parse_vector(vector, &inputs)?;
+
+        let res = match &options.clock {
+            Some(clock) => stepper.tick(clock, signals),
+            None if !model.state_nets().is_empty() => stepper.step(signals),
+            None => model.stim(signals),
+        };
+
+        writeln!(out, "{} {}", vector, format_outputs(model, &res))?;
+
+        recorder.record(time as u64, &res);
+    }
+
+    if let Some(path) = &options.vcd {
+        let mut file = std::fs::File::create(path).map_err(|err| Error::Io(path.clone(), err))?;
+        recorder.write_vcd(&mut file, "1ns").map_err(|err| Error::Io(path.clone(), err))?;
+    }
+
+    Ok(())
+}
+
+fn truth_table(model: &Model, options: &Options, out: &mut dyn Write) -> Result<(), Error> {
+    let table = TruthTable::new(model, options.max_inputs).map_err(|err| match err {
+        TruthTableError::Loop(err) => Error::Loop(err),
+        TruthTableError::TooManyInputs { model, inputs, max } => Error::TooManyInputs { model, inputs, max },
+    })?;
+
+    match options.format {
+        Format::Table => table.write_table(out)?,
+        Format::Csv => table.write_csv(out)?,
+        Format::Pla => table.write_pla(out)?,
+    }
+
+    Ok(())
+}
+
+fn test(model: &Model, path: &str, out: &mut dyn Write) -> Result<(), Error> {
+    model.check().map_err(Error::Loop)?;
+
+    let source = std::fs::read_to_string(path).map_err(|err| Error::Io(path.into(), err))?;
+    let report = Stimulus::parse(&source)
+        .and_then(|stimulus| stimulus.run(model))
+        .map_err(|err| Error::Stimulus(path.into(), err))?;
+
+    for mismatch in &report.mismatches {
+        writeln!(out, "{}", mismatch)?;
+    }
+    writeln!(out, "{}", report)?;
+
+    if report.passed() { Ok(()) } else { Err(Error::TestFailed) }
+}
+
+fn stats(blif: &Blif, out: &mut dyn Write) -> Result<(), Error> {
+    for (i, model) in blif.models().iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+
+        writeln!(out, "{}", Stats::new(model))?;
+
+        for lint in lint(model) {
+            writeln!(out, "  warning: {}", lint)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn sweep(blif: &Blif, out: &mut dyn Write) -> Result<(), Error> {
+    let (swept, sweeps) = blif.sweep();
+
+    for (model, sweep) in sweeps {
+        writeln!(out, "# {}: {}", model, sweep)?;
+    }
+    write!(out, "{}", blif::write(&swept))?;
+
+    Ok(())
+}
+
+fn aiger(model: &Model, options: &Options, out: &mut dyn Write) -> Result<(), Error> {
+    let aig = Aig::from_model(model).map_err(Error::Aig)?;
+
+    if options.binary {
+        write_aig(out, &aig)?;
+    } else {
+        write_aag(out, &aig)?;
+    }
+
+    Ok(())
+}
+
+fn cnf(model: &Model, out: &mut dyn Write) -> Result<(), Error> {
+    Encoding::new(model).map_err(Error::Aig)?.write_dimacs(out)?;
+
+    Ok(())
+}
+
+fn sat(model: &Model, options: &Options, out: &mut dyn Write) -> Result<(), Error> {
+    let constraints = options.vectors.iter().try_fold(SignalsBuilder::new(), |builder, constraint| {
+        match constraint.split_once('=') {
+            Some((net, "0")) => Ok(builder.add_signal(net, SignalState::Low)),
+            Some((net, "1")) => Ok(builder.add_signal(net, SignalState::High)),
+            _ => Err(Error::InvalidConstraint(constraint.clone())),
+        }
+    })?.build();
+
+    let encoding = Encoding::new(model).map_err(Error::Aig)?;
+
+    match encoding.satisfy(&constraints).map_err(Error::Query)? {
+        Some(signals) => {
+            writeln!(out, "satisfiable")?;
+            for (name, _) in &encoding.signals {
+                writeln!(out, "{}={}", name, signals.get(name))?;
+            }
+        }
+        None => writeln!(out, "unsatisfiable")?,
+    }
+
+    Ok(())
+}
+
+fn dot(model: &Model, options: &Options, out: &mut dyn Write) -> Result<(), Error> {
+    let values = match options.vectors.first() {
+        Some(vector) => {
+            model.check().map_err(Error::Loop)?;
+
+            let inputs: Vec<&String> = model.inputs.iter().collect();
+            Some(model.stim(parse_vector(vector, &inputs)?))
+        }
+        None => None,
+    };
+
+    write_dot(out, model, values.as_ref())?;
+
+    Ok(())
+}
+
+pub fn run(args: &[String], input: &mut dyn BufRead, out: &mut dyn Write) -> Result<(), Error> {
+    let options = Options::parse(args)?;
+
+    if let Command::Help = options.command {
+        writeln!(out, "{}", USAGE)?;
+        return Ok(());
+    }
+
+    let mut blif = load(&options.path)?;
+    if options.sweep {
+        blif = blif.sweep().0;
+    }
+    if options.minimise {
+        blif = blif.minimise();
+    }
+
+    match options.command {
+        Command::Help => unreachable!(),
+        Command::Check => check(&blif, out),
+        Command::Sim => sim(select(&blif, &options.model)?, &options, input, out),
+        Command::TruthTable => truth_table(select(&blif, &options.model)?, &options, out),
+        Command::Test => test(select(&blif, &options.model)?, &options.vectors[0], out),
+        Command::Write => write!(out, "{}", blif::write(&blif)).map_err(Error::from),
+        Command::Verilog => write_verilog(out, &blif).map_err(Error::from),
+        Command::Dot => dot(select(&blif, &options.model)?, &options, out),
+        Command::Stats => stats(&blif, out),
+        Command::Sweep => sweep(&blif, out),
+        Command::Aiger => aiger(select(&blif, &options.model)?, &options, out),
+        Command::Cnf => cnf(select(&blif, &options.model)?, out),
+        Command::Sat => sat(select(&blif, &options.model)?, &options, out),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_with(args: &[&str], stdin: &str) -> Result<String, Error> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        let mut out = Vec::new();
+
+        run(&args, &mut stdin.as_bytes(), &mut out)?;
+
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_check() {
+        let out = run_with(&["check", "fixtures/med.blif"], "").unwrap();
+
+        assert_eq!(out, concat!(
+            "model a_not_b: 2 inputs, 1 outputs, 7 gates, 0 subckts, 0 latches\n",
+            "model top (top): 2 inputs, 2 outputs, 4 gates, 2 subckts, 0 latches\n",
+        ));
+    }
+
+    #[test]
+    fn test_check_missing_file() {
+        let err = run_with(&["check", "fixtures/missing.blif"], "").unwrap_err();
+
+        assert_eq!(err.exit_code(), 1);
+    }
+
+    #[test]
+    fn test_usage() {
+        assert_eq!(run_with(&[], "").unwrap_err().exit_code(), 2);
+        assert_eq!(run_with(&["frobnicate", "fixtures/smol.blif"], "").unwrap_err().exit_code(), 2);
+        assert_eq!(run_with(&["check", "--model"], "").unwrap_err().exit_code(), 2);
+
+        let err = run_with(&["stats", "fixtures/smol.blif", "00"], "").unwrap_err();
+        assert!(err.to_string().starts_with(
+            "error: only `sim` (input vectors), `dot` (an input vector), `sat` (constraints) and `test` (a stimulus file) take anything after the BLIF file\n",
+        ));
+    }
+
+    #[test]
+    fn test_sim_vectors() {
+        let out = run_with(&["sim", "fixtures/smol.blif", "00", "10", "11"], "").unwrap();
+
+        assert_eq!(out, "00 0\n10 1\n11 0\n");
+    }
+
+    #[test]
+    fn test_sim_stdin() {
+        let out = run_with(&["sim", "-m", "a_not_b", "fixtures/med.blif"], "10\n\n01\n").unwrap();
+
+        assert_eq!(out, "10 1\n01 0\n");
+    }
+
+    #[test]
+    fn test_sim_clock() {
+        let out = run_with(&["sim", "--clock", "clk", "fixtures/counter.blif", "", "", ""], "").unwrap();
+
+        assert_eq!(out, " 10\n 01\n 11\n");
+    }
+
+    #[test]
+    fn test_sim_hierarchical_state() {
+        // The latches are all inside instances, ticked by the `clk` of each vector.
+        let out = run_with(&["sim", "fixtures/tff_counter.blif", "0", "1", "0", "1", "0", "1"], "").unwrap();
+
+        assert_eq!(out, "0 00\n1 10\n0 10\n1 01\n0 01\n1 11\n");
+    }
+
+    #[test]
+    fn test_sim_vcd() {
+        let path = std::env::temp_dir().join(format!("garnierisator-{}.vcd", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        run_with(&["sim", "--vcd", path, "fixtures/smol.blif", "00", "10"], "").unwrap();
+
+        let vcd = std::fs::read_to_string(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert!(vcd.contains("$var wire 1 # o_led $end\n"));
+        assert!(vcd.ends_with("#1\n1!\n1#\n"));
+    }
+
+    #[test]
+    fn test_sim_invalid_vector() {
+        let err = run_with(&["sim", "fixtures/smol.blif", "1"], "").unwrap_err();
+
+        assert_eq!(err.to_string(), "error: invalid input vector `1`");
+    }
+
+    #[test]
+    fn test_truth_table() {
+        let out = run_with(&["truth-table", "fixtures/smol.blif"], "").unwrap();
+
+        assert_eq!(out, concat!(
+            "i_A i_B | o_led\n",
+            "00 | 0\n",
+            "01 | 0\n",
+            "10 | 1\n",
+            "11 | 0\n",
+        ));
+    }
+
+    #[test]
+    fn test_test_counter() {
+        let out = run_with(&["test", "fixtures/counter.blif", "fixtures/counter.stim"], "").unwrap();
+
+        assert_eq!(out, "PASS: 5 vectors\n");
+    }
+
+    #[test]
+    fn test_test_usage() {
+        assert_eq!(run_with(&["test", "fixtures/counter.blif"], "").unwrap_err().exit_code(), 2);
+        assert_eq!(run_with(&["test", "fixtures/smol.blif", "fixtures/counter.stim"], "").unwrap_err().exit_code(), 1);
+    }
+
+    #[test]
+    fn test_write() {
+        let out = run_with(&["write", "fixtures/smol_crlf.blif"], "").unwrap();
+
+        assert_eq!(blif::parse(&out), blif::parse(include_str!("../fixtures/smol_crlf.blif")));
+        assert!(!out.contains('\r'));
+    }
+
+    #[test]
+    fn test_verilog() {
+        let out = run_with(&["verilog", "fixtures/med.blif"], "").unwrap();
+
+        assert!(out.starts_with("module a_not_b(i_A, i_B, o_led);\n"));
+        assert!(out.contains("\nendmodule\n\nmodule top(A, B, o_m1, o_m2);\n"));
+    }
+
+    #[test]
+    fn test_dot() {
+        let out = run_with(&["dot", "fixtures/smol.blif", "10"], "").unwrap();
+
+        assert!(out.starts_with("digraph \"blinky\" {\n"));
+        assert!(out.contains("  \"g3\" -> \"out:o_led\" [label=\"o_led = 1\"];\n"));
+        assert_eq!(run_with(&["dot", "fixtures/smol.blif", "10", "11"], "").unwrap_err().exit_code(), 2);
+    }
+
+    #[test]
+    fn test_stats() {
+        let out = run_with(&["stats", "fixtures/counter.blif"], "").unwrap();
+
+        assert_eq!(out, concat!(
+            "model counter\n",
+            "  gates: 5 (0: 2, 1: 1, NOT: 1, XOR: 1)\n",
+            "  latches: 2, subckts: 0\n",
+            "  cubes: 4, literals: 5\n",
+            "  depth: 1, max fan-in: 2, max fan-out: 2\n",
+            "  warning: gate output `$false` is never read\n",
+            "  warning: gate output `$true` is never read\n",
+            "  warning: gate output `$undef` is never read\n",
+        ));
+    }
+
+    #[test]
+    fn test_write_minimise() {
+        let path = std::env::temp_dir().join(format!("garnierisator-{}.blif", std::process::id()));
+        let path = path.to_str().unwrap();
+        std::fs::write(path, ".model m\n.inputs a b\n.outputs o\n.names a b o\n0- 1\n1- 1\n-- 1\n01 1\n.end\n").unwrap();
+
+        let out = run_with(&["write", "--minimise", path], "");
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(out.unwrap(), ".model m\n.inputs a b\n.outputs o\n.names a b o\n-- 1\n.end\n");
+    }
+
+    #[test]
+    fn test_sweep() {
+        let out = run_with(&["sweep", "fixtures/smol.blif"], "").unwrap();
+
+        assert_eq!(out, concat!(
+            "# blinky: propagated 0 constants, removed 0 buffers, 1 inverter and 3 dead gates\n",
+            ".model blinky\n",
+            ".inputs i_A i_B\n",
+            ".outputs o_led\n",
+            ".names i_A i_B o_led\n",
+            "10 1\n",
+            ".end\n",
+        ));
+        assert_eq!(blif::parse(&out).unwrap().models().len(), 1);
+    }
+
+    #[test]
+    fn test_aiger() {
+        let out = run_with(&["aiger", "fixtures/smol.blif"], "").unwrap();
+
+        assert_eq!(out, "aag 3 2 0 1 1\n2\n4\n6\n6 5 2\ni0 i_A\ni1 i_B\no0 o_led\n");
+
+        let binary = run_with(&["aiger", "--binary", "fixtures/smol.blif"], "").unwrap();
+        assert!(binary.starts_with("aig 3 2 0 1 1\n6\n"));
+
+        let err = run_with(&["aiger", "-m", "a_not_b", "fixtures/counter.blif"], "").unwrap_err();
+        assert_eq!(err.to_string(), "error: no model named `a_not_b`");
+    }
+
+    #[test]
+    fn test_read_aiger() {
+        let path = std::env::temp_dir().join(format!("garnierisator-{}.aag", std::process::id()));
+        let path = path.to_str().unwrap();
+        std::fs::write(path, "aag 3 2 0 1 1\n2\n4\n6\n6 5 2\ni0 a\ni1 b\no0 y\n").unwrap();
+
+        let out = run_with(&["sim", path, "00", "10", "11"], "");
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(out.unwrap(), "00 0\n10 1\n11 0\n");
+    }
+
+    #[test]
+    fn test_cnf() {
+        let out = run_with(&["cnf", "fixtures/smol.blif"], "").unwrap();
+
+        assert!(out.starts_with("c i_A 2\nc i_B 3\nc o_led 4\np cnf 4 4\n"));
+    }
+
+    #[test]
+    fn test_sat() {
+        let out = run_with(&["sat", "fixtures/med.blif", "o_m2=1", "B=1"], "").unwrap();
+        assert_eq!(out, "unsatisfiable\n");
+
+        let out = run_with(&["sat", "fixtures/med.blif", "o_m2=1"], "").unwrap();
+        assert_eq!(out, "satisfiable\nA=1\nB=0\no_m1=1\no_m2=1\n");
+
+        let err = run_with(&["sat", "fixtures/med.blif", "o_m2"], "").unwrap_err();
+        assert_eq!(err.to_string(), "error: invalid constraint `o_m2`");
+
+        let err = run_with(&["sat", "fixtures/med.blif", "C=1"], "").unwrap_err();
+        assert_eq!(err.to_string(), "error: net `C` is not an input, latch or output of the model");
+    }
+
+    #[test]
+    fn test_truth_table_pla() {
+        let out = run_with(&["truth-table", "--format", "pla", "fixtures/full_adder.blif"], "").unwrap();
+
+        assert!(out.starts_with(".i 3\n.o 2\n.ilb a b cin\n.ob sum cout\n"));
+        assert!(out.contains("\n111 11\n.e\n"));
+    }
+
+    #[test]
+    fn test_truth_table_too_many_inputs() {
+        let err = run_with(&["truth-table", "--max-inputs", "1", "fixtures/smol.blif"], "").unwrap_err();
+
+        assert_eq!(err.exit_code(), 1);
+    }
+}
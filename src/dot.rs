@@ -0,0 +1,273 @@
+use crate::blif::{GateFunction, LogicGate, Model};
+use crate::simulation::{SignalState, Signals};
+
+use std::collections::HashMap;
+use std::io::Write;
+
+/// A net of the drawn hierarchy, port connections being merged with the
+/// net they are bound to.
+struct Net {
+    /// Name in the scope the net was first seen in.
+    label: String,
+    value: Option<SignalState>,
+    drivers: Vec<String>,
+    readers: Vec<String>,
+}
+
+struct Graph<'a> {
+    out: &'a mut dyn Write,
+    nets: Vec<Net>,
+    index: HashMap<String, usize>,
+}
+
+/// Write `model` as a Graphviz digraph.
+///
+/// Primary inputs and outputs, gates, latches and tri-state buffers are
+/// nodes, and each net is drawn as edges from its drivers to its readers,
+/// labelled with its name. Gates are labelled with their `GateFunction`
+/// when it is recognised, and with their cover otherwise. Each `.subckt`
+/// instance is a cluster holding the nodes of its model.
+///
+/// With `values`, such as the result of `Simulable::stim` on `model`, the
+/// edges are also labelled with the value of their net; the nets inside
+/// instances are evaluated from their ports.
+pub fn write_dot(out: &mut dyn Write, model: &Model, values: Option<&Signals>) -> std::io::Result<()> {
+    writeln!(out, "digraph {} {{", quote(&model.name))?;
+    writeln!(out, "  rankdir=LR;")?;
+    writeln!(out, "  node [fontname=\"monospace\"];")?;
+
+    let mut graph = Graph { out, nets: Vec::new(), index: HashMap::new() };
+
+    for input in &model.inputs {
+        let node = format!("in:{}", input);
+        writeln!(graph.out, "  {} [shape=invhouse, label={}];", quote(&node), quote(input))?;
+
+        let net = graph.net("", &HashMap::new(), input, values);
+        graph.nets[net].drivers.push(node);
+    }
+
+    for output in &model.outputs {
+        let node = format!("out:{}", output);
+        writeln!(graph.out, "  {} [shape=house, label={}];", quote(&node), quote(output))?;
+
+        let net = graph.net("", &HashMap::new(), output, values);
+        graph.nets[net].readers.push(node);
+    }
+
+    graph.model(model, "", &HashMap::new(), values, 1)?;
+
+    for (i, net) in graph.nets.iter().enumerate() {
+        let label = match net.value {
+            Some(value) => format!("{} = {}", net.label, value),
+            None => net.label.clone(),
+        };
+
+        // Undriven nets start from a point, so that their readers still show.
+        let drivers = if net.drivers.is_empty() && !net.readers.is_empty() {
+            let node = format!("net:{}", i);
+            writeln!(graph.out, "  {} [shape=point];", quote(&node))?;
+            vec![node]
+        } else {
+            net.drivers.clone()
+        };
+
+        for driver in &drivers {
+            for reader in &net.readers {
+                writeln!(graph.out, "  {} -> {} [label={}];", quote(driver), quote(reader), quote(&label))?;
+            }
+        }
+    }
+
+    writeln!(graph.out, "}}")
+}
+
+impl Graph<'_> {
+    /// Index of net `name` of the scope `prefix`, whose ports are `bound`.
+    fn net(&mut self, prefix: &str, bound: &HashMap<&str, usize>, name: &str, values: Option<&Signals>) -> usize {
+        if let Some(net) = bound.get(name) {
+            return *net;
+        }
+
+        let key = format!("{}{}", prefix, name);
+        if let Some(net) = self.index.get(&key) {
+            return *net;
+        }
+
+        self.nets.push(Net {
+            label: name.to_string(),
+            value: values.map(|values| values.get(name)),
+            drivers: Vec::new(),
+            readers: Vec::new(),
+        });
+        self.index.insert(key, self.nets.len() - 1);
+
+        self.nets.len() - 1
+    }
+
+    fn model(&mut self, model: &Model, prefix: &str, bound: &HashMap<&str, usize>, values: Option<&Signals>, depth: usize) -> std::io::Result<()> {
+        let indent = "  ".repeat(depth);
+
+        for (i, gate) in model.gates.iter().enumerate() {
+            let node = format!("{}g{}", prefix, i);
+            let label = match gate.function() {
+                GateFunction::Cover => quote_lines(&cover(gate)),
+                function => quote(&function.to_string()),
+            };
+            writeln!(self.out, "{}{} [shape=box, label={}];", indent, quote(&node), label)?;
+
+            for input in &gate.inputs {
+                let net = self.net(prefix, bound, input, values);
+                self.nets[net].readers.push(node.clone());
+            }
+
+            let net = self.net(prefix, bound, &gate.output, values);
+            self.nets[net].drivers.push(node);
+        }
+
+        for (i, latch) in model.latches.iter().enumerate() {
+            let node = format!("{}l{}", prefix, i);
+            let (label, control) = match &latch.control {
+                Some((kind, control)) if control != "NIL" => (format!("latch {}", kind), Some(control)),
+                _ => ("latch".to_string(), None),
+            };
+            writeln!(self.out, "{}{} [shape=box, style=rounded, label={}];", indent, quote(&node), quote(&label))?;
+
+            for input in std::iter::once(&latch.input).chain(control) {
+                let net = self.net(prefix, bound, input, values);
+                self.nets[net].readers.push(node.clone());
+            }
+
+            let net = self.net(prefix, bound, &latch.output, values);
+            self.nets[net].drivers.push(node);
+        }
+
+        for (i, subckt) in model.subckts.iter().enumerate() {
+            let name = model.instance_name(i);
+            let node = format!("{}{}", prefix, name);
+
+            match (&subckt.definition, subckt.bindings()) {
+                (Some(definition), Some((inputs, outputs))) => {
+                    let ports: HashMap<&str, usize> = inputs.iter().chain(&outputs)
+                        .map(|(port, actual)| (*port, self.net(prefix, bound, actual, values)))
+                        .collect();
+                    let values = values.map(|values| values.scope(&name));
+
+                    writeln!(self.out, "{}subgraph {} {{", indent, quote(&format!("cluster_{}", node)))?;
+                    writeln!(self.out, "{}  label={};", indent, quote(&format!("{} ({})", name, subckt.model)))?;
+                    self.model(definition, &format!("{}/", node), &ports, values.as_ref(), depth + 1)?;
+                    writeln!(self.out, "{}}}", indent)?;
+                }
+                // `$_TBUF_`, drawn as a single node.
+                (None, Some((inputs, outputs))) => {
+                    writeln!(self.out, "{}{} [shape=box, label={}];", indent, quote(&node), quote(&subckt.model))?;
+
+                    for (_, actual) in inputs {
+                        let net = self.net(prefix, bound, actual, values);
+                        self.nets[net].readers.push(node.clone());
+                    }
+
+                    for (_, actual) in outputs {
+                        let net = self.net(prefix, bound, actual, values);
+                        self.nets[net].drivers.push(node.clone());
+                    }
+                }
+                // Unknown model: the direction of its ports is unknown too.
+                _ => {
+                    writeln!(self.out, "{}{} [shape=box, style=dashed, label={}];", indent, quote(&node), quote(&subckt.model))?;
+
+                    for (_, actual) in &subckt.connections {
+                        let net = self.net(prefix, bound, actual, values);
+                        self.nets[net].readers.push(node.clone());
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The inputs of `gate` then its cover, one row per line.
+fn cover(gate: &LogicGate) -> Vec<String> {
+    let mut lines = vec![gate.inputs.join(" ")];
+
+    lines.extend(gate.single_output_cover.iter().map(|(inputs, output)| {
+        let inputs: String = inputs.iter().map(|input| input.to_string()).collect();
+        format!("{} {}", inputs, output).trim_start().to_string()
+    }));
+
+    lines
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", escape(text))
+}
+
+/// A label of left-justified lines.
+fn quote_lines(lines: &[String]) -> String {
+    let lines: String = lines.iter().map(|line| format!("{}\\l", escape(line))).collect();
+
+    format!("\"{}\"", lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blif;
+    use crate::simulation::{Simulable, SignalsBuilder};
+
+    fn render(model: &Model, values: Option<&Signals>) -> String {
+        let mut out = Vec::new();
+        write_dot(&mut out, model, values).unwrap();
+
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_smol() {
+        let blif = blif::parse(include_str!("../fixtures/smol.blif")).unwrap();
+
+        assert_eq!(render(blif.top().unwrap(), None), concat!(
+            "digraph \"blinky\" {\n",
+            "  rankdir=LR;\n",
+            "  node [fontname=\"monospace\"];\n",
+            "  \"in:i_A\" [shape=invhouse, label=\"i_A\"];\n",
+            "  \"in:i_B\" [shape=invhouse, label=\"i_B\"];\n",
+            "  \"out:o_led\" [shape=house, label=\"o_led\"];\n",
+            "  \"g0\" [shape=box, label=\"0\"];\n",
+            "  \"g1\" [shape=box, label=\"1\"];\n",
+            "  \"g2\" [shape=box, label=\"0\"];\n",
+            "  \"g3\" [shape=box, label=\"AND\"];\n",
+            "  \"g4\" [shape=box, label=\"NOT\"];\n",
+            "  \"in:i_A\" -> \"g3\" [label=\"i_A\"];\n",
+            "  \"in:i_B\" -> \"g4\" [label=\"i_B\"];\n",
+            "  \"g3\" -> \"out:o_led\" [label=\"o_led\"];\n",
+            "  \"g4\" -> \"g3\" [label=\"Y\"];\n",
+            "}\n",
+        ));
+    }
+
+    #[test]
+    fn test_clusters() {
+        let blif = blif::parse(include_str!("../fixtures/med.blif")).unwrap();
+        let top = blif.top().unwrap();
+        let values = top.stim(SignalsBuilder::new()
+            .add_signal("A", SignalState::High)
+            .add_signal("B", SignalState::Low)
+            .build());
+
+        let dot = render(top, Some(&values));
+
+        assert!(dot.contains("  subgraph \"cluster_a_not_b_1\" {\n    label=\"a_not_b_1 (a_not_b)\";\n"), "{}", dot);
+        assert!(dot.contains("    \"a_not_b_1/g3\" [shape=box, label=\"AND\"];\n"), "{}", dot);
+        // Port connections are the nets of the parent.
+        assert!(dot.contains("  \"in:A\" -> \"a_not_b_0/g6\" [label=\"A = 1\"];\n"), "{}", dot);
+        assert!(dot.contains("  \"a_not_b_0/g5\" -> \"g3\" [label=\"m2_A = 1\"];\n"), "{}", dot);
+        // Inside the instances, from the values of their ports.
+        assert!(dot.contains("  \"a_not_b_1/g3\" -> \"a_not_b_1/g5\" [label=\"$logic_and$fixtures/med.v:2$3_Y = 1\"];\n"), "{}", dot);
+    }
+}
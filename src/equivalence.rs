@@ -0,0 +1,392 @@
+use crate::aig::AigError;
+use crate::blif::{CombinationalLoop, InputValue, LogicGate, Model, Subckt};
+use crate::netlist::parallel::{Lanes, ParallelEvaluator, LANES};
+use crate::netlist::{NetId, Netlist};
+use crate::rng::Rng;
+use crate::simulation::{SignalState, Signals, SignalsBuilder};
+use crate::tseitin::Encoding;
+
+/// Models with at most this many inputs are checked on every input vector.
+pub const EXHAUSTIVE_INPUTS: usize = 16;
+
+/// Random patterns tried before solving larger models, in batches of `LANES`.
+const RANDOM_BATCHES: usize = 64;
+
+/// Output of the miter, high when the two models differ.
+pub const MITER_OUTPUT: &str = "$miter";
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum EquivalenceError {
+    Loop(CombinationalLoop),
+    /// The models do not have the same set of inputs (or outputs).
+    PortMismatch { kind: &'static str, only_a: Vec<String>, only_b: Vec<String> },
+    /// One of the models holds latches.
+    Sequential(String),
+    /// Models with more than `EXHAUSTIVE_INPUTS` inputs which may compute
+    /// unknown or high-impedance values, and so cannot be encoded for SAT.
+    Unsupported(AigError),
+}
+
+impl std::fmt::Display for EquivalenceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Loop(err) => write!(f, "{}", err),
+            Self::PortMismatch { kind, only_a, only_b } => {
+                write!(f, "the models have different {}", kind)?;
+                if !only_a.is_empty() {
+                    write!(f, ", only the first has {}", only_a.join(" "))?;
+                }
+                if !only_b.is_empty() {
+                    write!(f, ", only the second has {}", only_b.join(" "))?;
+                }
+                Ok(())
+            }
+            Self::Sequential(model) => write!(f, "model `{}` holds latches, only combinational models can be compared", model),
+            Self::Unsupported(err) => {
+                write!(f, "{}, models with more than {} inputs can only be compared when two-valued", err, EXHAUSTIVE_INPUTS)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EquivalenceError {}
+
+#[derive(Debug, PartialEq)]
+pub enum Equivalence {
+    Equivalent,
+    /// An input vector for which some output differs.
+    Different(Signals),
+}
+
+fn same_ports(kind: &'static str, a: &[String], b: &[String]) -> Result<(), EquivalenceError> {
+    let only_a: Vec<String> = a.iter().filter(|port| !b.contains(port)).cloned().collect();
+    let only_b: Vec<String> = b.iter().filter(|port| !a.contains(port)).cloned().collect();
+
+    if only_a.is_empty() && only_b.is_empty() {
+        Ok(())
+    } else {
+        Err(EquivalenceError::PortMismatch { kind, only_a, only_b })
+    }
+}
+
+/// The nets of the miter carrying output `output` of the first and second model.
+fn miter_nets(output: &str) -> (String, String) {
+    (format!("$a${}", output), format!("$b${}", output))
+}
+
+/// A model instantiating `a` and `b` on the same inputs, whose only output
+/// `MITER_OUTPUT` is high when some output of `a` differs from the output of
+/// `b` of the same name.
+///
+/// Ports are matched by name, whatever their order.
+pub fn miter(a: &Model, b: &Model) -> Result<Model, EquivalenceError> {
+    same_ports("inputs", &a.inputs, &b.inputs)?;
+    same_ports("outputs", &a.outputs, &b.outputs)?;
+
+    let mut model = Model::new(format!("miter_{}_{}", a.name, b.name), a.inputs.clone(), vec![MITER_OUTPUT.into()], Vec::new());
+
+    for (side, definition) in [(0, a), (1, b)] {
+        let connections = definition.inputs.iter().map(|input| (input.clone(), input.clone()))
+            .chain(definition.outputs.iter().map(|output| {
+                let (a, b) = miter_nets(output);
+                (output.clone(), if side == 0 { a } else { b })
+            }))
+            .collect();
+
+        let mut subckt = Subckt::new(definition.name.clone(), connections);
+        subckt.definition = Some(Box::new(definition.clone()));
+        model.subckts.push(subckt);
+    }
+
+    // One XOR per output, then their OR.
+    let mut differences = Vec::new();
+    for output in &a.outputs {
+        let (a, b) = miter_nets(output);
+        let difference = format!("$diff${}", output);
+
+        model.gates.push(LogicGate::new(vec![a, b], difference.clone(), vec![
+            (vec![InputValue::Uncomplemented, InputValue::Complemented], InputValue::Uncomplemented),
+            (vec![InputValue::Complemented, InputValue::Uncomplemented], InputValue::Uncomplemented),
+        ]));
+        differences.push(difference);
+    }
+
+    let rows = (0..differences.len()).map(|i| {
+        let mut row = vec![InputValue::NotUsed; differences.len()];
+        row[i] = InputValue::Uncomplemented;
+        (row, InputValue::Uncomplemented)
+    }).collect();
+    model.gates.push(LogicGate::new(differences, MITER_OUTPUT.into(), rows));
+
+    Ok(model)
+}
+
+/// Decide whether `a` and `b` compute the same outputs for every input vector.
+///
+/// Models with up to `EXHAUSTIVE_INPUTS` inputs are simulated on every input
+/// vector, 64 at a time. Larger ones are first tried on random vectors, then
+/// the Tseitin encoding of the miter is solved for a high `MITER_OUTPUT`.
+///
+/// Outputs left unknown by a complete input vector, by undriven nets for
+/// instance, must be unknown in both models. Only small models can compute
+/// such values: the encoding of larger ones is two-valued, so they are
+/// refused.
+pub fn check_equivalence(a: &Model, b: &Model) -> Result<Equivalence, EquivalenceError> {
+    if let Some(model) = [a, b].into_iter().find(|model| has_latches(model)) {
+        return Err(EquivalenceError::Sequential(model.name.clone()));
+    }
+
+    let miter = miter(a, b)?;
+    let netlist = Netlist::new(&miter).map_err(EquivalenceError::Loop)?;
+
+    let checker = Checker::new(&netlist, a);
+
+    let found = if a.inputs.len() <= EXHAUSTIVE_INPUTS {
+        checker.exhaustive()
+    } else {
+        let encoding = Encoding::new(&miter).map_err(EquivalenceError::Unsupported)?;
+        checker.random().or_else(|| checker.solve(&encoding))
+    };
+
+    Ok(match found {
+        Some(vector) => Equivalence::Different(checker.signals(&vector)),
+        None => Equivalence::Equivalent,
+    })
+}
+
+fn has_latches(model: &Model) -> bool {
+    !model.latches.is_empty() || model.subckts.iter().any(|subckt| {
+        subckt.definition.as_ref().is_some_and(|definition| has_latches(definition))
+    })
+}
+
+struct Checker<'a> {
+    netlist: &'a Netlist,
+    miter: NetId,
+    /// `(first, second)` nets of each output.
+    outputs: Vec<(NetId, NetId)>,
+}
+
+impl<'a> Checker<'a> {
+    fn new(netlist: &'a Netlist, a: &Model) -> Self {
+        let outputs = a.outputs.iter().map(|output| {
+            let (a, b) = miter_nets(output);
+            (netlist.net(&a).unwrap(), netlist.net(&b).unwrap())
+        }).collect();
+
+        Self { netlist, miter: netlist.net(MITER_OUTPUT).unwrap(), outputs }
+    }
+
+    fn signals(&self, vector: &[SignalState]) -> Signals {
+        std::iter::zip(&self.netlist.inputs, vector).fold(SignalsBuilder::new(), |builder, (net, state)| {
+            builder.add_signal(self.netlist.net_name(*net), *state)
+        }).build()
+    }
+
+    /// Lanes of `evaluator` where the outputs of the models differ.
+    fn differing(&self, evaluator: &ParallelEvaluator) -> u64 {
+        let miter = evaluator.get(self.miter);
+        let high = miter.high & !miter.low;
+        let undecided = !(miter.high ^ miter.low);
+
+        // Unknown outputs only differ when they are not unknown on both sides.
+        let mismatched = self.outputs.iter().fold(0, |mismatched, (a, b)| {
+            let (a, b) = (evaluator.get(*a), evaluator.get(*b));
+            mismatched | (a.high ^ b.high) | (a.low ^ b.low)
+        });
+
+        high | (undecided & mismatched)
+    }
+
+    /// Run `patterns` (one `Lanes` per input) and return the first differing vector.
+    fn simulate(&self, evaluator: &mut ParallelEvaluator, patterns: &[Lanes], lanes: usize) -> Option<Vec<SignalState>> {
+        evaluator.set_inputs(patterns);
+        evaluator.evaluate();
+
+        let differing = self.differing(evaluator) & if lanes == LANES { !0 } else { (1 << lanes) - 1 };
+        if differing == 0 {
+            return None;
+        }
+
+        let lane = differing.trailing_zeros() as usize;
+        Some(patterns.iter().map(|lanes| lanes.get(lane)).collect())
+    }
+
+    fn exhaustive(&self) -> Option<Vec<SignalState>> {
+        let n = self.netlist.inputs.len();
+        let mut evaluator = ParallelEvaluator::new(self.netlist);
+
+        (0..1u64 << n).step_by(LANES).find_map(|first| {
+            let patterns: Vec<Lanes> = (0..n).map(|i| {
+                Lanes::from_bits((0..LANES as u64).fold(0, |bits, lane| bits | ((first + lane) >> i & 1) << lane))
+            }).collect();
+
+            self.simulate(&mut evaluator, &patterns, LANES.min((1 << n) - first as usize))
+        })
+    }
+
+    fn random(&self) -> Option<Vec<SignalState>> {
+        let mut evaluator = ParallelEvaluator::new(self.netlist);
+
+        let mut rng = Rng::new(0x2545_f491_4f6c_dd1d);
+
+        (0..RANDOM_BATCHES).find_map(|_| {
+            let patterns: Vec<Lanes> = self.netlist.inputs.iter().map(|_| Lanes::from_bits(rng.next_u64())).collect();
+
+            self.simulate(&mut evaluator, &patterns, LANES)
+        })
+    }
+
+    /// An input vector for which `encoding`, of the miter, has a high output.
+    fn solve(&self, encoding: &Encoding) -> Option<Vec<SignalState>> {
+        let constraint = SignalsBuilder::new().add_signal(MITER_OUTPUT, SignalState::High).build();
+        let witness = encoding.satisfy(&constraint).expect("the miter output is encoded")?;
+
+        Some(self.netlist.inputs.iter().map(|net| witness.get(self.netlist.net_name(*net))).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blif;
+    use crate::simulation::Simulable;
+
+    fn full_adder() -> Model {
+        blif::parse(include_str!("../fixtures/full_adder.blif")).unwrap().top().unwrap().clone()
+    }
+
+    /// `sum` and `cout` as a single cover each, with the outputs swapped.
+    fn full_adder_covers() -> Model {
+        let blif = blif::parse(concat!(
+            ".model full_adder_covers\n",
+            ".inputs cin b a\n",
+            ".outputs cout sum\n",
+            ".names a b cin sum\n",
+            "100 1\n010 1\n001 1\n111 1\n",
+            ".names a b cin cout\n",
+            "11- 1\n1-1 1\n-11 1\n",
+            ".end\n",
+        )).unwrap();
+
+        blif.top().unwrap().clone()
+    }
+
+    #[test]
+    fn test_equivalent() {
+        assert_eq!(check_equivalence(&full_adder(), &full_adder_covers()), Ok(Equivalence::Equivalent));
+    }
+
+    #[test]
+    fn test_counterexample() {
+        let mut broken = full_adder_covers();
+        // cout misses a & b & !cin.
+        broken.gates[1].single_output_cover[0].0[2] = InputValue::Uncomplemented;
+
+        let vector = match check_equivalence(&full_adder(), &broken) {
+            Ok(Equivalence::Different(vector)) => vector,
+            res => panic!("{:?}", res),
+        };
+
+        assert_eq!(full_adder().stim(vector.clone()).get("cout"), SignalState::High);
+        assert_eq!(broken.stim(vector).get("cout"), SignalState::Low);
+    }
+
+    #[test]
+    fn test_port_mismatch() {
+        let blif = blif::parse(include_str!("../fixtures/smol.blif")).unwrap();
+
+        assert!(matches!(
+            check_equivalence(&full_adder(), blif.top().unwrap()),
+            Err(EquivalenceError::PortMismatch { kind: "inputs", .. }),
+        ));
+    }
+
+    /// Two `n`-input parity trees, chained and balanced, the first one
+    /// inverted on input `flip` when there is one.
+    fn parities(n: usize, flip: Option<usize>) -> (Model, Model) {
+        let inputs: Vec<String> = (0..n).map(|i| format!("i{}", i)).collect();
+        let xor = |a: String, b: String, y: String| LogicGate::new(vec![a, b], y, vec![
+            (vec![InputValue::Uncomplemented, InputValue::Complemented], InputValue::Uncomplemented),
+            (vec![InputValue::Complemented, InputValue::Uncomplemented], InputValue::Uncomplemented),
+        ]);
+
+        let mut chain = Model::new("chain".into(), inputs.clone(), vec!["y".into()], Vec::new());
+        let mut previous = inputs[0].clone();
+        for (i, input) in inputs.iter().enumerate().skip(1) {
+            let output = if i == n - 1 { "y".to_string() } else { format!("c{}", i) };
+            chain.gates.push(xor(previous, input.clone(), output.clone()));
+            previous = output;
+        }
+
+        let mut tree = Model::new("tree".into(), inputs.clone(), vec!["y".into()], Vec::new());
+        let mut level: Vec<String> = inputs.clone();
+        if let Some(flip) = flip {
+            tree.gates.push(LogicGate::new(vec![inputs[flip].clone()], "flipped".into(), vec![
+                (vec![InputValue::Complemented], InputValue::Uncomplemented),
+            ]));
+            level[flip] = "flipped".into();
+        }
+        let mut count = 0;
+        while level.len() > 1 {
+            level = level.chunks(2).map(|pair| match pair {
+                [a, b] => {
+                    count += 1;
+                    let output = format!("t{}", count);
+                    tree.gates.push(xor(a.clone(), b.clone(), output.clone()));
+                    output
+                }
+                [a] => a.clone(),
+                _ => unreachable!(),
+            }).collect();
+        }
+        tree.gates.push(LogicGate::new(vec![level[0].clone()], "y".into(), vec![
+            (vec![InputValue::Uncomplemented], InputValue::Uncomplemented),
+        ]));
+
+        (chain, tree)
+    }
+
+    #[test]
+    fn test_solve() {
+        let (chain, tree) = parities(17, None);
+        assert_eq!(check_equivalence(&chain, &tree), Ok(Equivalence::Equivalent));
+
+        // Far too many inputs to enumerate.
+        let (chain, tree) = parities(64, None);
+        assert_eq!(check_equivalence(&chain, &tree), Ok(Equivalence::Equivalent));
+
+        let (chain, tree) = parities(64, Some(7));
+        let vector = match check_equivalence(&chain, &tree) {
+            Ok(Equivalence::Different(vector)) => vector,
+            res => panic!("{:?}", res),
+        };
+        assert_ne!(chain.stim(vector.clone()).get("y"), tree.stim(vector).get("y"));
+
+        // Only differing on the all-high vector, which random patterns miss.
+        let inputs: Vec<String> = (0..40).map(|i| format!("i{}", i)).collect();
+        let all = Model::new("all".into(), inputs.clone(), vec!["y".into()], vec![
+            LogicGate::new(inputs.clone(), "y".into(), vec![(vec![InputValue::Uncomplemented; 40], InputValue::Uncomplemented)]),
+        ]);
+        let none = Model::new("none".into(), inputs.clone(), vec!["y".into()], vec![
+            LogicGate::new(inputs, "y".into(), Vec::new()),
+        ]);
+
+        let vector = match check_equivalence(&all, &none) {
+            Ok(Equivalence::Different(vector)) => vector,
+            res => panic!("{:?}", res),
+        };
+        assert!(vector.iter().all(|signal| signal.state() == SignalState::High));
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let (mut chain, tree) = parities(17, None);
+        chain.gates[0].inputs[0] = "undriven".into();
+
+        assert_eq!(
+            check_equivalence(&chain, &tree),
+            Err(EquivalenceError::Unsupported(AigError::Unknown("chain_0/undriven".into()))),
+        );
+    }
+}
@@ -0,0 +1,118 @@
+pub mod blif;
+pub mod simulation;
+pub mod vcd;
+pub mod verilog;
+pub mod dot;
+pub mod testbench;
+pub mod truth_table;
+pub mod netlist;
+pub mod equivalence;
+pub mod stats;
+pub mod bdd;
+pub mod aig;
+pub mod sat;
+pub mod tseitin;
+pub mod cli;
+mod rng;
+
+#[cfg(test)]
+mod tests {
+    use crate::blif::*;
+    use crate::simulation::*;
+
+    #[test]
+    fn test_pipeline_smol() {
+        let smol = include_str!("../fixtures/smol.blif");
+        let blif = parse(smol).unwrap();
+
+        let res = blif.stim(
+            SignalsBuilder::new()
+                .add_signal("i_A", SignalState::Low)
+                .add_signal("i_B", SignalState::Low)
+                .build()
+        );
+        assert_eq!(res.get("o_led"), SignalState::Low);
+
+        let res = blif.stim(
+            SignalsBuilder::new()
+                .add_signal("i_A", SignalState::High)
+                .add_signal("i_B", SignalState::Low)
+                .build()
+        );
+        assert_eq!(res.get("o_led"), SignalState::High);
+
+        let res = blif.stim(
+            SignalsBuilder::new()
+                .add_signal("i_A", SignalState::High)
+                .add_signal("i_B", SignalState::High)
+                .build()
+        );
+        assert_eq!(res.get("o_led"), SignalState::Low);
+    }
+
+    #[test]
+    fn test_pipeline_med() {
+        let smol = include_str!("../fixtures/med.blif");
+        let blif = parse(smol).unwrap();
+
+        let res = blif.stim(
+            SignalsBuilder::new()
+                .add_signal("A", SignalState::High)
+                .add_signal("B", SignalState::Low)
+                .build()
+        );
+
+        assert_eq!(res.get("o_m1"), SignalState::High);
+    }
+
+    #[test]
+    fn test_pipeline_counter() {
+        let counter = include_str!("../fixtures/counter.blif");
+        let blif = parse(counter).unwrap();
+
+        let mut stepper = Stepper::new(&blif);
+        assert_eq!(stepper.state().get("q[0]"), SignalState::Low);
+        assert_eq!(stepper.state().get("q[1]"), SignalState::Low);
+
+        let expected = [
+            (SignalState::High, SignalState::Low),
+            (SignalState::Low, SignalState::High),
+            (SignalState::High, SignalState::High),
+            (SignalState::Low, SignalState::Low),
+        ];
+
+        for (q0, q1) in expected {
+            let res = stepper.tick("clk", Signals::new());
+
+            assert_eq!(res.get("q[0]"), q0);
+            assert_eq!(res.get("q[1]"), q1);
+        }
+
+        assert_eq!(stepper.cycle(), 4);
+    }
+
+    #[test]
+    fn test_pipeline_hierarchical_counter() {
+        // The counter again, from two instances of a toggle flip-flop.
+        let blif = parse(include_str!("../fixtures/tff_counter.blif")).unwrap();
+
+        let mut stepper = Stepper::new(&blif);
+        assert_eq!(stepper.state().get("tff_0/q"), SignalState::Low);
+        assert_eq!(stepper.state().get("tff_1/q"), SignalState::Low);
+
+        let expected = [
+            (SignalState::High, SignalState::Low),
+            (SignalState::Low, SignalState::High),
+            (SignalState::High, SignalState::High),
+            (SignalState::Low, SignalState::Low),
+        ];
+
+        for (q0, q1) in expected {
+            let res = stepper.tick("clk", Signals::new());
+
+            assert_eq!((res.get("q0"), res.get("q1")), (q0, q1));
+            // Nets inside the instances are returned too.
+            assert_eq!((res.get("tff_0/q"), res.get("tff_1/t")), (q0, q0));
+        }
+    }
+}
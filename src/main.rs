@@ -1,51 +1,13 @@
-#![feature(map_try_insert)]
-
-mod blif;
-mod simulation;
+use garnierisator::cli;
 
 fn main() {
-    blif::parse("");
-}
-
-// #[cfg(test)]
-// mod tests {
-//     use crate::blif::*;
-//     use crate::simulation::*;
-
-//     #[test]
-//     fn test_pipeline_smol() {
-//         let smol = include_str!("../fixtures/smol.blif");
-//         let blif = parser::parse(smol);
+    let args: Vec<String> = std::env::args().skip(1).collect();
 
-//         let res = blif.stim(
-//             SignalsBuilder::new()
-//                 .add_signal("i_A", SignalState::Low)
-//                 .add_signal("i_B", SignalState::Low)
-//                 .build()
-//         );
-//         assert_eq!(res.get("o_led"), SignalState::Low);
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
 
-//         let res = blif.stim(
-//             SignalsBuilder::new()
-//                 .add_signal("i_A", SignalState::High)
-//                 .add_signal("i_B", SignalState::Low)
-//                 .build()
-//         );
-//         assert_eq!(res.get("o_led"), SignalState::High);
-//     }
-
-//     #[test]
-//     fn test_pipeline_med() {
-//         let smol = include_str!("../fixtures/med.blif");
-//         let blif = parser::parse(smol);
-
-//         let res = blif.stim(
-//             SignalsBuilder::new()
-//                 .add_signal("i_A", SignalState::High)
-//                 .add_signal("i_B", SignalState::Low)
-//                 .build()
-//         );
-
-//         assert_eq!(res.get("o_m1"), SignalState::High);
-//     }
-// }
+    if let Err(err) = cli::run(&args, &mut stdin.lock(), &mut stdout.lock()) {
+        eprintln!("{}", err);
+        std::process::exit(err.exit_code());
+    }
+}
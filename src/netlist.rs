@@ -0,0 +1,638 @@
+use crate::blif::{is_tautology, CombinationalLoop, InputValue, LatchInit, LatchType, LogicGate, Model};
+use crate::simulation::{SignalState, Signals};
+
+use std::collections::{HashMap, VecDeque};
+
+pub mod parallel;
+pub mod event;
+
+/// Index of a net in a `Netlist`.
+pub type NetId = usize;
+
+/// A `.names` cover packed into bit masks.
+///
+/// Each row takes `2 * words` words of `Netlist::cubes`: the care mask (bit
+/// `i` set when input `i` is not `-`), then the value of the cared-for inputs.
+/// Only the rows of the polarity of the cover are kept.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Cover {
+    /// Range of `Netlist::fanins` holding the inputs of the gate.
+    pub fanin: std::ops::Range<usize>,
+    /// Range of `Netlist::cubes` holding the rows.
+    pub cubes: std::ops::Range<usize>,
+    /// Words per mask, at least one.
+    pub words: usize,
+    /// Output when a row matches, and when none does.
+    pub matched: SignalState,
+    pub unmatched: SignalState,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Cell {
+    Cover(Cover),
+    /// `$_TBUF_`: `a` when `e` is high, high impedance when it is low.
+    Tbuf { a: NetId, e: NetId },
+    /// An output port of a flattened instance copied to its actual net.
+    Buffer(NetId),
+    /// Drives the nets of unresolved instances.
+    Const(SignalState),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Node {
+    pub cell: Cell,
+    pub output: NetId,
+    /// Whether a node earlier in the evaluation order also drives `output`,
+    /// in which case both values are resolved against each other.
+    pub resolve: bool,
+}
+
+/// A flattened `.subckt` instance, named `{model}_{index}` after its position in the parent.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Instance {
+    pub name: String,
+    /// `None` for instances of the top-level model.
+    pub parent: Option<usize>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct CompiledLatch {
+    pub input: NetId,
+    pub output: NetId,
+    /// `None` for latches on the global clock, including `NIL` controls.
+    pub control: Option<(LatchType, NetId)>,
+    pub init: LatchInit,
+}
+
+/// A `Model` lowered to integer net IDs, with every `.subckt` instance
+/// flattened and every node in evaluation order.
+///
+/// Nets of instances are named `{instance}/{net}` after the `Instance` they
+/// belong to, and the input ports of an instance are the nets of the parent
+/// bound to them. Unlike `Model`, which only holds the state
+/// of its own latches, every latch of the hierarchy holds state here.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Netlist {
+    pub name: String,
+    names: Vec<String>,
+    index: HashMap<String, NetId>,
+    /// Instance of each net, and where its name within the instance starts.
+    scopes: Vec<(Option<usize>, usize)>,
+    pub instances: Vec<Instance>,
+    pub inputs: Vec<NetId>,
+    pub outputs: Vec<NetId>,
+    pub nodes: Vec<Node>,
+    pub latches: Vec<CompiledLatch>,
+    fanins: Vec<NetId>,
+    cubes: Vec<u64>,
+}
+
+impl Netlist {
+    pub fn new(model: &Model) -> Result<Self, CombinationalLoop> {
+        model.check()?;
+
+        let mut builder = Builder::default();
+        // Primary inputs and outputs first, as in `Model::nets`.
+        let inputs = model.inputs.iter().map(|net| builder.net(None, "", net)).collect();
+        let outputs = model.outputs.iter().map(|net| builder.net(None, "", net)).collect();
+
+        builder.flatten(model, None, "", &HashMap::new());
+        let nodes = builder.order();
+
+        Ok(Self {
+            name: model.name.clone(),
+            names: builder.names,
+            index: builder.index,
+            scopes: builder.scopes,
+            instances: builder.instances,
+            inputs,
+            outputs,
+            nodes,
+            latches: builder.latches,
+            fanins: builder.fanins,
+            cubes: builder.cubes,
+        })
+    }
+
+    pub fn net(&self, name: &str) -> Option<NetId> {
+        self.index.get(name).copied()
+    }
+
+    pub fn net_name(&self, net: NetId) -> &str {
+        &self.names[net]
+    }
+
+    /// Instance `net` belongs to, `None` for the nets of the top-level model.
+    pub fn net_instance(&self, net: NetId) -> Option<usize> {
+        self.scopes[net].0
+    }
+
+    /// Name of `net` within its instance.
+    pub fn local_name(&self, net: NetId) -> &str {
+        &self.names[net][self.scopes[net].1..]
+    }
+
+    pub fn net_count(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn fanin(&self, cover: &Cover) -> &[NetId] {
+        &self.fanins[cover.fanin.clone()]
+    }
+
+    /// `(care, value)` masks of each row of `cover`.
+    pub fn rows<'a>(&'a self, cover: &'a Cover) -> impl Iterator<Item=(&'a [u64], &'a [u64])> + 'a {
+        self.cubes[cover.cubes.clone()]
+            .chunks(2 * cover.words)
+            .map(move |row| row.split_at(cover.words))
+    }
+
+    /// Nets read by `node`.
+    pub fn node_inputs(&self, node: &Node) -> Vec<NetId> {
+        match &node.cell {
+            Cell::Cover(cover) => self.fanin(cover).to_vec(),
+            Cell::Tbuf { a, e } => vec![*a, *e],
+            Cell::Buffer(input) => vec![*input],
+            Cell::Const(_) => Vec::new(),
+        }
+    }
+
+    /// Same result as `LogicGate::evaluate`, with the rows matched word by
+    /// word against the inputs set in `known` and their values in `bits`.
+    pub fn evaluate_cover(&self, cover: &Cover, known: &[u64], bits: &[u64]) -> SignalState {
+        let words = cover.words;
+        let mut undecided = false;
+
+        for (care, value) in self.rows(cover) {
+            let ruled_out = (0..words).any(|w| (bits[w] ^ value[w]) & care[w] & known[w] != 0);
+            if ruled_out {
+                continue;
+            }
+
+            if (0..words).all(|w| care[w] & !known[w] == 0) {
+                return cover.matched;
+            }
+
+            undecided = true;
+        }
+
+        if !undecided {
+            return cover.unmatched;
+        }
+
+        // Some rows depend on unknown inputs: they may still cover every case.
+        let fanin = self.fanin(cover).len();
+        let cubes: Vec<Vec<InputValue>> = self.rows(cover)
+            .filter(|(care, value)| (0..words).all(|w| (bits[w] ^ value[w]) & care[w] & known[w] == 0))
+            .map(|(care, value)| (0..fanin).map(|i| {
+                let (w, bit) = (i / 64, 1 << (i % 64));
+
+                match (care[w] & !known[w] & bit != 0, value[w] & bit != 0) {
+                    (false, _) => InputValue::NotUsed,
+                    (true, true) => InputValue::Uncomplemented,
+                    (true, false) => InputValue::Complemented,
+                }
+            }).collect())
+            .collect();
+
+        if is_tautology(&cubes) { cover.matched } else { SignalState::Unknown }
+    }
+
+    /// Value driven by `cell` given the state of every net in `values`.
+    ///
+    /// `known` and `bits` are scratch space of at least `max_words` words.
+    pub fn evaluate_cell(&self, cell: &Cell, values: &[SignalState], known: &mut [u64], bits: &mut [u64]) -> SignalState {
+        match cell {
+            Cell::Cover(cover) => {
+                let words = cover.words;
+
+                known[..words].fill(0);
+                bits[..words].fill(0);
+
+                for (i, net) in self.fanin(cover).iter().enumerate() {
+                    match values[*net] {
+                        SignalState::High => {
+                            known[i / 64] |= 1 << (i % 64);
+                            bits[i / 64] |= 1 << (i % 64);
+                        }
+                        SignalState::Low => known[i / 64] |= 1 << (i % 64),
+                        _ => (),
+                    }
+                }
+
+                self.evaluate_cover(cover, &known[..words], &bits[..words])
+            }
+            Cell::Tbuf { a, e } => match values[*e] {
+                SignalState::High => values[*a],
+                SignalState::Low => SignalState::HighImpedance,
+                _ => SignalState::Unknown,
+            },
+            Cell::Buffer(input) => values[*input],
+            Cell::Const(state) => *state,
+        }
+    }
+
+    /// Words of the widest cover.
+    pub fn max_words(&self) -> usize {
+        self.nodes.iter().map(|node| match &node.cell {
+            Cell::Cover(cover) => cover.words,
+            _ => 0,
+        }).max().unwrap_or(0)
+    }
+}
+
+#[derive(Default)]
+struct Builder {
+    names: Vec<String>,
+    index: HashMap<String, NetId>,
+    scopes: Vec<(Option<usize>, usize)>,
+    instances: Vec<Instance>,
+    /// `(cell, inputs, output)` in order of appearance.
+    cells: Vec<(Cell, Vec<NetId>, NetId)>,
+    latches: Vec<CompiledLatch>,
+    fanins: Vec<NetId>,
+    cubes: Vec<u64>,
+}
+
+impl Builder {
+    /// The net `name` of `instance`, whose nets are named with `prefix`.
+    fn net(&mut self, instance: Option<usize>, prefix: &str, name: &str) -> NetId {
+        let full = format!("{}{}", prefix, name);
+        if let Some(net) = self.index.get(&full) {
+            return *net;
+        }
+
+        self.index.insert(full.clone(), self.names.len());
+        self.names.push(full);
+        self.scopes.push((instance, prefix.len()));
+
+        self.names.len() - 1
+    }
+
+    fn push(&mut self, cell: Cell, inputs: Vec<NetId>, output: NetId) {
+        self.cells.push((cell, inputs, output));
+    }
+
+    fn cover(&mut self, gate: &LogicGate, fanin: Vec<NetId>) -> Cover {
+        let words = fanin.len().div_ceil(64).max(1);
+        let polarity = gate.polarity();
+
+        let start = self.fanins.len();
+        self.fanins.extend(fanin);
+        let first = self.cubes.len();
+
+        for (row, _) in gate.single_output_cover.iter().filter(|(_, output)| *output == polarity) {
+            let mut care = vec![0u64; words];
+            let mut value = vec![0u64; words];
+
+            for (i, literal) in row.iter().enumerate() {
+                match literal {
+                    InputValue::Uncomplemented => {
+                        care[i / 64] |= 1 << (i % 64);
+                        value[i / 64] |= 1 << (i % 64);
+                    }
+                    InputValue::Complemented => care[i / 64] |= 1 << (i % 64),
+                    InputValue::NotUsed => (),
+                }
+            }
+
+            self.cubes.extend(care);
+            self.cubes.extend(value);
+        }
+
+        let (matched, unmatched) = match polarity {
+            InputValue::Complemented => (SignalState::Low, SignalState::High),
+            _ => (SignalState::High, SignalState::Low),
+        };
+
+        Cover { fanin: start..self.fanins.len(), cubes: first..self.cubes.len(), words, matched, unmatched }
+    }
+
+    /// Lower `model` as `instance`, naming its nets with `prefix` except for
+    /// the input ports bound to nets of the parent in `ports`.
+    fn flatten(&mut self, model: &Model, instance: Option<usize>, prefix: &str, ports: &HashMap<&str, NetId>) {
+        let local = |builder: &mut Self, name: &str| match ports.get(name) {
+            Some(net) => *net,
+            None => builder.net(instance, prefix, name),
+        };
+
+        for gate in &model.gates {
+            let fanin: Vec<NetId> = gate.inputs.iter().map(|input| local(self, input)).collect();
+            let output = local(self, &gate.output);
+
+            // Parsed covers have one column per input, but gates built by
+            // hand may not: they are skipped, their output left unknown.
+            if gate.single_output_cover.iter().any(|(row, _)| row.len() != fanin.len()) {
+                self.push(Cell::Const(SignalState::Unknown), Vec::new(), output);
+                continue;
+            }
+
+            let cover = self.cover(gate, fanin.clone());
+            self.push(Cell::Cover(cover), fanin, output);
+        }
+
+        for latch in &model.latches {
+            let control = match &latch.control {
+                Some((_, control)) if control == "NIL" => None,
+                Some((kind, control)) => Some((*kind, local(self, control))),
+                None => None,
+            };
+
+            let compiled = CompiledLatch {
+                input: local(self, &latch.input),
+                output: local(self, &latch.output),
+                control,
+                init: latch.init,
+            };
+            self.latches.push(compiled);
+        }
+
+        for (i, subckt) in model.subckts.iter().enumerate() {
+            let (inputs, outputs) = match subckt.bindings() {
+                Some(bindings) => bindings,
+                None => {
+                    for (_, actual) in &subckt.connections {
+                        let output = local(self, actual);
+                        self.push(Cell::Const(SignalState::Unknown), Vec::new(), output);
+                    }
+                    continue;
+                }
+            };
+
+            match &subckt.definition {
+                Some(definition) => {
+                    let name = model.instance_name(i);
+                    let child = format!("{}{}/", prefix, name);
+                    self.instances.push(Instance { name, parent: instance });
+                    let index = self.instances.len() - 1;
+
+                    let bound: HashMap<&str, NetId> = inputs.iter()
+                        .map(|(port, actual)| (*port, local(self, actual)))
+                        .collect();
+
+                    self.flatten(definition, Some(index), &child, &bound);
+
+                    for (port, actual) in outputs {
+                        let input = match bound.get(port) {
+                            Some(net) => *net,
+                            None => self.net(Some(index), &child, port),
+                        };
+                        let output = local(self, actual);
+
+                        self.push(Cell::Buffer(input), vec![input], output);
+                    }
+                }
+                None => {
+                    // `$_TBUF_`, the only built-in cell.
+                    let port = |builder: &mut Self, name: &str| {
+                        match inputs.iter().chain(&outputs).find(|(port, _)| *port == name) {
+                            Some((_, actual)) => local(builder, actual),
+                            None => local(builder, &format!("{}_{}.{}", subckt.model, i, name)),
+                        }
+                    };
+
+                    let (a, e) = (port(self, "A"), port(self, "E"));
+                    if outputs.iter().any(|(port, _)| *port == "Y") {
+                        let y = port(self, "Y");
+                        self.push(Cell::Tbuf { a, e }, vec![a, e], y);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Nodes after the ones driving their inputs, keeping the order of
+    /// appearance where possible.
+    fn order(&mut self) -> Vec<Node> {
+        let mut drivers: HashMap<NetId, Vec<usize>> = HashMap::new();
+        for (i, (_, _, output)) in self.cells.iter().enumerate() {
+            drivers.entry(*output).or_default().push(i);
+        }
+
+        let mut successors = vec![Vec::new(); self.cells.len()];
+        let mut pending = vec![0usize; self.cells.len()];
+
+        for (i, (_, inputs, _)) in self.cells.iter().enumerate() {
+            for driver in inputs.iter().filter_map(|net| drivers.get(net)).flatten() {
+                successors[*driver].push(i);
+                pending[i] += 1;
+            }
+        }
+
+        let mut ready: VecDeque<usize> = (0..self.cells.len()).filter(|i| pending[*i] == 0).collect();
+        let mut order = Vec::with_capacity(self.cells.len());
+
+        while let Some(i) = ready.pop_front() {
+            order.push(i);
+
+            for successor in &successors[i] {
+                pending[*successor] -= 1;
+                if pending[*successor] == 0 {
+                    ready.push_back(*successor);
+                }
+            }
+        }
+
+        // `Model::check` rules out loops in the hierarchy, and so in its flattening.
+        assert_eq!(order.len(), self.cells.len(), "combinational loop in a checked model");
+
+        let mut cells: Vec<Option<(Cell, NetId)>> = std::mem::take(&mut self.cells).into_iter()
+            .map(|(cell, _, output)| Some((cell, output)))
+            .collect();
+        let mut driven = vec![false; self.names.len()];
+
+        order.into_iter().map(|i| {
+            let (cell, output) = cells[i].take().unwrap();
+            let resolve = std::mem::replace(&mut driven[output], true);
+
+            Node { cell, output, resolve }
+        }).collect()
+    }
+}
+
+/// Evaluates a `Netlist` over one `SignalState` per net.
+///
+/// Primary inputs and latch outputs are set by the caller; every other net
+/// is computed by `evaluate`.
+pub struct Evaluator<'a> {
+    netlist: &'a Netlist,
+    values: Vec<SignalState>,
+    /// Scratch masks of the known inputs of a cover and of their values.
+    known: Vec<u64>,
+    bits: Vec<u64>,
+}
+
+impl<'a> Evaluator<'a> {
+    pub fn new(netlist: &'a Netlist) -> Self {
+        let words = netlist.max_words();
+
+        let mut evaluator = Self {
+            netlist,
+            values: vec![SignalState::Unknown; netlist.net_count()],
+            known: vec![0; words],
+            bits: vec![0; words],
+        };
+        evaluator.reset();
+
+        evaluator
+    }
+
+    /// Put every latch back to its initial value.
+    pub fn reset(&mut self) {
+        for latch in &self.netlist.latches {
+            self.values[latch.output] = latch.init.into();
+        }
+    }
+
+    pub fn get(&self, net: NetId) -> SignalState {
+        self.values[net]
+    }
+
+    pub fn set(&mut self, net: NetId, state: SignalState) {
+        self.values[net] = state;
+    }
+
+    pub fn values(&self) -> &[SignalState] {
+        &self.values
+    }
+
+    /// Set the primary inputs, in `.inputs` order.
+    pub fn set_inputs(&mut self, states: &[SignalState]) {
+        for (net, state) in std::iter::zip(&self.netlist.inputs, states) {
+            self.values[*net] = *state;
+        }
+    }
+
+    /// Set the nets of `signals` found in the netlist.
+    pub fn set_signals(&mut self, signals: &Signals) {
+        for signal in signals.iter() {
+            if let Some(net) = self.netlist.net(signal.name()) {
+                self.values[net] = signal.state();
+            }
+        }
+    }
+
+    pub fn outputs(&self) -> Vec<SignalState> {
+        self.netlist.outputs.iter().map(|net| self.values[*net]).collect()
+    }
+
+    pub fn evaluate(&mut self) {
+        let netlist = self.netlist;
+
+        for node in &netlist.nodes {
+            let state = netlist.evaluate_cell(&node.cell, &self.values, &mut self.known, &mut self.bits);
+
+            self.values[node.output] = if node.resolve { self.values[node.output].resolve(state) } else { state };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blif::{self, Subckt};
+    use crate::simulation::{Simulable, SignalsBuilder};
+
+    const STATES: [SignalState; 4] = [SignalState::Low, SignalState::High, SignalState::Unknown, SignalState::HighImpedance];
+
+    /// Compare the evaluator with `Model::stim` on every combination of input states.
+    fn assert_matches_model(model: &Model) {
+        let netlist = Netlist::new(model).unwrap();
+        let mut evaluator = Evaluator::new(&netlist);
+
+        let n = model.inputs.len();
+        for combination in 0..STATES.len().pow(n as u32) {
+            let states: Vec<SignalState> = (0..n).map(|i| STATES[combination / STATES.len().pow(i as u32) % STATES.len()]).collect();
+
+            let signals = std::iter::zip(&model.inputs, &states).fold(SignalsBuilder::new(), |builder, (input, state)| {
+                builder.add_signal(input, *state)
+            }).build();
+            let expected = model.stim(signals);
+
+            evaluator.set_inputs(&states);
+            evaluator.evaluate();
+
+            for net in model.nets() {
+                assert_eq!(evaluator.get(netlist.net(&net).unwrap()), expected.get(&net), "net {} for {:?}", net, states);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fixtures() {
+        for source in [include_str!("../fixtures/smol.blif"), include_str!("../fixtures/full_adder.blif"), include_str!("../fixtures/med.blif")] {
+            let blif = blif::parse(source).unwrap();
+            assert_matches_model(blif.top().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_flattened_names() {
+        let blif = blif::parse(include_str!("../fixtures/med.blif")).unwrap();
+        let netlist = Netlist::new(blif.top().unwrap()).unwrap();
+
+        assert!(netlist.net("a_not_b_1/o_led").is_some());
+        // Input ports are the nets of the parent.
+        assert_eq!(netlist.net("a_not_b_1/i_A"), None);
+        assert_eq!(netlist.nodes.iter().filter(|node| matches!(node.cell, Cell::Buffer(_))).count(), 2);
+    }
+
+    #[test]
+    fn test_tri_state_bus() {
+        let mut model = Model::new("bus".into(), vec!["a".into(), "b".into(), "sel".into()], vec!["y".into()], Vec::new());
+        model.gates.push(LogicGate::new(vec!["sel".into()], "nsel".into(), vec![(vec![InputValue::Complemented], InputValue::Uncomplemented)]));
+        model.subckts.push(Subckt::new("$_TBUF_".into(), vec![("A".into(), "a".into()), ("E".into(), "sel".into()), ("Y".into(), "y".into())]));
+        model.subckts.push(Subckt::new("$_TBUF_".into(), vec![("A".into(), "b".into()), ("E".into(), "nsel".into()), ("Y".into(), "y".into())]));
+
+        assert_matches_model(&model);
+    }
+
+    #[test]
+    fn test_wide_cover() {
+        // A 70-input AND, over two words.
+        let inputs: Vec<String> = (0..70).map(|i| format!("i{}", i)).collect();
+        let gate = LogicGate::new(inputs.clone(), "y".into(), vec![(vec![InputValue::Uncomplemented; 70], InputValue::Uncomplemented)]);
+        let model = Model::new("and70".into(), inputs, vec!["y".into()], vec![gate]);
+
+        let netlist = Netlist::new(&model).unwrap();
+        let mut evaluator = Evaluator::new(&netlist);
+
+        evaluator.set_inputs(&[SignalState::High; 70]);
+        evaluator.evaluate();
+        assert_eq!(evaluator.outputs(), vec![SignalState::High]);
+
+        evaluator.set(netlist.net("i65").unwrap(), SignalState::Unknown);
+        evaluator.evaluate();
+        assert_eq!(evaluator.outputs(), vec![SignalState::Unknown]);
+
+        evaluator.set(netlist.net("i3").unwrap(), SignalState::Low);
+        evaluator.evaluate();
+        assert_eq!(evaluator.outputs(), vec![SignalState::Low]);
+    }
+
+    #[test]
+    fn test_loop() {
+        let ring = Model::new("ring".into(), Vec::new(), Vec::new(), vec![
+            LogicGate::new(vec!["b".into()], "a".into(), vec![(vec![InputValue::Uncomplemented], InputValue::Uncomplemented)]),
+            LogicGate::new(vec!["a".into()], "b".into(), vec![(vec![InputValue::Uncomplemented], InputValue::Uncomplemented)]),
+        ]);
+
+        assert!(Netlist::new(&ring).is_err());
+    }
+
+    #[test]
+    fn test_malformed_cover() {
+        let wide = vec![InputValue::Uncomplemented; 65];
+        let model = Model::new("wide".into(), vec!["a".into()], vec!["y".into(), "z".into()], vec![
+            LogicGate::new(vec!["a".into()], "y".into(), vec![(wide, InputValue::Uncomplemented)]),
+            LogicGate::new(vec!["a".into()], "z".into(), vec![(vec![InputValue::Complemented], InputValue::Uncomplemented)]),
+        ]);
+
+        let netlist = Netlist::new(&model).unwrap();
+        let mut evaluator = Evaluator::new(&netlist);
+        evaluator.set_inputs(&[SignalState::Low]);
+        evaluator.evaluate();
+
+        assert_eq!(evaluator.outputs(), vec![SignalState::Unknown, SignalState::High]);
+    }
+}
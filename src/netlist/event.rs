@@ -0,0 +1,317 @@
+use super::{Cell, Evaluator, NetId, Netlist};
+use crate::simulation::SignalState;
+
+use std::collections::BTreeMap;
+
+/// Propagation delay of covers and tri-state buffers unless set otherwise.
+pub const DEFAULT_DELAY: u64 = 1;
+
+/// A net taking a new value.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Change {
+    pub time: u64,
+    pub net: NetId,
+    pub state: SignalState,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum EventError {
+    /// The netlist holds latches, which are not simulated.
+    Sequential,
+    /// An event scheduled at `time`, before the current time `now`.
+    Past { time: u64, now: u64 },
+}
+
+impl std::fmt::Display for EventError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Sequential => write!(f, "the netlist holds latches, only combinational netlists can be simulated with delays"),
+            Self::Past { time, now } => write!(f, "event scheduled at {} in the past of {}", time, now),
+        }
+    }
+}
+
+impl std::error::Error for EventError {}
+
+#[derive(Debug, Clone, Copy)]
+enum Event {
+    /// A node starts driving a new value.
+    Node(usize, SignalState),
+    /// An undriven net, such as a primary input, is set from the outside.
+    Net(NetId, SignalState),
+}
+
+/// Event-driven simulation of a `Netlist` with transport delays.
+///
+/// Each node drives its output `delay` time units after one of its inputs
+/// changed, and only the nodes reading a net that changed are evaluated
+/// again, so that glitches and hazards show up in the recorded `Change`s.
+/// Port connections of flattened instances take no time.
+///
+/// Only combinational netlists are simulated: netlists with latches are
+/// refused.
+pub struct EventSimulator<'a> {
+    netlist: &'a Netlist,
+    delays: Vec<u64>,
+    /// Nodes reading and driving each net.
+    fanout: Vec<Vec<usize>>,
+    drivers: Vec<Vec<usize>>,
+    /// Value currently driven by each node, and the last one scheduled for it.
+    driven: Vec<SignalState>,
+    scheduled: Vec<SignalState>,
+    /// Values set on undriven nets.
+    forced: Vec<SignalState>,
+    values: Vec<SignalState>,
+    initial: Vec<SignalState>,
+    queue: BTreeMap<u64, Vec<Event>>,
+    time: u64,
+    changes: Vec<Change>,
+    known: Vec<u64>,
+    bits: Vec<u64>,
+}
+
+impl<'a> EventSimulator<'a> {
+    /// Start from the settled state of `netlist` with every input unknown,
+    /// `delay` being the delay of each node.
+    pub fn new(netlist: &'a Netlist, delay: u64) -> Result<Self, EventError> {
+        if !netlist.latches.is_empty() {
+            return Err(EventError::Sequential);
+        }
+
+        let mut evaluator = Evaluator::new(netlist);
+        evaluator.evaluate();
+        let values = evaluator.values().to_vec();
+
+        let mut fanout = vec![Vec::new(); netlist.net_count()];
+        let mut drivers = vec![Vec::new(); netlist.net_count()];
+
+        for (i, node) in netlist.nodes.iter().enumerate() {
+            for net in netlist.node_inputs(node) {
+                fanout[net].push(i);
+            }
+            drivers[node.output].push(i);
+        }
+
+        let delays = netlist.nodes.iter().map(|node| match node.cell {
+            Cell::Cover(_) | Cell::Tbuf { .. } => delay,
+            Cell::Buffer(_) | Cell::Const(_) => 0,
+        }).collect();
+
+        let words = netlist.max_words();
+        let mut known = vec![0; words];
+        let mut bits = vec![0; words];
+
+        let driven: Vec<SignalState> = netlist.nodes.iter()
+            .map(|node| netlist.evaluate_cell(&node.cell, &values, &mut known, &mut bits))
+            .collect();
+
+        Ok(Self {
+            netlist,
+            delays,
+            fanout,
+            drivers,
+            scheduled: driven.clone(),
+            driven,
+            forced: values.clone(),
+            initial: values.clone(),
+            values,
+            queue: BTreeMap::new(),
+            time: 0,
+            changes: Vec::new(),
+            known,
+            bits,
+        })
+    }
+
+    /// Set the delay of the nodes driving `net`.
+    pub fn set_delay(&mut self, net: NetId, delay: u64) {
+        for node in &self.drivers[net] {
+            self.delays[*node] = delay;
+        }
+    }
+
+    pub fn time(&self) -> u64 {
+        self.time
+    }
+
+    pub fn get(&self, net: NetId) -> SignalState {
+        self.values[net]
+    }
+
+    /// Values of every net before the first event.
+    pub fn initial(&self) -> &[SignalState] {
+        &self.initial
+    }
+
+    /// Every change so far, in time order.
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    /// Set `net` to `state` at `time`, which must not be in the past.
+    pub fn schedule(&mut self, net: NetId, state: SignalState, time: u64) -> Result<(), EventError> {
+        if time < self.time {
+            return Err(EventError::Past { time, now: self.time });
+        }
+
+        self.queue.entry(time).or_default().push(Event::Net(net, state));
+        Ok(())
+    }
+
+    /// Set the primary inputs at `time`, in `.inputs` order.
+    pub fn schedule_inputs(&mut self, states: &[SignalState], time: u64) -> Result<(), EventError> {
+        for (net, state) in std::iter::zip(&self.netlist.inputs, states) {
+            self.schedule(*net, *state, time)?;
+        }
+
+        Ok(())
+    }
+
+    /// Process the events up to and including `time`.
+    pub fn run_until(&mut self, time: u64) {
+        while self.queue.first_key_value().is_some_and(|(next, _)| *next <= time) {
+            self.step();
+        }
+
+        self.time = self.time.max(time);
+    }
+
+    /// Process every pending event and return the time the design settled at.
+    pub fn run(&mut self) -> u64 {
+        while self.step() {}
+
+        self.time
+    }
+
+    /// Process the events of the earliest pending time, returning whether there was any.
+    ///
+    /// Nodes without delay schedule their events at the same time, which are
+    /// processed by the following steps.
+    fn step(&mut self) -> bool {
+        let (time, events) = match self.queue.pop_first() {
+            Some(entry) => entry,
+            None => return false,
+        };
+        self.time = time;
+
+        let mut touched = Vec::new();
+        for event in events {
+            match event {
+                Event::Node(node, state) => {
+                    self.driven[node] = state;
+                    touched.push(self.netlist.nodes[node].output);
+                }
+                Event::Net(net, state) => {
+                    self.forced[net] = state;
+                    touched.push(net);
+                }
+            }
+        }
+
+        let mut evaluate: Vec<usize> = Vec::new();
+        for net in touched {
+            let state = match self.drivers[net].split_first() {
+                Some((first, others)) => others.iter().fold(self.driven[*first], |state, node| {
+                    state.resolve(self.driven[*node])
+                }),
+                None => self.forced[net],
+            };
+
+            if state != self.values[net] {
+                self.values[net] = state;
+                self.changes.push(Change { time, net, state });
+                evaluate.extend(&self.fanout[net]);
+            }
+        }
+
+        evaluate.sort_unstable();
+        evaluate.dedup();
+
+        for node in evaluate {
+            let cell = &self.netlist.nodes[node].cell;
+            let state = self.netlist.evaluate_cell(cell, &self.values, &mut self.known, &mut self.bits);
+
+            if state != self.scheduled[node] {
+                self.scheduled[node] = state;
+                self.queue.entry(time + self.delays[node]).or_default().push(Event::Node(node, state));
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blif::{self, InputValue, LogicGate, Model};
+
+    fn hazard() -> Model {
+        // y = a & !a, with the inverter slower than the AND gate.
+        Model::new("hazard".into(), vec!["a".into()], vec!["y".into()], vec![
+            LogicGate::new(vec!["a".into()], "na".into(), vec![(vec![InputValue::Complemented], InputValue::Uncomplemented)]),
+            LogicGate::new(vec!["a".into(), "na".into()], "y".into(), vec![
+                (vec![InputValue::Uncomplemented, InputValue::Uncomplemented], InputValue::Uncomplemented),
+            ]),
+        ])
+    }
+
+    #[test]
+    fn test_glitch() {
+        let model = hazard();
+        let netlist = Netlist::new(&model).unwrap();
+        let (a, na, y) = (netlist.net("a").unwrap(), netlist.net("na").unwrap(), netlist.net("y").unwrap());
+
+        let mut sim = EventSimulator::new(&netlist, DEFAULT_DELAY).unwrap();
+        sim.set_delay(na, 3);
+
+        sim.schedule(a, SignalState::Low, 0).unwrap();
+        assert_eq!(sim.run(), 3);
+        assert_eq!(sim.get(na), SignalState::High);
+
+        sim.schedule(a, SignalState::High, 10).unwrap();
+        assert_eq!(sim.run(), 14);
+        assert_eq!(sim.schedule(a, SignalState::Low, 13), Err(EventError::Past { time: 13, now: 14 }));
+
+        let y_changes: Vec<(u64, SignalState)> = sim.changes().iter()
+            .filter(|change| change.net == y)
+            .map(|change| (change.time, change.state))
+            .collect();
+
+        // Unknown until `na` settles, then a one-unit pulse on the rising edge of `a`.
+        assert_eq!(y_changes, vec![
+            (1, SignalState::Low),
+            (11, SignalState::High),
+            (14, SignalState::Low),
+        ]);
+    }
+
+    #[test]
+    fn test_settles_to_zero_delay_result() {
+        let blif = blif::parse(include_str!("../../fixtures/med.blif")).unwrap();
+        let netlist = Netlist::new(blif.top().unwrap()).unwrap();
+
+        let mut sim = EventSimulator::new(&netlist, DEFAULT_DELAY).unwrap();
+        let mut evaluator = Evaluator::new(&netlist);
+
+        for (time, inputs) in [[SignalState::High, SignalState::Low], [SignalState::Low, SignalState::Low], [SignalState::High, SignalState::High]].iter().enumerate() {
+            sim.schedule_inputs(inputs, 100 * time as u64).unwrap();
+            sim.run();
+
+            evaluator.set_inputs(inputs);
+            evaluator.evaluate();
+
+            for net in 0..netlist.net_count() {
+                assert_eq!(sim.get(net), evaluator.get(net), "net {}", netlist.net_name(net));
+            }
+        }
+    }
+
+    #[test]
+    fn test_latches_refused() {
+        let blif = blif::parse(include_str!("../../fixtures/counter.blif")).unwrap();
+        let netlist = Netlist::new(blif.top().unwrap()).unwrap();
+
+        assert_eq!(EventSimulator::new(&netlist, DEFAULT_DELAY).err(), Some(EventError::Sequential));
+    }
+}
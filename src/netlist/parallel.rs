@@ -0,0 +1,370 @@
+use super::{Cell, Cover, NetId, Netlist};
+use crate::simulation::{SignalState, Signal, Signals};
+
+/// Number of patterns simulated at once.
+pub const LANES: usize = 64;
+
+/// The states of a net in each of `LANES` independent patterns.
+///
+/// Bit `i` of `high` and `low` hold the state in lane `i`: `High` is `(1, 0)`,
+/// `Low` is `(0, 1)`, `Unknown` is `(0, 0)` and `HighImpedance` is `(1, 1)`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Lanes {
+    pub high: u64,
+    pub low: u64,
+}
+
+impl Lanes {
+    /// `state` in every lane.
+    pub fn splat(state: SignalState) -> Self {
+        Self::splat_masked(state, !0)
+    }
+
+    /// `state` in the lanes of `mask`, `Unknown` in the others.
+    fn splat_masked(state: SignalState, mask: u64) -> Self {
+        match state {
+            SignalState::High => Self { high: mask, low: 0 },
+            SignalState::Low => Self { high: 0, low: mask },
+            SignalState::Unknown => Self { high: 0, low: 0 },
+            SignalState::HighImpedance => Self { high: mask, low: mask },
+        }
+    }
+
+    /// Binary patterns: `High` in the lanes set in `bits`, `Low` in the others.
+    pub fn from_bits(bits: u64) -> Self {
+        Self { high: bits, low: !bits }
+    }
+
+    pub fn get(&self, lane: usize) -> SignalState {
+        match (self.high >> lane & 1, self.low >> lane & 1) {
+            (1, 0) => SignalState::High,
+            (0, 1) => SignalState::Low,
+            (1, 1) => SignalState::HighImpedance,
+            _ => SignalState::Unknown,
+        }
+    }
+
+    pub fn set(&mut self, lane: usize, state: SignalState) {
+        let bit = 1 << lane;
+        let lanes = Self::splat_masked(state, bit);
+
+        self.high = (self.high & !bit) | lanes.high;
+        self.low = (self.low & !bit) | lanes.low;
+    }
+
+    /// Lanes holding `High`.
+    fn is_high(&self) -> u64 {
+        self.high & !self.low
+    }
+
+    /// Lanes holding `Low`.
+    fn is_low(&self) -> u64 {
+        self.low & !self.high
+    }
+
+    fn mask(self, mask: u64) -> Self {
+        Self { high: self.high & mask, low: self.low & mask }
+    }
+
+    fn or(self, other: Self) -> Self {
+        Self { high: self.high | other.high, low: self.low | other.low }
+    }
+
+    /// `SignalState::resolve` in every lane.
+    pub fn resolve(self, other: Self) -> Self {
+        let z = self.high & self.low;
+        let other_z = other.high & other.low;
+        let equal = !((self.high ^ other.high) | (self.low ^ other.low));
+
+        let keep = !z & (other_z | equal);
+
+        other.mask(z).or(self.mask(keep))
+    }
+}
+
+/// Evaluates a `Netlist` over `LANES` patterns at once, with bitwise
+/// operations on the rows of each cover.
+///
+/// Results are the same as the ones of `Evaluator` in every lane: lanes
+/// where unknown inputs leave a cover undecided are evaluated on their own.
+pub struct ParallelEvaluator<'a> {
+    netlist: &'a Netlist,
+    values: Vec<Lanes>,
+    /// Scratch masks for the lanes evaluated on their own.
+    known: Vec<u64>,
+    bits: Vec<u64>,
+}
+
+impl<'a> ParallelEvaluator<'a> {
+    pub fn new(netlist: &'a Netlist) -> Self {
+        let words = netlist.max_words();
+
+        let mut evaluator = Self {
+            netlist,
+            values: vec![Lanes::default(); netlist.net_count()],
+            known: vec![0; words],
+            bits: vec![0; words],
+        };
+        evaluator.reset();
+
+        evaluator
+    }
+
+    /// Put every latch back to its initial value, in every lane.
+    pub fn reset(&mut self) {
+        for latch in &self.netlist.latches {
+            self.values[latch.output] = Lanes::splat(latch.init.into());
+        }
+    }
+
+    pub fn get(&self, net: NetId) -> Lanes {
+        self.values[net]
+    }
+
+    pub fn set(&mut self, net: NetId, lanes: Lanes) {
+        self.values[net] = lanes;
+    }
+
+    /// Set the primary inputs, in `.inputs` order.
+    pub fn set_inputs(&mut self, lanes: &[Lanes]) {
+        for (net, lanes) in std::iter::zip(&self.netlist.inputs, lanes) {
+            self.values[*net] = *lanes;
+        }
+    }
+
+    pub fn outputs(&self) -> Vec<Lanes> {
+        self.netlist.outputs.iter().map(|net| self.values[*net]).collect()
+    }
+
+    pub fn evaluate(&mut self) {
+        let netlist = self.netlist;
+
+        for node in &netlist.nodes {
+            let lanes = match &node.cell {
+                Cell::Cover(cover) => self.cover(cover),
+                Cell::Tbuf { a, e } => {
+                    let e = self.values[*e];
+
+                    self.values[*a].mask(e.is_high()).or(Lanes::splat_masked(SignalState::HighImpedance, e.is_low()))
+                }
+                Cell::Buffer(input) => self.values[*input],
+                Cell::Const(state) => Lanes::splat(*state),
+            };
+
+            self.values[node.output] = if node.resolve { self.values[node.output].resolve(lanes) } else { lanes };
+        }
+    }
+
+    fn cover(&mut self, cover: &Cover) -> Lanes {
+        let netlist = self.netlist;
+        let fanin = netlist.fanin(cover);
+
+        // Lanes where a row certainly matches, and where one may match.
+        let mut matched = 0;
+        let mut possible = 0;
+
+        for (care, value) in netlist.rows(cover) {
+            let mut certainly = !0;
+            let mut maybe = !0;
+
+            for (i, net) in fanin.iter().enumerate() {
+                let (w, bit) = (i / 64, 1 << (i % 64));
+                if care[w] & bit == 0 {
+                    continue;
+                }
+
+                let lanes = self.values[*net];
+                let (is, is_not) = if value[w] & bit != 0 {
+                    (lanes.is_high(), lanes.is_low())
+                } else {
+                    (lanes.is_low(), lanes.is_high())
+                };
+
+                certainly &= is;
+                maybe &= !is_not;
+            }
+
+            matched |= certainly;
+            possible |= maybe;
+        }
+
+        let mut lanes = Lanes::splat_masked(cover.matched, matched)
+            .or(Lanes::splat_masked(cover.unmatched, !possible));
+
+        let mut undecided = possible & !matched;
+        while undecided != 0 {
+            let lane = undecided.trailing_zeros() as usize;
+            undecided &= undecided - 1;
+
+            lanes.set(lane, self.cover_lane(cover, lane));
+        }
+
+        lanes
+    }
+
+    fn cover_lane(&mut self, cover: &Cover, lane: usize) -> SignalState {
+        let words = cover.words;
+
+        self.known[..words].fill(0);
+        self.bits[..words].fill(0);
+
+        for (i, net) in self.netlist.fanin(cover).iter().enumerate() {
+            match self.values[*net].get(lane) {
+                SignalState::High => {
+                    self.known[i / 64] |= 1 << (i % 64);
+                    self.bits[i / 64] |= 1 << (i % 64);
+                }
+                SignalState::Low => self.known[i / 64] |= 1 << (i % 64),
+                _ => (),
+            }
+        }
+
+        self.netlist.evaluate_cover(cover, &self.known[..words], &self.bits[..words])
+    }
+}
+
+/// Evaluate each of `batch` as `Simulable::stim` would, `LANES` at a time,
+/// and return the primary outputs for each of them.
+///
+/// Nets missing from an entry of `batch` are unknown, except for latch
+/// outputs which hold their initial value.
+pub fn simulate_batch(netlist: &Netlist, batch: &[Signals]) -> Vec<Signals> {
+    let mut evaluator = ParallelEvaluator::new(netlist);
+    let mut results = Vec::with_capacity(batch.len());
+
+    for chunk in batch.chunks(LANES) {
+        evaluator.values.fill(Lanes::default());
+        evaluator.reset();
+
+        for (lane, signals) in chunk.iter().enumerate() {
+            for signal in signals.iter() {
+                if let Some(net) = netlist.net(signal.name()) {
+                    evaluator.values[net].set(lane, signal.state());
+                }
+            }
+        }
+
+        evaluator.evaluate();
+
+        for lane in 0..chunk.len() {
+            let mut outputs = Signals::new();
+
+            for net in &netlist.outputs {
+                let mut signal = Signal::new(netlist.net_name(*net));
+                signal.set(evaluator.values[*net].get(lane));
+                outputs.add_signal(signal);
+            }
+
+            results.push(outputs);
+        }
+    }
+
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blif::{self, InputValue, LogicGate, Model, Subckt};
+    use crate::netlist::Evaluator;
+    use crate::rng::Rng;
+    use crate::simulation::{Simulable, SignalsBuilder};
+
+    const STATES: [SignalState; 4] = [SignalState::Low, SignalState::High, SignalState::Unknown, SignalState::HighImpedance];
+
+    /// Compare every net with `Evaluator`, lane by lane, on random four-valued patterns.
+    fn assert_matches_evaluator(model: &Model) {
+        let netlist = Netlist::new(model).unwrap();
+        let mut parallel = ParallelEvaluator::new(&netlist);
+        let mut scalar = Evaluator::new(&netlist);
+
+        let mut rng = Rng::new(0x9e37_79b9_7f4a_7c15);
+        let inputs: Vec<Lanes> = netlist.inputs.iter().map(|_| Lanes {
+            high: rng.next_u64(),
+            low: rng.next_u64(),
+        }).collect();
+
+        parallel.set_inputs(&inputs);
+        parallel.evaluate();
+
+        for lane in 0..LANES {
+            let states: Vec<SignalState> = inputs.iter().map(|lanes| lanes.get(lane)).collect();
+            scalar.set_inputs(&states);
+            scalar.evaluate();
+
+            for net in 0..netlist.net_count() {
+                assert_eq!(parallel.get(net).get(lane), scalar.get(net), "net {} in lane {}", netlist.net_name(net), lane);
+            }
+        }
+    }
+
+    #[test]
+    fn test_lanes() {
+        let mut lanes = Lanes::default();
+
+        for (lane, state) in STATES.iter().enumerate() {
+            lanes.set(lane, *state);
+        }
+
+        for (lane, state) in STATES.iter().enumerate() {
+            assert_eq!(lanes.get(lane), *state);
+        }
+    }
+
+    #[test]
+    fn test_resolve() {
+        for a in STATES {
+            for b in STATES {
+                assert_eq!(Lanes::splat(a).resolve(Lanes::splat(b)).get(7), a.resolve(b), "{} {}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fixtures() {
+        for source in [include_str!("../../fixtures/full_adder.blif"), include_str!("../../fixtures/med.blif")] {
+            let blif = blif::parse(source).unwrap();
+            assert_matches_evaluator(blif.top().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_undecided_cover() {
+        // y = a | !a, high whatever a is, and a redundant tri-state bus.
+        let mut model = Model::new("taut".into(), vec!["a".into(), "b".into(), "e".into()], vec!["y".into(), "z".into()], vec![
+            LogicGate::new(vec!["a".into(), "b".into()], "y".into(), vec![
+                (vec![InputValue::Uncomplemented, InputValue::NotUsed], InputValue::Uncomplemented),
+                (vec![InputValue::Complemented, InputValue::Uncomplemented], InputValue::Uncomplemented),
+                (vec![InputValue::Complemented, InputValue::Complemented], InputValue::Uncomplemented),
+            ]),
+        ]);
+        model.subckts.push(Subckt::new("$_TBUF_".into(), vec![("A".into(), "a".into()), ("E".into(), "e".into()), ("Y".into(), "z".into())]));
+        model.subckts.push(Subckt::new("$_TBUF_".into(), vec![("A".into(), "b".into()), ("E".into(), "e".into()), ("Y".into(), "z".into())]));
+
+        assert_matches_evaluator(&model);
+    }
+
+    #[test]
+    fn test_simulate_batch() {
+        let blif = blif::parse(include_str!("../../fixtures/full_adder.blif")).unwrap();
+        let model = blif.top().unwrap();
+        let netlist = Netlist::new(model).unwrap();
+
+        let batch: Vec<Signals> = (0..100).map(|i| {
+            model.inputs.iter().enumerate().fold(SignalsBuilder::new(), |builder, (j, input)| {
+                builder.add_signal(input, STATES[(i >> (2 * j)) % 4])
+            }).build()
+        }).collect();
+
+        let results = simulate_batch(&netlist, &batch);
+
+        assert_eq!(results.len(), batch.len());
+        for (signals, result) in std::iter::zip(batch, results) {
+            let expected = model.stim(signals);
+
+            for output in &model.outputs {
+                assert_eq!(result.get(output), expected.get(output));
+            }
+        }
+    }
+}
@@ -0,0 +1,26 @@
+/// A xorshift generator with a fixed seed, so that random patterns are the
+/// same on every run: counterexamples are reproducible and random tests do
+/// not flake.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    /// `seed` must not be zero, which xorshift never leaves.
+    pub fn new(seed: u64) -> Self {
+        assert_ne!(seed, 0, "a xorshift seed of zero");
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// A number below `n`, near enough uniformly for small `n`.
+    #[cfg(test)]
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
@@ -0,0 +1,592 @@
+use std::io::{self, Write};
+
+/// A formula in conjunctive normal form, with DIMACS literals: variables
+/// are numbered from 1, and negative literals are complemented.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Cnf {
+    pub var_count: usize,
+    pub clauses: Vec<Vec<i32>>,
+}
+
+impl Cnf {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A fresh variable, as its positive literal.
+    pub fn new_var(&mut self) -> i32 {
+        self.var_count += 1;
+        self.var_count as i32
+    }
+
+    pub fn add_clause(&mut self, clause: Vec<i32>) {
+        debug_assert!(clause.iter().all(|lit| *lit != 0 && lit.unsigned_abs() as usize <= self.var_count));
+
+        self.clauses.push(clause);
+    }
+}
+
+/// Write `cnf` in the DIMACS format read by most SAT solvers.
+pub fn write_dimacs(out: &mut dyn Write, cnf: &Cnf) -> io::Result<()> {
+    writeln!(out, "p cnf {} {}", cnf.var_count, cnf.clauses.len())?;
+
+    for clause in &cnf.clauses {
+        for lit in clause {
+            write!(out, "{} ", lit)?;
+        }
+        writeln!(out, "0")?;
+    }
+
+    Ok(())
+}
+
+/// A satisfying assignment found by a `Solver`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Assignment(Vec<bool>);
+
+impl Assignment {
+    /// Whether the DIMACS literal `lit` is true.
+    pub fn value(&self, lit: i32) -> bool {
+        self.0[lit.unsigned_abs() as usize - 1] == (lit > 0)
+    }
+}
+
+/// Literals within the solver, `2 * (var - 1) + complemented`.
+type Lit = usize;
+
+fn lit(dimacs: i32) -> Lit {
+    2 * (dimacs.unsigned_abs() as usize - 1) + (dimacs < 0) as usize
+}
+
+/// Conflicts before the first restart, scaled by the Luby sequence.
+const RESTART_BASE: usize = 100;
+
+/// Activity decay: the bump of the next conflict grows by its inverse.
+const ACTIVITY_DECAY: f64 = 0.95;
+
+/// A conflict-driven clause-learning solver: two watched literals, first-UIP
+/// learning with non-chronological backjumping, VSIDS-like activities with
+/// saved phases, and Luby restarts. Learnt clauses are never deleted, which
+/// suits the small formulas of single queries.
+#[derive(Debug, Clone)]
+pub struct Solver {
+    clauses: Vec<Vec<Lit>>,
+    /// Clauses watching each literal, visited when it becomes false.
+    watches: Vec<Vec<usize>>,
+    values: Vec<Option<bool>>,
+    levels: Vec<usize>,
+    /// Clause which implied each variable, `None` for decisions.
+    reasons: Vec<Option<usize>>,
+    trail: Vec<Lit>,
+    /// Length of the trail when each decision level started.
+    trail_limits: Vec<usize>,
+    /// Next literal of the trail to propagate.
+    head: usize,
+    activity: Vec<f64>,
+    bump: f64,
+    /// Unassigned variables, and possibly some assigned ones, by activity.
+    order: Order,
+    phases: Vec<bool>,
+    /// A clause was empty, or the unit clauses conflict.
+    unsatisfiable: bool,
+}
+
+impl Solver {
+    pub fn new(cnf: &Cnf) -> Self {
+        let n = cnf.var_count;
+        let mut solver = Self {
+            clauses: Vec::new(),
+            watches: vec![Vec::new(); 2 * n],
+            values: vec![None; n],
+            levels: vec![0; n],
+            reasons: vec![None; n],
+            trail: Vec::new(),
+            trail_limits: Vec::new(),
+            head: 0,
+            activity: vec![0.0; n],
+            bump: 1.0,
+            order: Order::new(n),
+            phases: vec![false; n],
+            unsatisfiable: false,
+        };
+
+        for clause in &cnf.clauses {
+            let mut clause: Vec<Lit> = clause.iter().map(|dimacs| lit(*dimacs)).collect();
+            clause.sort_unstable();
+            clause.dedup();
+
+            // Tautologies hold a literal and its complement, side by side.
+            if clause.windows(2).any(|pair| pair[0] ^ 1 == pair[1]) {
+                continue;
+            }
+
+            solver.add_clause(clause);
+        }
+
+        solver
+    }
+
+    fn value(&self, lit: Lit) -> Option<bool> {
+        self.values[lit / 2].map(|value| value != (lit & 1 == 1))
+    }
+
+    fn add_clause(&mut self, clause: Vec<Lit>) {
+        match clause.len() {
+            0 => self.unsatisfiable = true,
+            1 => match self.value(clause[0]) {
+                Some(false) => self.unsatisfiable = true,
+                Some(true) => (),
+                None => self.assign(clause[0], None),
+            },
+            _ => {
+                let index = self.clauses.len();
+                self.watches[clause[0]].push(index);
+                self.watches[clause[1]].push(index);
+                self.clauses.push(clause);
+            }
+        }
+    }
+
+    fn level(&self) -> usize {
+        self.trail_limits.len()
+    }
+
+    fn assign(&mut self, lit: Lit, reason: Option<usize>) {
+        let var = lit / 2;
+
+        self.values[var] = Some(lit & 1 == 0);
+        self.levels[var] = self.level();
+        self.reasons[var] = reason;
+        self.trail.push(lit);
+    }
+
+    /// Propagate the assignments of the trail, returning a conflicting
+    /// clause if any.
+    fn propagate(&mut self) -> Option<usize> {
+        while self.head < self.trail.len() {
+            let false_lit = self.trail[self.head] ^ 1;
+            self.head += 1;
+
+            let watching = std::mem::take(&mut self.watches[false_lit]);
+            let mut kept = Vec::with_capacity(watching.len());
+            let mut conflict = None;
+
+            for (i, index) in watching.iter().copied().enumerate() {
+                if conflict.is_some() {
+                    kept.extend_from_slice(&watching[i..]);
+                    break;
+                }
+
+                let clause = &mut self.clauses[index];
+                if clause[0] == false_lit {
+                    clause.swap(0, 1);
+                }
+
+                if self.values[clause[0] / 2].map(|value| value != (clause[0] & 1 == 1)) == Some(true) {
+                    kept.push(index);
+                    continue;
+                }
+
+                let replacement = (2..clause.len()).find(|k| {
+                    let lit = clause[*k];
+                    self.values[lit / 2].map(|value| value != (lit & 1 == 1)) != Some(false)
+                });
+
+                match replacement {
+                    Some(k) => {
+                        clause.swap(1, k);
+                        let watch = clause[1];
+                        self.watches[watch].push(index);
+                    }
+                    None => {
+                        kept.push(index);
+                        let first = clause[0];
+
+                        match self.value(first) {
+                            Some(false) => conflict = Some(index),
+                            _ => self.assign(first, Some(index)),
+                        }
+                    }
+                }
+            }
+
+            self.watches[false_lit] = kept;
+
+            if conflict.is_some() {
+                return conflict;
+            }
+        }
+
+        None
+    }
+
+    /// The first-UIP clause learnt from `conflict`, its asserting literal
+    /// first, and the level to backjump to.
+    fn analyze(&mut self, conflict: usize) -> (Vec<Lit>, usize) {
+        let mut seen = vec![false; self.values.len()];
+        let mut learnt = vec![0];
+        // Literals of the current level left to resolve.
+        let mut pending = 0;
+        let mut index = self.trail.len();
+        let mut clause = conflict;
+        // Reasons hold the literal they imply first, which is skipped.
+        let mut start = 0;
+
+        loop {
+            for k in start..self.clauses[clause].len() {
+                let lit = self.clauses[clause][k];
+                let var = lit / 2;
+
+                if seen[var] || self.levels[var] == 0 {
+                    continue;
+                }
+
+                seen[var] = true;
+                self.bump_activity(var);
+
+                if self.levels[var] == self.level() {
+                    pending += 1;
+                } else {
+                    learnt.push(lit);
+                }
+            }
+
+            // The latest literal of the trail involved in the conflict.
+            loop {
+                index -= 1;
+                if seen[self.trail[index] / 2] {
+                    break;
+                }
+            }
+
+            let lit = self.trail[index];
+            seen[lit / 2] = false;
+            pending -= 1;
+
+            if pending == 0 {
+                learnt[0] = lit ^ 1;
+                break;
+            }
+
+            clause = self.reasons[lit / 2].expect("only decisions have no reason");
+            start = 1;
+        }
+
+        // The literal of the highest level below the current one is watched
+        // second, so the learnt clause is unit after backjumping to it.
+        let mut backjump = 0;
+        for k in 1..learnt.len() {
+            if self.levels[learnt[k] / 2] > backjump {
+                backjump = self.levels[learnt[k] / 2];
+                learnt.swap(1, k);
+            }
+        }
+
+        (learnt, backjump)
+    }
+
+    fn bump_activity(&mut self, var: usize) {
+        self.activity[var] += self.bump;
+
+        // Scaling every activity alike keeps the order of the heap.
+        if self.activity[var] > 1e100 {
+            self.activity.iter_mut().for_each(|activity| *activity *= 1e-100);
+            self.bump *= 1e-100;
+        }
+
+        self.order.increase(var, &self.activity);
+    }
+
+    fn backjump(&mut self, level: usize) {
+        if self.level() <= level {
+            return;
+        }
+
+        let limit = self.trail_limits[level];
+        for lit in self.trail.drain(limit..) {
+            self.phases[lit / 2] = lit & 1 == 0;
+            self.values[lit / 2] = None;
+            self.reasons[lit / 2] = None;
+            self.order.insert(lit / 2, &self.activity);
+        }
+
+        self.trail_limits.truncate(level);
+        self.head = limit;
+    }
+
+    /// The unassigned variable of highest activity, in its saved phase.
+    fn decide(&mut self) -> Option<Lit> {
+        // Assigned variables are only removed from the heap when they reach the top.
+        loop {
+            let var = self.order.pop(&self.activity)?;
+
+            if self.values[var].is_none() {
+                return Some(2 * var + !self.phases[var] as usize);
+            }
+        }
+    }
+
+    /// A satisfying assignment of the formula, or `None` when it has none.
+    pub fn solve(&mut self) -> Option<Assignment> {
+        if self.unsatisfiable {
+            return None;
+        }
+
+        let mut conflicts = 0;
+        let mut restart = 1;
+        let mut budget = RESTART_BASE;
+
+        loop {
+            if let Some(conflict) = self.propagate() {
+                if self.level() == 0 {
+                    self.unsatisfiable = true;
+                    return None;
+                }
+
+                let (learnt, level) = self.analyze(conflict);
+                self.backjump(level);
+                self.bump /= ACTIVITY_DECAY;
+
+                let asserting = learnt[0];
+                if learnt.len() == 1 {
+                    self.assign(asserting, None);
+                } else {
+                    let index = self.clauses.len();
+                    self.watches[learnt[0]].push(index);
+                    self.watches[learnt[1]].push(index);
+                    self.clauses.push(learnt);
+                    self.assign(asserting, Some(index));
+                }
+
+                conflicts += 1;
+                if conflicts == budget {
+                    conflicts = 0;
+                    restart += 1;
+                    budget = RESTART_BASE * luby(restart);
+                    self.backjump(0);
+                }
+
+                continue;
+            }
+
+            match self.decide() {
+                Some(lit) => {
+                    self.trail_limits.push(self.trail.len());
+                    self.assign(lit, None);
+                }
+                None => {
+                    let assignment = Assignment(self.values.iter().map(|value| value.unwrap()).collect());
+                    self.backjump(0);
+
+                    return Some(assignment);
+                }
+            }
+        }
+    }
+}
+
+/// A binary max-heap of variables by activity, lowest variable first among
+/// equal activities, with the position of each variable in the heap so its
+/// activity can be increased in place.
+#[derive(Debug, Clone)]
+struct Order {
+    heap: Vec<usize>,
+    positions: Vec<Option<usize>>,
+}
+
+impl Order {
+    /// Every variable, all of activity zero.
+    fn new(var_count: usize) -> Self {
+        Self { heap: (0..var_count).collect(), positions: (0..var_count).map(Some).collect() }
+    }
+
+    fn before(a: usize, b: usize, activity: &[f64]) -> bool {
+        activity[a].total_cmp(&activity[b]).then(b.cmp(&a)).is_gt()
+    }
+
+    fn insert(&mut self, var: usize, activity: &[f64]) {
+        if self.positions[var].is_none() {
+            self.positions[var] = Some(self.heap.len());
+            self.heap.push(var);
+            self.up(self.heap.len() - 1, activity);
+        }
+    }
+
+    /// Restore the heap after the activity of `var` increased.
+    fn increase(&mut self, var: usize, activity: &[f64]) {
+        if let Some(position) = self.positions[var] {
+            self.up(position, activity);
+        }
+    }
+
+    fn pop(&mut self, activity: &[f64]) -> Option<usize> {
+        let last = self.heap.pop()?;
+        if self.heap.is_empty() {
+            self.positions[last] = None;
+            return Some(last);
+        }
+
+        let top = std::mem::replace(&mut self.heap[0], last);
+        self.positions[top] = None;
+        self.positions[last] = Some(0);
+        self.down(0, activity);
+
+        Some(top)
+    }
+
+    fn swap(&mut self, i: usize, j: usize) {
+        self.heap.swap(i, j);
+        self.positions[self.heap[i]] = Some(i);
+        self.positions[self.heap[j]] = Some(j);
+    }
+
+    fn up(&mut self, mut i: usize, activity: &[f64]) {
+        while i > 0 && Self::before(self.heap[i], self.heap[(i - 1) / 2], activity) {
+            self.swap(i, (i - 1) / 2);
+            i = (i - 1) / 2;
+        }
+    }
+
+    fn down(&mut self, mut i: usize, activity: &[f64]) {
+        loop {
+            let mut first = i;
+            for child in [2 * i + 1, 2 * i + 2] {
+                if child < self.heap.len() && Self::before(self.heap[child], self.heap[first], activity) {
+                    first = child;
+                }
+            }
+
+            if first == i {
+                return;
+            }
+
+            self.swap(i, first);
+            i = first;
+        }
+    }
+}
+
+/// The `i`-th term of the Luby sequence 1, 1, 2, 1, 1, 2, 4, 1, ..., from 1.
+fn luby(i: usize) -> usize {
+    let mut i = i;
+
+    loop {
+        // The smallest 2^k - 1 at least i.
+        let mut k = 1;
+        while (1 << k) - 1 < i {
+            k += 1;
+        }
+
+        if (1 << k) - 1 == i {
+            return 1 << (k - 1);
+        }
+
+        i -= (1 << (k - 1)) - 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    fn cnf(var_count: usize, clauses: &[&[i32]]) -> Cnf {
+        Cnf { var_count, clauses: clauses.iter().map(|clause| clause.to_vec()).collect() }
+    }
+
+    fn satisfies(cnf: &Cnf, assignment: &Assignment) -> bool {
+        cnf.clauses.iter().all(|clause| clause.iter().any(|lit| assignment.value(*lit)))
+    }
+
+    #[test]
+    fn test_small() {
+        let formula = cnf(3, &[&[1, 2], &[-1, 3], &[-2, 3], &[-3, 1]]);
+        let assignment = Solver::new(&formula).solve().unwrap();
+        assert!(satisfies(&formula, &assignment));
+        assert!(assignment.value(1) && assignment.value(3));
+
+        assert_eq!(Solver::new(&cnf(1, &[&[1], &[-1]])).solve(), None);
+        assert_eq!(Solver::new(&cnf(1, &[&[]])).solve(), None);
+        assert!(Solver::new(&cnf(2, &[&[1, -1], &[2]])).solve().is_some());
+        assert!(Solver::new(&cnf(0, &[])).solve().is_some());
+    }
+
+    #[test]
+    fn test_pigeonhole() {
+        // Five pigeons in four holes: p(i, j) when pigeon i sits in hole j.
+        let (pigeons, holes) = (5, 4);
+        let p = |i: usize, j: usize| (i * holes + j + 1) as i32;
+
+        let mut formula = Cnf { var_count: pigeons * holes, clauses: Vec::new() };
+        for i in 0..pigeons {
+            formula.add_clause((0..holes).map(|j| p(i, j)).collect());
+        }
+        for j in 0..holes {
+            for a in 0..pigeons {
+                for b in a + 1..pigeons {
+                    formula.add_clause(vec![-p(a, j), -p(b, j)]);
+                }
+            }
+        }
+
+        assert_eq!(Solver::new(&formula).solve(), None);
+
+        // With one pigeon less, they fit.
+        formula.clauses.retain(|clause| clause.iter().all(|lit| lit.unsigned_abs() as usize <= (pigeons - 1) * holes));
+        let assignment = Solver::new(&formula).solve().unwrap();
+        assert!(satisfies(&formula, &assignment));
+    }
+
+    #[test]
+    fn test_random_3sat() {
+        let mut rng = Rng::new(0x9e37_79b9_7f4a_7c15);
+
+        // Around the threshold of 4.26 clauses per variable, where about
+        // half of the formulas are satisfiable.
+        for _ in 0..200 {
+            let n = 3 + rng.below(10);
+            let clauses = (0..(n * 426 / 100).max(1)).map(|_| {
+                (0..3).map(|_| (1 + rng.below(n)) as i32 * if rng.below(2) == 0 { 1 } else { -1 }).collect()
+            }).collect();
+            let formula = Cnf { var_count: n, clauses };
+
+            let brute_force = (0..1usize << n).any(|bits| {
+                let assignment = Assignment((0..n).map(|i| bits >> i & 1 == 1).collect());
+                satisfies(&formula, &assignment)
+            });
+
+            match Solver::new(&formula).solve() {
+                Some(assignment) => assert!(satisfies(&formula, &assignment), "{:?}", formula),
+                None => assert!(!brute_force, "{:?}", formula),
+            }
+        }
+    }
+
+    #[test]
+    fn test_order() {
+        let mut activity = vec![0.0, 3.0, 1.0, 3.0, 2.0];
+        let mut order = Order::new(5);
+        order.heap.clone().into_iter().for_each(|var| order.increase(var, &activity));
+
+        assert_eq!(order.pop(&activity), Some(1));
+        activity[0] = 5.0;
+        order.increase(0, &activity);
+        order.insert(1, &activity);
+
+        let popped: Vec<usize> = std::iter::from_fn(|| order.pop(&activity)).collect();
+        assert_eq!(popped, vec![0, 1, 3, 4, 2]);
+    }
+
+    #[test]
+    fn test_luby() {
+        assert_eq!((1..16).map(luby).collect::<Vec<_>>(), vec![1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8]);
+    }
+
+    #[test]
+    fn test_dimacs() {
+        let mut out = Vec::new();
+        write_dimacs(&mut out, &cnf(3, &[&[1, -2], &[3]])).unwrap();
+
+        assert_eq!(String::from_utf8(out).unwrap(), "p cnf 3 2\n1 -2 0\n3 0\n");
+    }
+}
@@ -1,11 +1,61 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+mod sequential;
+pub use sequential::{Sequential, Stepper};
 
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub enum SignalState {
     High,
     Low,
     Unknown,
+    /// Not driven, as on a released tri-state bus.
+    HighImpedance,
+}
+
+impl SignalState {
+    /// Whether the state is a proper logic level, `High` or `Low`.
+    pub fn is_known(&self) -> bool {
+        matches!(self, Self::High | Self::Low)
+    }
+
+    /// Value of a net driven by both `self` and `other`.
+    ///
+    /// A high-impedance driver gives way to the other one, equal drivers
+    /// agree and anything else conflicts into `Unknown`.
+    pub fn resolve(self, other: SignalState) -> SignalState {
+        match (self, other) {
+            (Self::HighImpedance, state) | (state, Self::HighImpedance) => state,
+            (a, b) if a == b => a,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl std::convert::TryFrom<char> for SignalState {
+    type Error = &'static str;
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        match c {
+            '1' => Ok(Self::High),
+            '0' => Ok(Self::Low),
+            'x' | 'X' => Ok(Self::Unknown),
+            'z' | 'Z' => Ok(Self::HighImpedance),
+            _ => Err("expected [0|1|x|z] to create a SignalState"),
+        }
+    }
 }
+
+impl std::fmt::Display for SignalState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::High => write!(f, "1"),
+            Self::Low => write!(f, "0"),
+            Self::Unknown => write!(f, "x"),
+            Self::HighImpedance => write!(f, "z"),
+        }
+    }
+}
+
 #[derive(PartialEq, Clone, Debug)]
 pub struct Signal {
     name: String,
@@ -27,9 +77,21 @@ impl Signal {
     pub fn set_high(&mut self) {
         self.state = SignalState::High;
     }
+
+    pub fn set(&mut self, state: SignalState) {
+        self.state = state;
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn state(&self) -> SignalState {
+        self.state
+    }
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Clone, Debug, Default)]
 pub struct Signals {
     signals: HashMap<String, Signal>,
 }
@@ -58,6 +120,20 @@ impl Signals {
         }
     }
 
+    /// Merge the outputs of one driver into `self`.
+    ///
+    /// Nets listed in `driven` already got a value from another driver during
+    /// the same evaluation and are resolved against it instead of overwritten.
+    pub fn drive_with(&mut self, other: Signals, driven: &mut HashSet<String>) {
+        for (name, mut signal) in other.signals.into_iter() {
+            if !driven.insert(name.clone()) {
+                signal.state = self.get(&name).resolve(signal.state);
+            }
+
+            self.signals.insert(name, signal);
+        }
+    }
+
     pub fn get(&self, name: &str) -> SignalState {
         match self.signals.get(name) {
             Some(signal) => signal.state,
@@ -66,10 +142,28 @@ impl Signals {
     }
 
     pub fn iter(&self) -> impl Iterator<Item=&Signal> {
-        self.signals.iter().map(|(_key, value)| value)
+        self.signals.values()
+    }
+
+    /// The signals named `{scope}/{name}`, named `name`.
+    pub fn scope(&self, scope: &str) -> Signals {
+        let prefix = format!("{}/", scope);
+
+        self.iter()
+            .filter_map(|signal| Some((signal.name.strip_prefix(&prefix)?, signal.state)))
+            .fold(SignalsBuilder::new(), |builder, (name, state)| builder.add_signal(name, state))
+            .build()
+    }
+
+    /// Every signal renamed `{scope}/{name}`.
+    pub fn in_scope(self, scope: &str) -> Signals {
+        self.signals.into_values()
+            .fold(SignalsBuilder::new(), |builder, signal| builder.add_signal(&format!("{}/{}", scope, signal.name), signal.state))
+            .build()
     }
 }
 
+#[derive(Default)]
 pub struct SignalsBuilder {
     signals: HashMap<String, Signal>,
 }
@@ -95,8 +189,6 @@ impl SignalsBuilder {
     }
 }
 
-use std::collections::HashSet;
-
 pub trait Simulable {
     fn get_inputs(&self) -> HashSet<String>;
     fn children(&self) -> Vec<Box<dyn Simulable>>;
@@ -111,12 +203,49 @@ pub trait Simulable {
     }
 
     fn stim(&self, inputs: Signals) -> Signals {
+        let mut driven = HashSet::new();
+
         self.children().into_iter().fold(inputs, |mut signals, child| {
             let child_inputs = self.filter_for(&(*child), &signals);
 
-            signals.update_with(child.stim(child_inputs));
+            signals.drive_with(child.stim(child_inputs), &mut driven);
 
             signals
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        use SignalState::*;
+
+        assert_eq!(HighImpedance.resolve(High), High);
+        assert_eq!(Low.resolve(HighImpedance), Low);
+        assert_eq!(HighImpedance.resolve(HighImpedance), HighImpedance);
+        assert_eq!(High.resolve(High), High);
+        assert_eq!(High.resolve(Low), Unknown);
+        assert_eq!(Unknown.resolve(Low), Unknown);
+    }
+
+    #[test]
+    fn test_drive_with() {
+        let mut signals = SignalsBuilder::new()
+            .add_signal("bus", SignalState::Low)
+            .build();
+        let mut driven = HashSet::new();
+
+        // The first driver of an evaluation overwrites the previous value.
+        signals.drive_with(SignalsBuilder::new().add_signal("bus", SignalState::HighImpedance).build(), &mut driven);
+        assert_eq!(signals.get("bus"), SignalState::HighImpedance);
+
+        signals.drive_with(SignalsBuilder::new().add_signal("bus", SignalState::High).build(), &mut driven);
+        assert_eq!(signals.get("bus"), SignalState::High);
+
+        signals.drive_with(SignalsBuilder::new().add_signal("bus", SignalState::Low).build(), &mut driven);
+        assert_eq!(signals.get("bus"), SignalState::Unknown);
+    }
+}
@@ -0,0 +1,99 @@
+use super::{Simulable, SignalState, Signals, SignalsBuilder};
+
+/// A design holding state between evaluations.
+pub trait Sequential: Simulable {
+    /// State elements (latch outputs) before the first step.
+    fn initial_state(&self) -> Signals;
+
+    /// State elements after an evaluation, given the current `state`, the
+    /// settled nets before (`previous`) and after (`current`) the evaluation.
+    ///
+    /// `global_clock` is true when the implicit clock of the design ticks.
+    fn next_state(&self, state: &Signals, previous: &Signals, current: &Signals, global_clock: bool) -> Signals;
+}
+
+/// Cycle-based simulation of a `Sequential` design.
+///
+/// Each evaluation feeds the state into the combinational logic, then lets
+/// the state elements sample the settled nets.
+pub struct Stepper<'a, S: Sequential> {
+    design: &'a S,
+    state: Signals,
+    previous: Signals,
+    cycle: usize,
+}
+
+impl<'a, S: Sequential> Stepper<'a, S> {
+    pub fn new(design: &'a S) -> Self {
+        Self {
+            design,
+            state: design.initial_state(),
+            previous: Signals::new(),
+            cycle: 0,
+        }
+    }
+
+    pub fn state(&self) -> &Signals {
+        &self.state
+    }
+
+    /// Number of global clock cycles simulated so far.
+    pub fn cycle(&self) -> usize {
+        self.cycle
+    }
+
+    fn evaluate(&self, inputs: &Signals) -> Signals {
+        let mut signals = inputs.clone();
+        signals.update_with(self.state.clone());
+
+        self.design.stim(signals)
+    }
+
+    fn run(&mut self, inputs: Signals, global_clock: bool) -> Signals {
+        let mut nets = self.evaluate(&inputs);
+        let mut previous = std::mem::replace(&mut self.previous, Signals::new());
+        let mut global_clock = global_clock;
+
+        // Edges are only seen by the first update; transparent latches may
+        // then need a few more rounds until the state is stable.
+        for _ in 0..=self.state.iter().count() {
+            let next = self.design.next_state(&self.state, &previous, &nets, global_clock);
+            if next == self.state {
+                break;
+            }
+
+            self.state = next;
+            nets = self.evaluate(&inputs);
+            previous = nets.clone();
+            global_clock = false;
+        }
+
+        self.previous = nets.clone();
+
+        nets
+    }
+
+    /// Apply `inputs` and let the controlled latches react to them, without
+    /// ticking the global clock.
+    pub fn settle(&mut self, inputs: Signals) -> Signals {
+        self.run(inputs, false)
+    }
+
+    /// Apply `inputs` and tick the global clock once.
+    pub fn step(&mut self, inputs: Signals) -> Signals {
+        self.cycle += 1;
+        self.run(inputs, true)
+    }
+
+    /// One full period of `clock`: a low phase followed by a high phase
+    /// (the rising edge), which also ticks the global clock.
+    pub fn tick(&mut self, clock: &str, inputs: Signals) -> Signals {
+        let mut low = inputs.clone();
+        low.update_with(SignalsBuilder::new().add_signal(clock, SignalState::Low).build());
+        self.settle(low);
+
+        let mut high = inputs;
+        high.update_with(SignalsBuilder::new().add_signal(clock, SignalState::High).build());
+        self.step(high)
+    }
+}
@@ -0,0 +1,307 @@
+use crate::blif::{CombinationalLoop, InputValue, Model};
+use crate::netlist::{Cell, Netlist};
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Size and shape of a model, counting its own gates, latches and instances.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Stats {
+    pub model: String,
+    pub gates: usize,
+    pub latches: usize,
+    pub subckts: usize,
+    /// Number of gates of each `GateFunction`, by name.
+    pub functions: BTreeMap<String, usize>,
+    pub cubes: usize,
+    pub literals: usize,
+    /// Largest number of gates and tri-state buffers on a path from a
+    /// primary input or latch, through the instances, or `None` when the
+    /// model has a combinational loop.
+    pub depth: Option<usize>,
+    pub max_fanin: usize,
+    /// Readers of a net are the gates, latches and instances of the model.
+    pub max_fanout: usize,
+}
+
+impl Stats {
+    pub fn new(model: &Model) -> Self {
+        let mut functions = BTreeMap::new();
+        for gate in &model.gates {
+            *functions.entry(gate.function().to_string()).or_insert(0) += 1;
+        }
+
+        let rows = model.gates.iter().flat_map(|gate| &gate.single_output_cover);
+        let literals = rows.clone()
+            .map(|(inputs, _)| inputs.iter().filter(|input| **input != InputValue::NotUsed).count())
+            .sum();
+
+        let connections = Connections::new(model);
+
+        Self {
+            model: model.name.clone(),
+            gates: model.gates.len(),
+            latches: model.latches.len(),
+            subckts: model.subckts.len(),
+            functions,
+            cubes: rows.count(),
+            literals,
+            depth: depth(model),
+            max_fanin: model.gates.iter().map(|gate| gate.inputs.len()).max().unwrap_or(0),
+            max_fanout: connections.readers.values().copied().max().unwrap_or(0),
+        }
+    }
+}
+
+impl std::fmt::Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let functions: Vec<String> = self.functions.iter().map(|(function, count)| format!("{}: {}", function, count)).collect();
+
+        writeln!(f, "model {}", self.model)?;
+        writeln!(f, "  gates: {} ({})", self.gates, functions.join(", "))?;
+        writeln!(f, "  latches: {}, subckts: {}", self.latches, self.subckts)?;
+        writeln!(f, "  cubes: {}, literals: {}", self.cubes, self.literals)?;
+
+        match self.depth {
+            Some(depth) => write!(f, "  depth: {}", depth)?,
+            None => write!(f, "  depth: -")?,
+        }
+        write!(f, ", max fan-in: {}, max fan-out: {}", self.max_fanin, self.max_fanout)
+    }
+}
+
+/// Something likely wrong with a model, though it parses and simulates.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Lint {
+    /// A net read by the model but driven by nothing.
+    Undriven(String),
+    /// A net with several drivers, unless they are all tri-state buffers.
+    MultiplyDriven { net: String, drivers: usize },
+    /// A primary output driven by nothing.
+    OutputUndriven(String),
+    UnusedInput(String),
+    /// A gate output read by nothing and not a primary output.
+    DanglingOutput(String),
+    /// A row of the cover of `output` with `width` columns for `inputs` inputs.
+    CoverWidth { output: String, row: usize, width: usize, inputs: usize },
+    Loop(CombinationalLoop),
+}
+
+impl std::fmt::Display for Lint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Undriven(net) => write!(f, "net `{}` is read but never driven", net),
+            Self::MultiplyDriven { net, drivers } => write!(f, "net `{}` has {} drivers", net, drivers),
+            Self::OutputUndriven(net) => write!(f, "output `{}` is never driven", net),
+            Self::UnusedInput(net) => write!(f, "input `{}` is never read", net),
+            Self::DanglingOutput(net) => write!(f, "gate output `{}` is never read", net),
+            Self::CoverWidth { output, row, width, inputs } => {
+                write!(f, "row {} of the cover of `{}` has {} columns for {} inputs", row + 1, output, width, inputs)
+            }
+            Self::Loop(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+/// Look for the `Lint`s of `model`, not including the models it instantiates.
+///
+/// Nets connected to an instance of an unknown model could be driven or read
+/// by it, so they are only checked for multiple drivers.
+pub fn lint(model: &Model) -> Vec<Lint> {
+    let connections = Connections::new(model);
+    let mut lints = Vec::new();
+
+    let outputs: HashSet<&str> = model.outputs.iter().map(String::as_str).collect();
+    let gate_outputs: HashSet<&str> = model.gates.iter().map(|gate| gate.output.as_str()).collect();
+
+    for net in model.nets() {
+        let drivers = connections.drivers.get(net.as_str()).map_or(&[][..], Vec::as_slice);
+        let readers = connections.readers.get(net.as_str()).copied().unwrap_or(0);
+        let unknown = connections.unknown.contains(net.as_str());
+
+        if drivers.len() > 1 && !drivers.iter().all(|tbuf| *tbuf) {
+            lints.push(Lint::MultiplyDriven { net: net.clone(), drivers: drivers.len() });
+        }
+
+        if unknown {
+            continue;
+        }
+
+        if outputs.contains(net.as_str()) {
+            if drivers.is_empty() {
+                lints.push(Lint::OutputUndriven(net));
+            }
+        } else if drivers.is_empty() && readers > 0 {
+            lints.push(Lint::Undriven(net));
+        } else if readers == 0 && model.inputs.contains(&net) {
+            lints.push(Lint::UnusedInput(net));
+        } else if readers == 0 && gate_outputs.contains(net.as_str()) {
+            lints.push(Lint::DanglingOutput(net));
+        }
+    }
+
+    for gate in &model.gates {
+        for (row, (inputs, _)) in gate.single_output_cover.iter().enumerate() {
+            if inputs.len() != gate.inputs.len() {
+                lints.push(Lint::CoverWidth { output: gate.output.clone(), row, width: inputs.len(), inputs: gate.inputs.len() });
+            }
+        }
+    }
+
+    if let Err(err) = model.evaluation_order() {
+        lints.push(Lint::Loop(err));
+    }
+
+    lints
+}
+
+/// Drivers and readers of the nets of a model.
+struct Connections<'a> {
+    /// Whether each driver is a tri-state buffer, primary inputs included.
+    drivers: HashMap<&'a str, Vec<bool>>,
+    readers: HashMap<&'a str, usize>,
+    /// Nets connected to instances of unknown models.
+    unknown: HashSet<&'a str>,
+}
+
+impl<'a> Connections<'a> {
+    fn new(model: &'a Model) -> Self {
+        let mut connections = Self { drivers: HashMap::new(), readers: HashMap::new(), unknown: HashSet::new() };
+
+        for input in &model.inputs {
+            connections.drive(input, false);
+        }
+
+        for gate in &model.gates {
+            gate.inputs.iter().for_each(|input| connections.read(input));
+            connections.drive(&gate.output, false);
+        }
+
+        for latch in &model.latches {
+            connections.read(&latch.input);
+            if let Some((_, control)) = latch.control.as_ref().filter(|(_, control)| control != "NIL") {
+                connections.read(control);
+            }
+            connections.drive(&latch.output, false);
+        }
+
+        for subckt in &model.subckts {
+            match subckt.bindings() {
+                Some((inputs, outputs)) => {
+                    inputs.iter().for_each(|(_, net)| connections.read(net));
+                    outputs.iter().for_each(|(_, net)| connections.drive(net, subckt.definition.is_none()));
+                }
+                None => connections.unknown.extend(subckt.connections.iter().map(|(_, actual)| actual.as_str())),
+            }
+        }
+
+        connections
+    }
+
+    fn drive(&mut self, net: &'a str, tbuf: bool) {
+        self.drivers.entry(net).or_default().push(tbuf);
+    }
+
+    fn read(&mut self, net: &'a str) {
+        *self.readers.entry(net).or_insert(0) += 1;
+    }
+}
+
+fn depth(model: &Model) -> Option<usize> {
+    let netlist = Netlist::new(model).ok()?;
+    let mut levels = vec![0; netlist.net_count()];
+
+    // Nodes are in topological order, the drivers of a net before its readers.
+    for node in &netlist.nodes {
+        let cost = match node.cell {
+            Cell::Cover(_) | Cell::Tbuf { .. } => 1,
+            Cell::Buffer(_) | Cell::Const(_) => 0,
+        };
+        let level = netlist.node_inputs(node).iter().map(|net| levels[*net]).max().unwrap_or(0) + cost;
+
+        levels[node.output] = levels[node.output].max(level);
+    }
+
+    Some(levels.into_iter().max().unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blif::{self, InputValue, LogicGate};
+
+    #[test]
+    fn test_smol() {
+        let blif = blif::parse(include_str!("../fixtures/smol.blif")).unwrap();
+        let model = blif.top().unwrap();
+
+        assert_eq!(Stats::new(model).to_string(), concat!(
+            "model blinky\n",
+            "  gates: 5 (0: 2, 1: 1, AND: 1, NOT: 1)\n",
+            "  latches: 0, subckts: 0\n",
+            "  cubes: 3, literals: 3\n",
+            "  depth: 2, max fan-in: 2, max fan-out: 1",
+        ));
+
+        // The constants of Yosys are never read.
+        assert_eq!(lint(model), vec![
+            Lint::DanglingOutput("$false".into()),
+            Lint::DanglingOutput("$true".into()),
+            Lint::DanglingOutput("$undef".into()),
+        ]);
+    }
+
+    #[test]
+    fn test_depth_through_instances() {
+        let blif = blif::parse(include_str!("../fixtures/med.blif")).unwrap();
+
+        assert_eq!(Stats::new(blif.model("a_not_b").unwrap()).depth, Some(3));
+        // Through both chained instances, to `o_m2`.
+        assert_eq!(Stats::new(blif.top().unwrap()).depth, Some(6));
+    }
+
+    #[test]
+    fn test_lints() {
+        let blif = blif::parse(concat!(
+            ".model lints\n",
+            ".inputs a b unused\n",
+            ".outputs y z floating bus\n",
+            ".names a b y\n",
+            "11 1\n",
+            ".names a y\n",
+            "0 1\n",
+            ".names a c z\n",
+            "11 1\n",
+            ".names b dangling\n",
+            "1 1\n",
+            ".subckt $_TBUF_ A=a E=b Y=bus\n",
+            ".subckt $_TBUF_ A=b E=a Y=bus\n",
+            ".subckt blackbox i=a o=mystery\n",
+            ".end\n",
+        )).unwrap();
+
+        assert_eq!(lint(blif.top().unwrap()), vec![
+            Lint::UnusedInput("unused".into()),
+            Lint::MultiplyDriven { net: "y".into(), drivers: 2 },
+            Lint::OutputUndriven("floating".into()),
+            Lint::Undriven("c".into()),
+            Lint::DanglingOutput("dangling".into()),
+        ]);
+    }
+
+    #[test]
+    fn test_cover_width() {
+        // The parser rejects such rows, but a model built in code can have them.
+        let mut model = blif::parse(".model wide\n.inputs a b\n.outputs y\n.end\n").unwrap().top().unwrap().clone();
+        model.gates.push(LogicGate::new(vec!["a".into(), "b".into()], "y".into(), vec![
+            (vec![InputValue::Uncomplemented], InputValue::Uncomplemented),
+            (vec![InputValue::Complemented; 3], InputValue::Uncomplemented),
+        ]));
+
+        assert_eq!(lint(&model), vec![
+            Lint::CoverWidth { output: "y".into(), row: 0, width: 1, inputs: 2 },
+            Lint::CoverWidth { output: "y".into(), row: 1, width: 3, inputs: 2 },
+        ]);
+        assert_eq!(lint(&model)[0].to_string(), "row 1 of the cover of `y` has 1 columns for 2 inputs");
+        assert_eq!(Stats::new(&model).cubes, 2);
+    }
+}
@@ -0,0 +1,371 @@
+use crate::blif::Model;
+use crate::simulation::{SignalState, Signals, SignalsBuilder, Stepper};
+
+/// A stimulus file: input vectors applied in order to a model, with the
+/// outputs expected after each of them.
+///
+/// ```text
+/// # comments run to the end of the line
+/// .inputs a b       optional, defaults to the `.inputs` of the model
+/// .outputs y        optional, defaults to the `.outputs` of the model
+/// .clock clk        optional clock input, ticked by `@` lines
+/// 00 | 0            inputs, then the expected outputs after `|`
+/// 1 0 | 1           whitespace inside a vector is ignored
+/// 11                no `|`: nothing is checked
+/// 01 | -            `-` is a don't-care
+/// @ 01 | 1          `@`: apply the inputs and tick the clock once
+/// @ | 0             an empty vector keeps the previous inputs
+/// ```
+///
+/// Inputs are written as one `0`/`1`/`x`/`z` per input and expected outputs
+/// as one `0`/`1`/`x`/`z`/`-` per output, in declaration order. A `@` line
+/// ticks the `.clock` input, or the global clock when there is none.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Stimulus {
+    pub inputs: Option<Declaration>,
+    pub outputs: Option<Declaration>,
+    /// Holds a single net.
+    pub clock: Option<Declaration>,
+    pub vectors: Vec<Vector>,
+}
+
+/// A `.inputs`, `.outputs` or `.clock` line of a stimulus file.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Declaration {
+    pub line: usize,
+    pub nets: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Vector {
+    /// 1-based line of the vector in the stimulus file.
+    pub line: usize,
+    /// Whether the vector is a cycle (`@`) rather than a combinational step.
+    pub clocked: bool,
+    /// `None` keeps the inputs of the previous vector.
+    pub inputs: Option<Vec<SignalState>>,
+    /// Expected outputs, `None` being a don't-care. Empty when nothing is checked.
+    pub expected: Vec<Option<SignalState>>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct StimulusError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for StimulusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for StimulusError {}
+
+fn error(line: usize, message: String) -> StimulusError {
+    StimulusError { line, message }
+}
+
+/// Parse the characters of `field`, ignoring whitespace.
+fn parse_states<T>(field: &str, line: usize, parse: impl Fn(char) -> Option<T>) -> Result<Vec<T>, StimulusError> {
+    field.chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| parse(c).ok_or_else(|| error(line, format!("unexpected `{}` in vector", c))))
+        .collect()
+}
+
+impl Stimulus {
+    pub fn parse(input: &str) -> Result<Self, StimulusError> {
+        let mut stimulus = Self::default();
+
+        for (i, line) in input.lines().enumerate() {
+            let number = i + 1;
+            let line = line.split('#').next().unwrap().trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            if line.starts_with('.') {
+                if !stimulus.vectors.is_empty() {
+                    return Err(error(number, "declarations must come before the first vector".into()));
+                }
+
+                let mut tokens = line.split_whitespace();
+                let directive = tokens.next().unwrap();
+                let nets: Vec<String> = tokens.map(String::from).collect();
+
+                match (directive, nets.as_slice()) {
+                    (".inputs", _) => stimulus.inputs = Some(Declaration { line: number, nets }),
+                    (".outputs", _) => stimulus.outputs = Some(Declaration { line: number, nets }),
+                    (".clock", [_]) => stimulus.clock = Some(Declaration { line: number, nets }),
+                    (".clock", _) => return Err(error(number, "expected a single clock net".into())),
+                    (directive, _) => return Err(error(number, format!("unknown declaration `{}`", directive))),
+                }
+
+                continue;
+            }
+
+            let (clocked, line) = match line.strip_prefix('@') {
+                Some(rest) => (true, rest),
+                None => (false, line),
+            };
+
+            let (inputs, expected) = match line.split_once('|') {
+                Some((inputs, expected)) => (inputs, Some(expected)),
+                None => (line, None),
+            };
+
+            let inputs = parse_states(inputs, number, |c| SignalState::try_from(c).ok())?;
+            let expected = match expected {
+                Some(expected) => {
+                    let expected = parse_states(expected, number, |c| match c {
+                        '-' => Some(None),
+                        c => SignalState::try_from(c).ok().map(Some),
+                    })?;
+
+                    if expected.is_empty() {
+                        return Err(error(number, "expected outputs after `|`".into()));
+                    }
+
+                    expected
+                }
+                None => Vec::new(),
+            };
+
+            stimulus.vectors.push(Vector {
+                line: number,
+                clocked,
+                inputs: if inputs.is_empty() { None } else { Some(inputs) },
+                expected,
+            });
+        }
+
+        Ok(stimulus)
+    }
+
+    pub fn clock(&self) -> Option<&String> {
+        self.clock.as_ref().map(|clock| &clock.nets[0])
+    }
+
+    /// Nets of `declaration`, all of which must be in `nets`, or `default`.
+    fn nets<'a>(declaration: &'a Option<Declaration>, nets: &[String], default: Vec<&'a String>, kind: &str) -> Result<Vec<&'a String>, StimulusError> {
+        match declaration {
+            Some(declaration) => {
+                match declaration.nets.iter().find(|net| !nets.contains(net)) {
+                    Some(net) => Err(error(declaration.line, format!("`{}` is not an {} of the model", net, kind))),
+                    None => Ok(declaration.nets.iter().collect()),
+                }
+            }
+            None => Ok(default),
+        }
+    }
+
+    /// Apply the vectors to `model`, from its initial state.
+    ///
+    /// Fails when the stimulus does not fit the model; mismatching outputs
+    /// are reported in the returned `Report`.
+    pub fn run(&self, model: &Model) -> Result<Report, StimulusError> {
+        let inputs = Self::nets(
+            &self.inputs,
+            &model.inputs,
+            model.inputs.iter().filter(|input| Some(*input) != self.clock()).collect(),
+            "input",
+        )?;
+        let outputs = Self::nets(&self.outputs, &model.outputs, model.outputs.iter().collect(), "output")?;
+
+        Self::nets(&self.clock, &model.inputs, Vec::new(), "input")?;
+
+        let mut stepper = Stepper::new(model);
+        let mut report = Report { vectors: self.vectors.len(), failed: 0, mismatches: Vec::new() };
+
+        let mut applied = vec![SignalState::Unknown; inputs.len()];
+        // The clock idles low and stays high after a tick, until the next one.
+        let mut clock_level = SignalState::Low;
+
+        for (index, vector) in self.vectors.iter().enumerate() {
+            if let Some(states) = &vector.inputs {
+                if states.len() != inputs.len() {
+                    return Err(error(vector.line, format!("expected {} inputs, got {}", inputs.len(), states.len())));
+                }
+
+                applied = states.clone();
+            }
+
+            if !vector.expected.is_empty() && vector.expected.len() != outputs.len() {
+                return Err(error(vector.line, format!("expected {} outputs, got {}", outputs.len(), vector.expected.len())));
+            }
+
+            let signals = std::iter::zip(&inputs, &applied).fold(SignalsBuilder::new(), |builder, (input, state)| {
+                builder.add_signal(input, *state)
+            }).build();
+
+            let nets = match (self.clock(), vector.clocked) {
+                (Some(clock), true) => {
+                    clock_level = SignalState::High;
+                    stepper.tick(clock, signals)
+                }
+                (None, true) => stepper.step(signals),
+                (Some(clock), false) => {
+                    let mut signals = signals;
+                    signals.update_with(SignalsBuilder::new().add_signal(clock, clock_level).build());
+                    stepper.settle(signals)
+                }
+                (None, false) => stepper.settle(signals),
+            };
+
+            let mismatches = report.check(index, vector, &outputs, &nets);
+            if mismatches > 0 {
+                report.failed += 1;
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// An output differing from the expected value.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Mismatch {
+    /// 0-based index of the vector among the vectors of the stimulus.
+    pub vector: usize,
+    pub line: usize,
+    pub net: String,
+    pub expected: SignalState,
+    pub actual: SignalState,
+}
+
+impl std::fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "vector {} (line {}): `{}` is {}, expected {}",
+            self.vector, self.line, self.net, self.actual, self.expected,
+        )
+    }
+}
+
+/// Outcome of running a `Stimulus`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Report {
+    pub vectors: usize,
+    /// Number of vectors with at least one mismatch.
+    pub failed: usize,
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl Report {
+    pub fn passed(&self) -> bool {
+        self.failed == 0
+    }
+
+    fn check(&mut self, index: usize, vector: &Vector, outputs: &[&String], nets: &Signals) -> usize {
+        let before = self.mismatches.len();
+
+        for (output, expected) in std::iter::zip(outputs, &vector.expected) {
+            let actual = nets.get(output);
+
+            match expected {
+                Some(expected) if *expected != actual => self.mismatches.push(Mismatch {
+                    vector: index,
+                    line: vector.line,
+                    net: output.to_string(),
+                    expected: *expected,
+                    actual,
+                }),
+                _ => (),
+            }
+        }
+
+        self.mismatches.len() - before
+    }
+}
+
+impl std::fmt::Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.passed() {
+            write!(f, "PASS: {} vectors", self.vectors)
+        } else {
+            write!(f, "FAIL: {} of {} vectors, {} mismatches", self.failed, self.vectors, self.mismatches.len())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blif;
+
+    #[test]
+    fn test_parse() {
+        let stimulus = Stimulus::parse(concat!(
+            "# header\n",
+            ".clock clk\n",
+            "1 0 | 1-  # trailing\n",
+            "\n",
+            "@ | x\n",
+        )).unwrap();
+
+        assert_eq!(stimulus.clock(), Some(&"clk".into()));
+        assert_eq!(stimulus.vectors, vec![
+            Vector {
+                line: 3,
+                clocked: false,
+                inputs: Some(vec![SignalState::High, SignalState::Low]),
+                expected: vec![Some(SignalState::High), None],
+            },
+            Vector { line: 5, clocked: true, inputs: None, expected: vec![Some(SignalState::Unknown)] },
+        ]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(Stimulus::parse("00 | 2\n"), Err(error(1, "unexpected `2` in vector".into())));
+        assert_eq!(Stimulus::parse("00\n.inputs a\n").unwrap_err().line, 2);
+        assert_eq!(Stimulus::parse(".outputs y\n01 |\n").unwrap_err().line, 2);
+    }
+
+    #[test]
+    fn test_run_smol() {
+        let blif = blif::parse(include_str!("../fixtures/smol.blif")).unwrap();
+        let stimulus = Stimulus::parse("00 | 0\n10 | 1\n11 | 1\n01 | -\n").unwrap();
+
+        let report = stimulus.run(blif.top().unwrap()).unwrap();
+
+        assert!(!report.passed());
+        assert_eq!(report.mismatches, vec![Mismatch {
+            vector: 2,
+            line: 3,
+            net: "o_led".into(),
+            expected: SignalState::High,
+            actual: SignalState::Low,
+        }]);
+        assert_eq!(report.to_string(), "FAIL: 1 of 4 vectors, 1 mismatches");
+        assert_eq!(report.mismatches[0].to_string(), "vector 2 (line 3): `o_led` is 0, expected 1");
+    }
+
+    #[test]
+    fn test_run_declared_order() {
+        let blif = blif::parse(include_str!("../fixtures/smol.blif")).unwrap();
+        let stimulus = Stimulus::parse(".inputs i_B i_A\n01 | 1\n10 | 0\n").unwrap();
+
+        assert!(stimulus.run(blif.top().unwrap()).unwrap().passed());
+    }
+
+    #[test]
+    fn test_run_counter() {
+        let blif = blif::parse(include_str!("../fixtures/counter.blif")).unwrap();
+        let stimulus = Stimulus::parse(".clock clk\n| 00\n@ | 10\n@ | 01\n| 01\n@ | 11\n").unwrap();
+
+        assert_eq!(stimulus.run(blif.top().unwrap()).unwrap().to_string(), "PASS: 5 vectors");
+    }
+
+    #[test]
+    fn test_run_does_not_fit() {
+        let blif = blif::parse(include_str!("../fixtures/smol.blif")).unwrap();
+        let model = blif.top().unwrap();
+
+        assert_eq!(Stimulus::parse("000\n").unwrap().run(model).unwrap_err().line, 1);
+        assert_eq!(Stimulus::parse("\n.inputs nope\n").unwrap().run(model).unwrap_err().line, 2);
+    }
+}
@@ -0,0 +1,227 @@
+use crate::blif::{CombinationalLoop, Model};
+use crate::netlist::Netlist;
+use crate::netlist::parallel::{Lanes, ParallelEvaluator, LANES};
+use crate::simulation::SignalState;
+
+use std::io::Write;
+
+/// Input count above which enumerating a model is refused by default.
+pub const DEFAULT_MAX_INPUTS: usize = 16;
+
+/// Input count above which enumerating a model is always refused: every row
+/// is held in memory, and the 2^20 rows of this many inputs already take
+/// around a hundred megabytes.
+pub const MAX_INPUTS: usize = 20;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum TruthTableError {
+    Loop(CombinationalLoop),
+    TooManyInputs { model: String, inputs: usize, max: usize },
+}
+
+impl std::fmt::Display for TruthTableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Loop(err) => write!(f, "{}", err),
+            Self::TooManyInputs { model, inputs, max } => {
+                write!(f, "model `{}` has {} inputs, more than the maximum of {}", model, inputs, max)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TruthTableError {}
+
+/// The outputs of a model for each of the 2^n combinations of its inputs.
+///
+/// Latches are held at their initial state, so the table of a sequential
+/// model is the one of its combinational logic in the reset state. Rows are
+/// evaluated 64 at a time on the flattened `Netlist` of the model.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TruthTable {
+    pub inputs: Vec<String>,
+    pub outputs: Vec<String>,
+    /// `(inputs, outputs)` in counting order, the first input being the most
+    /// significant bit.
+    pub rows: Vec<(Vec<SignalState>, Vec<SignalState>)>,
+}
+
+impl TruthTable {
+    /// Enumerate `model`, refusing models with more than `max_inputs` inputs
+    /// (and, whatever `max_inputs`, more than `MAX_INPUTS`).
+    pub fn new(model: &Model, max_inputs: usize) -> Result<Self, TruthTableError> {
+        let n = model.inputs.len();
+        let max = max_inputs.min(MAX_INPUTS);
+        if n > max {
+            return Err(TruthTableError::TooManyInputs { model: model.name.clone(), inputs: n, max });
+        }
+
+        let netlist = Netlist::new(model).map_err(TruthTableError::Loop)?;
+        let mut evaluator = ParallelEvaluator::new(&netlist);
+        let mut rows = Vec::with_capacity(1 << n);
+
+        // `LANES` rows at a time, the first input being the most significant bit.
+        for first in (0..(1u64 << n)).step_by(LANES) {
+            let inputs: Vec<Lanes> = (0..n).map(|i| {
+                let bits = (0..LANES as u64).fold(0, |bits, lane| bits | ((first + lane) >> (n - 1 - i) & 1) << lane);
+                Lanes::from_bits(bits)
+            }).collect();
+
+            evaluator.set_inputs(&inputs);
+            evaluator.evaluate();
+            let outputs = evaluator.outputs();
+
+            for lane in 0..LANES.min((1 << n) - first as usize) {
+                rows.push((
+                    inputs.iter().map(|lanes| lanes.get(lane)).collect(),
+                    outputs.iter().map(|lanes| lanes.get(lane)).collect(),
+                ));
+            }
+        }
+
+        Ok(Self { inputs: model.inputs.clone(), outputs: model.outputs.clone(), rows })
+    }
+
+    /// The table as printed by the `truth-table` command:
+    ///
+    /// ```text
+    /// a b | y
+    /// 00 | 0
+    /// ```
+    pub fn write_table(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        writeln!(out, "{} | {}", self.inputs.join(" "), self.outputs.join(" "))?;
+
+        for (inputs, outputs) in &self.rows {
+            writeln!(out, "{} | {}", join(inputs, ""), join(outputs, ""))?;
+        }
+
+        Ok(())
+    }
+
+    /// One column per input then per output, with a header row of net names.
+    pub fn write_csv(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        let header: Vec<String> = self.inputs.iter().chain(&self.outputs).map(|net| csv_field(net)).collect();
+        writeln!(out, "{}", header.join(","))?;
+
+        for (inputs, outputs) in &self.rows {
+            writeln!(out, "{},{}", join(inputs, ","), join(outputs, ","))?;
+        }
+
+        Ok(())
+    }
+
+    /// An Espresso PLA of type `fd`, one product term per row. Unknown and
+    /// high-impedance outputs are written as don't-cares (`-`).
+    pub fn write_pla(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        writeln!(out, ".i {}", self.inputs.len())?;
+        writeln!(out, ".o {}", self.outputs.len())?;
+        writeln!(out, ".ilb {}", self.inputs.join(" "))?;
+        writeln!(out, ".ob {}", self.outputs.join(" "))?;
+        writeln!(out, ".type fd")?;
+        writeln!(out, ".p {}", self.rows.len())?;
+
+        for (inputs, outputs) in &self.rows {
+            let outputs: String = outputs.iter().map(|state| match state {
+                SignalState::High => '1',
+                SignalState::Low => '0',
+                _ => '-',
+            }).collect();
+
+            writeln!(out, "{} {}", join(inputs, ""), outputs)?;
+        }
+
+        writeln!(out, ".e")
+    }
+}
+
+fn join(states: &[SignalState], separator: &str) -> String {
+    states.iter().map(|state| state.to_string()).collect::<Vec<_>>().join(separator)
+}
+
+/// Net names are quoted when they contain a separator or a quote.
+fn csv_field(name: &str) -> String {
+    if name.contains([',', '"']) {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    } else {
+        name.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blif;
+
+    fn render(write: impl Fn(&mut dyn Write) -> std::io::Result<()>) -> String {
+        let mut out = Vec::new();
+        write(&mut out).unwrap();
+
+        String::from_utf8(out).unwrap()
+    }
+
+    fn smol() -> TruthTable {
+        let blif = blif::parse(include_str!("../fixtures/smol.blif")).unwrap();
+
+        TruthTable::new(blif.top().unwrap(), DEFAULT_MAX_INPUTS).unwrap()
+    }
+
+    #[test]
+    fn test_csv() {
+        assert_eq!(render(|out| smol().write_csv(out)), concat!(
+            "i_A,i_B,o_led\n",
+            "0,0,0\n",
+            "0,1,0\n",
+            "1,0,1\n",
+            "1,1,0\n",
+        ));
+    }
+
+    #[test]
+    fn test_pla() {
+        assert_eq!(render(|out| smol().write_pla(out)), concat!(
+            ".i 2\n",
+            ".o 1\n",
+            ".ilb i_A i_B\n",
+            ".ob o_led\n",
+            ".type fd\n",
+            ".p 4\n",
+            "00 0\n",
+            "01 0\n",
+            "10 1\n",
+            "11 0\n",
+            ".e\n",
+        ));
+    }
+
+    #[test]
+    fn test_full_adder() {
+        let blif = blif::parse(include_str!("../fixtures/full_adder.blif")).unwrap();
+        let table = TruthTable::new(blif.top().unwrap(), DEFAULT_MAX_INPUTS).unwrap();
+
+        // `assign {cout, sum} = a + b + cin;` from fixtures/full_adder.v.
+        for (row, (_, outputs)) in table.rows.iter().enumerate() {
+            let total = row.count_ones();
+            let bit = |b: u32| if b == 1 { SignalState::High } else { SignalState::Low };
+
+            assert_eq!(outputs, &vec![bit(total & 1), bit(total >> 1)], "row {:03b}", row);
+        }
+    }
+
+    #[test]
+    fn test_too_many_inputs() {
+        let blif = blif::parse(include_str!("../fixtures/smol.blif")).unwrap();
+
+        assert_eq!(
+            TruthTable::new(blif.top().unwrap(), 1),
+            Err(TruthTableError::TooManyInputs { model: "blinky".into(), inputs: 2, max: 1 }),
+        );
+
+        // The hard limit is the one reported when it is the lower one.
+        let inputs: Vec<String> = (0..=MAX_INPUTS).map(|i| format!("i{}", i)).collect();
+        let wide = Model::new("wide".into(), inputs, Vec::new(), Vec::new());
+        assert_eq!(
+            TruthTable::new(&wide, usize::MAX),
+            Err(TruthTableError::TooManyInputs { model: "wide".into(), inputs: MAX_INPUTS + 1, max: MAX_INPUTS }),
+        );
+    }
+}
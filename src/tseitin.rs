@@ -0,0 +1,209 @@
+use crate::aig::{Aig, AigError, Lit};
+use crate::blif::Model;
+use crate::sat::{write_dimacs, Cnf, Solver};
+use crate::simulation::{SignalState, Signals, SignalsBuilder};
+
+use std::io::{self, Write};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum QueryError {
+    Aig(AigError),
+    /// A constraint on a net which is not an input, latch or output.
+    UnknownNet(String),
+    /// A constraint other than high or low.
+    UnknownState(String),
+}
+
+impl std::fmt::Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Aig(err) => write!(f, "{}", err),
+            Self::UnknownNet(net) => write!(f, "net `{}` is not an input, latch or output of the model", net),
+            Self::UnknownState(net) => write!(f, "net `{}` can only be constrained to 0 or 1", net),
+        }
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+/// The Tseitin encoding of the combinational logic of a model.
+///
+/// The model is first turned into an `Aig` by `Aig::combinational`, so
+/// instances are flattened and latches are cut whatever their clocking: their
+/// outputs are free variables, like the inputs, clocks included. DIMACS
+/// variable 1 is the constant of the AIG and each other AIG variable `v` is
+/// variable `v + 1`, with three clauses per AND node.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Encoding {
+    pub cnf: Cnf,
+    /// Literal of each input, latch and output, in that order.
+    pub signals: Vec<(String, i32)>,
+}
+
+impl Encoding {
+    pub fn new(model: &Model) -> Result<Self, AigError> {
+        Ok(Self::from_aig(&Aig::combinational(model)?))
+    }
+
+    pub fn from_aig(aig: &Aig) -> Self {
+        let literal = |lit: Lit| if lit.is_complemented() { -(lit.var() as i32 + 1) } else { lit.var() as i32 + 1 };
+
+        let mut cnf = Cnf::new();
+        for _ in 0..=aig.max_var() {
+            cnf.new_var();
+        }
+
+        cnf.add_clause(vec![literal(!Lit::FALSE)]);
+
+        let first = aig.max_var() - aig.ands().len() + 1;
+        for (i, (a, b)) in aig.ands().iter().enumerate() {
+            let (x, a, b) = (literal(Lit::new(first + i, false)), literal(*a), literal(*b));
+
+            cnf.add_clause(vec![-x, a]);
+            cnf.add_clause(vec![-x, b]);
+            cnf.add_clause(vec![x, -a, -b]);
+        }
+
+        let inputs = aig.inputs.iter().enumerate().map(|(i, input)| (input.clone(), literal(Lit::new(i + 1, false))));
+        let latches = aig.latches.iter().enumerate()
+            .map(|(i, latch)| (latch.name.clone(), literal(Lit::new(aig.inputs.len() + i + 1, false))));
+        let outputs = aig.outputs.iter().map(|(output, lit)| (output.clone(), literal(*lit)));
+
+        Self { cnf, signals: inputs.chain(latches).chain(outputs).collect() }
+    }
+
+    /// Literal true when `name` is high.
+    pub fn literal(&self, name: &str) -> Option<i32> {
+        self.signals.iter().find(|(signal, _)| signal == name).map(|(_, lit)| *lit)
+    }
+
+    /// Write the formula as DIMACS, after a comment line `c <name> <literal>`
+    /// per input, latch and output.
+    pub fn write_dimacs(&self, out: &mut dyn Write) -> io::Result<()> {
+        for (name, lit) in &self.signals {
+            writeln!(out, "c {} {}", name, lit)?;
+        }
+
+        write_dimacs(out, &self.cnf)
+    }
+
+    /// Values of the inputs, latches and outputs meeting every high or low
+    /// constraint of `constraints`, or `None` when no input and latch values
+    /// can.
+    pub fn satisfy(&self, constraints: &Signals) -> Result<Option<Signals>, QueryError> {
+        let mut cnf = self.cnf.clone();
+
+        for signal in constraints.iter() {
+            let lit = self.literal(signal.name()).ok_or_else(|| QueryError::UnknownNet(signal.name().into()))?;
+
+            match signal.state() {
+                SignalState::High => cnf.add_clause(vec![lit]),
+                SignalState::Low => cnf.add_clause(vec![-lit]),
+                _ => return Err(QueryError::UnknownState(signal.name().into())),
+            }
+        }
+
+        let assignment = match Solver::new(&cnf).solve() {
+            Some(assignment) => assignment,
+            None => return Ok(None),
+        };
+
+        Ok(Some(self.signals.iter().fold(SignalsBuilder::new(), |builder, (name, lit)| {
+            builder.add_signal(name, if assignment.value(*lit) { SignalState::High } else { SignalState::Low })
+        }).build()))
+    }
+}
+
+/// `Encoding::satisfy` on the encoding of `model`.
+pub fn satisfy(model: &Model, constraints: &Signals) -> Result<Option<Signals>, QueryError> {
+    Encoding::new(model).map_err(QueryError::Aig)?.satisfy(constraints)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blif;
+    use crate::simulation::Simulable;
+
+    fn signals(values: &[(&str, SignalState)]) -> Signals {
+        values.iter().fold(SignalsBuilder::new(), |builder, (name, state)| builder.add_signal(name, *state)).build()
+    }
+
+    #[test]
+    fn test_med() {
+        let blif = blif::parse(include_str!("../fixtures/med.blif")).unwrap();
+        let model = blif.top().unwrap();
+
+        // `o_m2` is `A & !B`.
+        assert_eq!(satisfy(model, &signals(&[("o_m2", SignalState::High), ("B", SignalState::High)])).unwrap(), None);
+
+        let witness = satisfy(model, &signals(&[("o_m2", SignalState::High)])).unwrap().unwrap();
+        assert_eq!(witness.get("A"), SignalState::High);
+        assert_eq!(witness.get("B"), SignalState::Low);
+        assert_eq!(witness.get("o_m1"), SignalState::High);
+    }
+
+    #[test]
+    fn test_full_adder() {
+        let blif = blif::parse(include_str!("../fixtures/full_adder.blif")).unwrap();
+        let model = blif.top().unwrap();
+        let encoding = Encoding::new(model).unwrap();
+
+        // Every assignment found agrees with the simulation of the model.
+        for sum in [SignalState::Low, SignalState::High] {
+            for cout in [SignalState::Low, SignalState::High] {
+                let witness = encoding.satisfy(&signals(&[("sum", sum), ("cout", cout)])).unwrap().unwrap();
+                let inputs = signals(&["a", "b", "cin"].map(|input| (input, witness.get(input))));
+                let outputs = model.stim(inputs);
+
+                assert_eq!((outputs.get("sum"), outputs.get("cout")), (sum, cout));
+            }
+        }
+
+        let constraints = signals(&[("a", SignalState::Low), ("b", SignalState::Low), ("cout", SignalState::High)]);
+        assert_eq!(encoding.satisfy(&constraints).unwrap(), None);
+    }
+
+    #[test]
+    fn test_latches() {
+        let blif = blif::parse(include_str!("../fixtures/counter.blif")).unwrap();
+        let encoding = Encoding::new(blif.top().unwrap()).unwrap();
+
+        // Latch outputs are free, so the outputs can take any value.
+        assert!(encoding.satisfy(&signals(&[("q[0]", SignalState::High), ("q[1]", SignalState::High)])).unwrap().is_some());
+        assert!(encoding.satisfy(&signals(&[("clk", SignalState::High)])).unwrap().is_some());
+        assert_eq!(
+            encoding.satisfy(&signals(&[("q[0]$next", SignalState::High)])).unwrap_err(),
+            QueryError::UnknownNet("q[0]$next".into()),
+        );
+
+        // Level-sensitive latches have no AIGER clock, but are cut all the same.
+        let blif = blif::parse(".model t\n.inputs d e\n.outputs y\n.latch d q ah e 0\n.names q e y\n11 1\n.end\n").unwrap();
+        let witness = satisfy(blif.top().unwrap(), &signals(&[("y", SignalState::High)])).unwrap().unwrap();
+        assert_eq!((witness.get("q"), witness.get("e")), (SignalState::High, SignalState::High));
+    }
+
+    #[test]
+    fn test_dimacs() {
+        let blif = blif::parse(include_str!("../fixtures/smol.blif")).unwrap();
+        let encoding = Encoding::new(blif.top().unwrap()).unwrap();
+
+        let mut out = Vec::new();
+        encoding.write_dimacs(&mut out).unwrap();
+
+        // o_led = i_A & !i_B.
+        assert_eq!(String::from_utf8(out).unwrap(), concat!(
+            "c i_A 2\n",
+            "c i_B 3\n",
+            "c o_led 4\n",
+            "p cnf 4 4\n",
+            "-1 0\n",
+            "-4 -3 0\n",
+            "-4 2 0\n",
+            "4 3 -2 0\n",
+        ));
+
+        let constraints = signals(&[("o_led", SignalState::Unknown)]);
+        assert_eq!(encoding.satisfy(&constraints).unwrap_err(), QueryError::UnknownState("o_led".into()));
+    }
+}
@@ -0,0 +1,313 @@
+use crate::blif::Model;
+use crate::netlist::Netlist;
+use crate::netlist::event::Change;
+use crate::simulation::{SignalState, Signals};
+
+use std::io::Write;
+
+/// The nets of one model instance, and the instances below it.
+struct Scope {
+    name: String,
+    /// `(net name, variable index)`.
+    nets: Vec<(String, usize)>,
+    /// `(index in the parent's subckts, scope)`.
+    instances: Vec<(usize, Scope)>,
+}
+
+impl Scope {
+    fn new(name: String, model: &Model, count: &mut usize) -> Self {
+        let nets = model.nets().into_iter().map(|net| {
+            *count += 1;
+            (net, *count - 1)
+        }).collect();
+
+        let instances = model.subckts.iter().enumerate().filter_map(|(i, subckt)| {
+            subckt.definition.as_ref().map(|definition| {
+                (i, Scope::new(model.instance_name(i), definition, count))
+            })
+        }).collect();
+
+        Self { name, nets, instances }
+    }
+
+    /// Scopes of a flattened netlist, one per `Instance`.
+    fn from_netlist(netlist: &Netlist) -> Self {
+        let mut scopes: Vec<Self> = netlist.instances.iter().map(|instance| {
+            Self { name: instance.name.clone(), nets: Vec::new(), instances: Vec::new() }
+        }).collect();
+        let mut root = Self { name: netlist.name.clone(), nets: Vec::new(), instances: Vec::new() };
+
+        for net in 0..netlist.net_count() {
+            let scope = match netlist.net_instance(net) {
+                Some(instance) => &mut scopes[instance],
+                None => &mut root,
+            };
+
+            scope.nets.push((netlist.local_name(net).to_string(), net));
+        }
+
+        // Instances come after their parent: attach them from the last one.
+        for (i, instance) in netlist.instances.iter().enumerate().rev() {
+            let scope = std::mem::replace(&mut scopes[i], Self { name: String::new(), nets: Vec::new(), instances: Vec::new() });
+            let parent = match instance.parent {
+                Some(parent) => &mut scopes[parent],
+                None => &mut root,
+            };
+
+            parent.instances.insert(0, (i, scope));
+        }
+
+        root
+    }
+
+    fn sample(&self, model: &Model, nets: &Signals, values: &mut [SignalState]) {
+        for (net, var) in &self.nets {
+            values[*var] = nets.get(net);
+        }
+
+        for (i, scope) in &self.instances {
+            let definition = model.subckts[*i].definition.as_ref().unwrap();
+
+            scope.sample(definition, &nets.scope(&scope.name), values);
+        }
+    }
+
+    fn write_definitions(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        writeln!(out, "$scope module {} $end", self.name)?;
+
+        for (net, var) in &self.nets {
+            writeln!(out, "$var wire 1 {} {} $end", identifier(*var), net)?;
+        }
+
+        for (_, scope) in &self.instances {
+            scope.write_definitions(out)?;
+        }
+
+        writeln!(out, "$upscope $end")
+    }
+}
+
+/// Short VCD identifier of variable `var`, in base 94 over the printable ASCII characters.
+fn identifier(mut var: usize) -> String {
+    let mut id = String::new();
+
+    loop {
+        id.push((b'!' + (var % 94) as u8) as char);
+        var /= 94;
+
+        if var == 0 {
+            return id;
+        }
+
+        var -= 1;
+    }
+}
+
+/// Records the value of every net of a `Model`, including the nets inside
+/// its `.subckt` instances, and writes them out as a VCD file.
+pub struct TraceRecorder<'a> {
+    model: &'a Model,
+    scope: Scope,
+    count: usize,
+    /// Timestamped changes, the first entry holding every variable.
+    changes: Vec<(u64, Vec<(usize, SignalState)>)>,
+    last: Vec<SignalState>,
+}
+
+impl<'a> TraceRecorder<'a> {
+    pub fn new(model: &'a Model) -> Self {
+        let mut count = 0;
+        let scope = Scope::new(model.name.clone(), model, &mut count);
+
+        Self { model, scope, count, changes: Vec::new(), last: Vec::new() }
+    }
+
+    /// Record the nets of the model at `time`, as returned by `Simulable::stim`
+    /// or a `Stepper`, nets inside instances included.
+    pub fn record(&mut self, time: u64, nets: &Signals) {
+        let mut values = vec![SignalState::Unknown; self.count];
+        self.scope.sample(self.model, nets, &mut values);
+
+        let changes: Vec<(usize, SignalState)> = if self.changes.is_empty() {
+            values.iter().copied().enumerate().collect()
+        } else {
+            values.iter().copied().enumerate().filter(|(var, value)| self.last[*var] != *value).collect()
+        };
+
+        if !changes.is_empty() || self.changes.is_empty() {
+            match self.changes.last_mut() {
+                Some((last, previous)) if *last == time => previous.extend(changes),
+                _ => self.changes.push((time, changes)),
+            }
+        }
+
+        self.last = values;
+    }
+
+    /// Write the trace with `timescale` (such as `1ns`) as the unit of the recorded times.
+    pub fn write_vcd(&self, out: &mut dyn Write, timescale: &str) -> std::io::Result<()> {
+        write_trace(out, timescale, &self.scope, &self.changes)
+    }
+}
+
+/// Write the value changes of a flattened `Netlist`, such as the ones of an
+/// `EventSimulator`, with one `$scope` per instance.
+///
+/// `initial` holds the value of every net before the first change.
+pub fn write_netlist_vcd(out: &mut dyn Write, timescale: &str, netlist: &Netlist, initial: &[SignalState], changes: &[Change]) -> std::io::Result<()> {
+    let mut values = initial.to_vec();
+    let mut first = changes.iter().take_while(|change| change.time == 0).peekable();
+    for change in first.by_ref() {
+        values[change.net] = change.state;
+    }
+
+    let mut grouped: Vec<(u64, Vec<(usize, SignalState)>)> = vec![(0, values.into_iter().enumerate().collect())];
+
+    for change in changes.iter().skip_while(|change| change.time == 0) {
+        match grouped.last_mut() {
+            Some((time, group)) if *time == change.time => group.push((change.net, change.state)),
+            _ => grouped.push((change.time, vec![(change.net, change.state)])),
+        }
+    }
+
+    write_trace(out, timescale, &Scope::from_netlist(netlist), &grouped)
+}
+
+/// `changes` holds timestamped changes, the first entry holding every variable.
+fn write_trace(out: &mut dyn Write, timescale: &str, scope: &Scope, changes: &[(u64, Vec<(usize, SignalState)>)]) -> std::io::Result<()> {
+    writeln!(out, "$version {} {} $end", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))?;
+    writeln!(out, "$timescale {} $end", timescale)?;
+    scope.write_definitions(out)?;
+    writeln!(out, "$enddefinitions $end")?;
+
+    for (i, (time, changes)) in changes.iter().enumerate() {
+        writeln!(out, "#{}", time)?;
+
+        if i == 0 {
+            writeln!(out, "$dumpvars")?;
+        }
+
+        for (var, value) in changes {
+            writeln!(out, "{}{}", value, identifier(*var))?;
+        }
+
+        if i == 0 {
+            writeln!(out, "$end")?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blif;
+    use crate::simulation::*;
+
+    #[test]
+    fn test_identifier() {
+        assert_eq!(identifier(0), "!");
+        assert_eq!(identifier(93), "~");
+        assert_eq!(identifier(94), "!!");
+        assert_eq!(identifier(95), "\"!");
+    }
+
+    #[test]
+    fn test_vcd_smol() {
+        let blif = blif::parse(include_str!("../fixtures/smol.blif")).unwrap();
+        let model = blif.top().unwrap();
+
+        let mut recorder = TraceRecorder::new(model);
+
+        for (time, (a, b)) in [(SignalState::Low, SignalState::Low), (SignalState::High, SignalState::Low)].into_iter().enumerate() {
+            let nets = model.stim(SignalsBuilder::new().add_signal("i_A", a).add_signal("i_B", b).build());
+            recorder.record(time as u64, &nets);
+        }
+
+        let mut out = Vec::new();
+        recorder.write_vcd(&mut out, "1ns").unwrap();
+
+        assert_eq!(String::from_utf8(out).unwrap(), format!(concat!(
+            "$version garnierisator {} $end\n",
+            "$timescale 1ns $end\n",
+            "$scope module blinky $end\n",
+            "$var wire 1 ! i_A $end\n",
+            "$var wire 1 \" i_B $end\n",
+            "$var wire 1 # o_led $end\n",
+            "$var wire 1 $ $false $end\n",
+            "$var wire 1 % $true $end\n",
+            "$var wire 1 & $undef $end\n",
+            "$var wire 1 ' Y $end\n",
+            "$upscope $end\n",
+            "$enddefinitions $end\n",
+            "#0\n",
+            "$dumpvars\n",
+            "0!\n",
+            "0\"\n",
+            "0#\n",
+            "0$\n",
+            "1%\n",
+            "0&\n",
+            "1'\n",
+            "$end\n",
+            "#1\n",
+            "1!\n",
+            "1#\n",
+        ), env!("CARGO_PKG_VERSION")));
+    }
+
+    #[test]
+    fn test_vcd_hierarchy() {
+        let blif = blif::parse(include_str!("../fixtures/med.blif")).unwrap();
+        let top = blif.top().unwrap();
+
+        let mut recorder = TraceRecorder::new(top);
+        let nets = top.stim(
+            SignalsBuilder::new()
+                .add_signal("A", SignalState::High)
+                .add_signal("B", SignalState::Low)
+                .build()
+        );
+        recorder.record(0, &nets);
+
+        let mut out = Vec::new();
+        recorder.write_vcd(&mut out, "1ns").unwrap();
+        let vcd = String::from_utf8(out).unwrap();
+
+        assert!(vcd.contains("$scope module top $end\n"));
+        assert!(vcd.contains("$scope module a_not_b_0 $end\n"));
+        assert!(vcd.contains("$scope module a_not_b_1 $end\n"));
+        assert_eq!(vcd.matches("$upscope $end").count(), 3);
+
+        // i_A of the second instance is m2_A, driven high by the first one.
+        let i_a = vcd.lines()
+            .skip_while(|line| *line != "$scope module a_not_b_1 $end")
+            .find(|line| line.ends_with(" i_A $end"))
+            .unwrap()
+            .split(' ')
+            .nth(3)
+            .unwrap();
+        assert!(vcd.contains(&format!("\n1{}\n", i_a)));
+    }
+
+    #[test]
+    fn test_vcd_netlist() {
+        let blif = blif::parse(include_str!("../fixtures/med.blif")).unwrap();
+        let netlist = crate::netlist::Netlist::new(blif.top().unwrap()).unwrap();
+
+        let mut sim = crate::netlist::event::EventSimulator::new(&netlist, 1).unwrap();
+        sim.schedule_inputs(&[SignalState::High, SignalState::Low], 0).unwrap();
+        sim.run();
+
+        let mut out = Vec::new();
+        write_netlist_vcd(&mut out, "1ns", &netlist, sim.initial(), sim.changes()).unwrap();
+        let vcd = String::from_utf8(out).unwrap();
+
+        assert!(vcd.contains("$scope module a_not_b_0 $end\n"));
+        assert_eq!(vcd.matches("$upscope $end").count(), 3);
+        assert!(vcd.contains("\n#0\n$dumpvars\n1!\n0\"\n"));
+        assert!(vcd.contains("\n#1\n"));
+        assert!(!vcd.contains("\n#0\n#"));
+    }
+}
@@ -0,0 +1,406 @@
+use crate::blif::{Blif, GateFunction, InputValue, Latch, LatchInit, LatchType, LogicGate, Model, Subckt};
+
+use std::collections::HashSet;
+use std::io::Write;
+
+/// Reserved words of Verilog-2005, which are escaped when used as names.
+const KEYWORDS: &[&str] = &[
+    "always", "and", "assign", "automatic", "begin", "buf", "bufif0", "bufif1", "case", "casex", "casez",
+    "cell", "cmos", "config", "deassign", "default", "defparam", "design", "disable", "edge", "else", "end",
+    "endcase", "endconfig", "endfunction", "endgenerate", "endmodule", "endprimitive", "endspecify",
+    "endtable", "endtask", "event", "for", "force", "forever", "fork", "function", "generate", "genvar",
+    "highz0", "highz1", "if", "ifnone", "incdir", "include", "initial", "inout", "input", "instance",
+    "integer", "join", "large", "liblist", "library", "localparam", "macromodule", "medium", "module",
+    "nand", "negedge", "nmos", "nor", "noshowcancelled", "not", "notif0", "notif1", "or", "output",
+    "parameter", "pmos", "posedge", "primitive", "pull0", "pull1", "pulldown", "pullup",
+    "pulsestyle_ondetect", "pulsestyle_onevent", "rcmos", "real", "realtime", "reg", "release", "repeat",
+    "rnmos", "rpmos", "rtran", "rtranif0", "rtranif1", "scalared", "showcancelled", "signed", "small",
+    "specify", "specparam", "strong0", "strong1", "supply0", "supply1", "table", "task", "time", "tran",
+    "tranif0", "tranif1", "tri", "tri0", "tri1", "triand", "trior", "trireg", "unsigned", "use", "uwire",
+    "vectored", "wait", "wand", "weak0", "weak1", "while", "wire", "wor", "xnor", "xor",
+];
+
+/// Write `blif` as structural Verilog, one `module` per model.
+///
+/// Each `.names` becomes an `assign` of its sum-of-products cover (the
+/// complement of the sum for OFF-set covers), or of an XOR or a `?:` when
+/// its `GateFunction` is one; each `.subckt` becomes an instance with its
+/// ports connected by name, and each `.latch` a `reg` assigned in an
+/// `always` block. Instances are named after their model and their
+/// position, as in VCD traces. Names which are not plain Verilog
+/// identifiers, such as the `$`-prefixed nets of Yosys, are escaped.
+///
+/// Latches on the global clock are clocked by an extra `global_clock`
+/// input, added after the outputs of every model using it, even through
+/// an instance. Don't-care initial values are written as 0, as simulated.
+pub fn write_verilog(out: &mut dyn Write, blif: &Blif) -> std::io::Result<()> {
+    for (i, model) in blif.models().iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+
+        write_model(out, model)?;
+    }
+
+    Ok(())
+}
+
+fn write_model(out: &mut dyn Write, model: &Model) -> std::io::Result<()> {
+    let clock = clock_port(model);
+
+    let mut ports: Vec<&str> = Vec::new();
+    for port in model.inputs.iter().chain(&clock).chain(&model.outputs) {
+        if !ports.contains(&port.as_str()) {
+            ports.push(port);
+        }
+    }
+
+    if ports.is_empty() {
+        writeln!(out, "module {};", identifier(&model.name))?;
+    } else {
+        let names: Vec<String> = ports.iter().map(|port| identifier(port)).collect();
+        writeln!(out, "module {}({});", identifier(&model.name), names.join(", "))?;
+    }
+
+    let inputs: HashSet<&str> = model.inputs.iter().chain(&clock).map(String::as_str).collect();
+    let regs: Vec<&str> = model.latches.iter()
+        .map(|latch| latch.output.as_str())
+        .filter({
+            let mut seen = HashSet::new();
+            move |output| seen.insert(*output)
+        })
+        .collect();
+
+    for port in &ports {
+        let direction = if inputs.contains(port) { "input" } else { "output" };
+        writeln!(out, "  {} {};", direction, identifier(port))?;
+    }
+
+    for reg in &regs {
+        writeln!(out, "  reg {};", identifier(reg))?;
+    }
+
+    for net in model.nets() {
+        if !ports.contains(&net.as_str()) && !regs.contains(&net.as_str()) {
+            writeln!(out, "  wire {};", identifier(&net))?;
+        }
+    }
+
+    if !model.gates.is_empty() {
+        writeln!(out)?;
+    }
+
+    for gate in &model.gates {
+        writeln!(out, "  assign {} = {};", identifier(&gate.output), expression(gate))?;
+    }
+
+    if !model.latches.is_empty() {
+        writeln!(out)?;
+    }
+
+    for latch in &model.latches {
+        write_latch(out, latch, clock.as_deref())?;
+    }
+
+    for (i, subckt) in model.subckts.iter().enumerate() {
+        write_subckt(out, subckt, i, clock.as_deref())?;
+    }
+
+    writeln!(out, "endmodule")
+}
+
+fn write_latch(out: &mut dyn Write, latch: &Latch, global_clock: Option<&str>) -> std::io::Result<()> {
+    let (input, output) = (identifier(&latch.input), identifier(&latch.output));
+
+    match latch.init {
+        LatchInit::Low | LatchInit::DontCare => writeln!(out, "  initial {} = 1'b0;", output)?,
+        LatchInit::High => writeln!(out, "  initial {} = 1'b1;", output)?,
+        LatchInit::Unknown => (),
+    }
+
+    let control = match &latch.control {
+        Some((kind, control)) if control != "NIL" => Some((kind, identifier(control))),
+        _ => None,
+    };
+
+    let sensitivity = match &control {
+        Some((LatchType::RisingEdge, control)) => format!("posedge {}", control),
+        Some((LatchType::FallingEdge, control)) => format!("negedge {}", control),
+        Some(_) => "*".to_string(),
+        None => format!("posedge {}", identifier(global_clock.expect("global clock of a model with latches on it"))),
+    };
+
+    let condition = match &control {
+        Some((LatchType::ActiveHigh, control)) => format!("if ({}) ", control),
+        Some((LatchType::ActiveLow, control)) => format!("if (!{}) ", control),
+        _ => String::new(),
+    };
+
+    writeln!(out, "  always @({}) {}{} <= {};", sensitivity, condition, output, input)
+}
+
+fn write_subckt(out: &mut dyn Write, subckt: &Subckt, index: usize, global_clock: Option<&str>) -> std::io::Result<()> {
+    writeln!(out)?;
+
+    // Y = E ? A : z, the only built-in cell.
+    if subckt.model == "$_TBUF_" {
+        let (inputs, outputs) = subckt.bindings().expect("built-in cell");
+        let port = |name: &str| {
+            inputs.iter().find(|(port, _)| *port == name).map_or("1'bx".to_string(), |(_, net)| identifier(net))
+        };
+
+        for (_, net) in outputs {
+            writeln!(out, "  assign {} = {} ? {} : 1'bz;", identifier(net), port("E"), port("A"))?;
+        }
+
+        return Ok(());
+    }
+
+    // Positional formals are named after the ports they resolve to.
+    let mut connections: Vec<(String, String)> = match subckt.bindings() {
+        Some((inputs, outputs)) => inputs.iter().chain(&outputs)
+            .map(|(port, net)| (identifier(port), identifier(net)))
+            .collect(),
+        None => subckt.connections.iter()
+            .map(|(formal, actual)| (identifier(formal), identifier(actual)))
+            .collect(),
+    };
+
+    if let Some(definition) = &subckt.definition {
+        if let Some(port) = clock_port(definition) {
+            let clock = global_clock.expect("global clock of a model instantiating a model using it");
+            connections.push((identifier(&port), identifier(clock)));
+        }
+    }
+
+    let connections: Vec<String> = connections.iter()
+        .map(|(port, net)| format!("    .{}({})", port, net))
+        .collect();
+
+    writeln!(out, "  {} {} (", identifier(&subckt.model), identifier(&format!("{}_{}", subckt.model, index)))?;
+    writeln!(out, "{}", connections.join(",\n"))?;
+    writeln!(out, "  );")
+}
+
+/// The function of `gate` as an expression of its inputs: XORs and
+/// multiplexers with their operators, which simulate unknown inputs as
+/// `LogicGate::evaluate` does, and anything else as its cover.
+fn expression(gate: &LogicGate) -> String {
+    let inputs: Vec<String> = gate.inputs.iter().map(|input| identifier(input)).collect();
+
+    match gate.function() {
+        GateFunction::Xor => inputs.join(" ^ "),
+        GateFunction::Xnor => format!("~({})", inputs.join(" ^ ")),
+        GateFunction::Mux { select, low, high } => format!("{} ? {} : {}", inputs[select], inputs[high], inputs[low]),
+        _ => cover(gate),
+    }
+}
+
+/// The cover of `gate` as a sum of products of its inputs.
+fn cover(gate: &LogicGate) -> String {
+    let polarity = gate.polarity();
+
+    let cubes: Vec<Vec<String>> = gate.single_output_cover.iter()
+        .filter(|(_, output)| *output == polarity)
+        .map(|(row, _)| {
+            std::iter::zip(row, &gate.inputs).filter_map(|(value, input)| match value {
+                InputValue::Uncomplemented => Some(identifier(input)),
+                InputValue::Complemented => Some(format!("~{}", identifier(input))),
+                InputValue::NotUsed => None,
+            }).collect()
+        })
+        .collect();
+
+    let complemented = polarity == InputValue::Complemented;
+    let (zero, one) = if complemented { ("1'b1", "1'b0") } else { ("1'b0", "1'b1") };
+
+    if cubes.is_empty() {
+        return zero.to_string();
+    }
+
+    if cubes.iter().any(Vec::is_empty) {
+        return one.to_string();
+    }
+
+    let sum = match cubes.as_slice() {
+        [cube] => cube.join(" & "),
+        _ => cubes.iter()
+            .map(|cube| if cube.len() > 1 { format!("({})", cube.join(" & ")) } else { cube[0].clone() })
+            .collect::<Vec<_>>()
+            .join(" | "),
+    };
+
+    match cubes.as_slice() {
+        [cube] if complemented && cube.len() == 1 => match sum.strip_prefix('~') {
+            Some(literal) => literal.to_string(),
+            None => format!("~{}", sum),
+        },
+        _ if complemented => format!("~({})", sum),
+        _ => sum,
+    }
+}
+
+/// Name of the input added to `model` for the latches on the global clock,
+/// if the model or any model it instantiates has some.
+fn clock_port(model: &Model) -> Option<String> {
+    if !uses_global_clock(model) {
+        return None;
+    }
+
+    let nets: HashSet<String> = model.nets().into_iter().collect();
+    let mut port = "global_clock".to_string();
+    while nets.contains(&port) {
+        port.push('_');
+    }
+
+    Some(port)
+}
+
+fn uses_global_clock(model: &Model) -> bool {
+    model.latches.iter().any(|latch| match &latch.control {
+        Some((_, control)) => control == "NIL",
+        None => true,
+    }) || model.subckts.iter().any(|subckt| subckt.definition.as_deref().is_some_and(uses_global_clock))
+}
+
+/// `name` as a Verilog identifier, escaped as `\name ` when it is not a
+/// simple identifier.
+fn identifier(name: &str) -> String {
+    let simple = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        && !KEYWORDS.contains(&name);
+
+    if simple {
+        name.to_string()
+    } else {
+        format!("\\{} ", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blif;
+
+    fn render(source: &str) -> String {
+        let mut out = Vec::new();
+        write_verilog(&mut out, &blif::parse(source).unwrap()).unwrap();
+
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_smol() {
+        assert_eq!(render(include_str!("../fixtures/smol.blif")), concat!(
+            "module blinky(i_A, i_B, o_led);\n",
+            "  input i_A;\n",
+            "  input i_B;\n",
+            "  output o_led;\n",
+            "  wire \\$false ;\n",
+            "  wire \\$true ;\n",
+            "  wire \\$undef ;\n",
+            "  wire Y;\n",
+            "\n",
+            "  assign \\$false  = 1'b0;\n",
+            "  assign \\$true  = 1'b1;\n",
+            "  assign \\$undef  = 1'b0;\n",
+            "  assign o_led = i_A & Y;\n",
+            "  assign Y = ~i_B;\n",
+            "endmodule\n",
+        ));
+    }
+
+    #[test]
+    fn test_instances() {
+        let verilog = render(include_str!("../fixtures/med.blif"));
+
+        // Positional formals are connected by port name.
+        assert!(verilog.contains(concat!(
+            "  a_not_b a_not_b_1 (\n",
+            "    .i_A(m2_A),\n",
+            "    .i_B(B),\n",
+            "    .o_led(o_m2)\n",
+            "  );\n",
+        )), "{}", verilog);
+        assert!(verilog.contains("  assign o_led = \\$logic_and$fixtures/med.v:2$3_Y ;\n"), "{}", verilog);
+    }
+
+    #[test]
+    fn test_covers() {
+        let verilog = render(concat!(
+            ".model covers\n",
+            ".inputs a b c\n",
+            ".outputs sum nand nor mux\n",
+            ".names a b c sum\n",
+            "1-0 1\n",
+            "01- 1\n",
+            "--1 1\n",
+            ".names a b nand\n",
+            "11 0\n",
+            ".names a nor\n",
+            "0 0\n",
+            ".names a b c mux\n",
+            "1-0 1\n",
+            "-11 1\n",
+            ".end\n",
+        ));
+
+        assert!(verilog.contains("  assign sum = (a & ~c) | (~a & b) | c;\n"), "{}", verilog);
+        assert!(verilog.contains("  assign \\nand  = ~(a & b);\n"), "{}", verilog);
+        assert!(verilog.contains("  assign \\nor  = a;\n"), "{}", verilog);
+        assert!(verilog.contains("  assign mux = c ? b : a;\n"), "{}", verilog);
+    }
+
+    #[test]
+    fn test_latches() {
+        assert_eq!(render(include_str!("../fixtures/counter.blif")), concat!(
+            "module counter(clk, \\q[0] , \\q[1] );\n",
+            "  input clk;\n",
+            "  output \\q[0] ;\n",
+            "  output \\q[1] ;\n",
+            "  reg \\q[0] ;\n",
+            "  reg \\q[1] ;\n",
+            "  wire \\$false ;\n",
+            "  wire \\$true ;\n",
+            "  wire \\$undef ;\n",
+            "  wire \\$0$q[0] ;\n",
+            "  wire \\$0$q[1] ;\n",
+            "\n",
+            "  assign \\$false  = 1'b0;\n",
+            "  assign \\$true  = 1'b1;\n",
+            "  assign \\$undef  = 1'b0;\n",
+            "  assign \\$0$q[0]  = ~\\q[0] ;\n",
+            "  assign \\$0$q[1]  = \\q[0]  ^ \\q[1] ;\n",
+            "\n",
+            "  initial \\q[0]  = 1'b0;\n",
+            "  always @(posedge clk) \\q[0]  <= \\$0$q[0] ;\n",
+            "  initial \\q[1]  = 1'b0;\n",
+            "  always @(posedge clk) \\q[1]  <= \\$0$q[1] ;\n",
+            "endmodule\n",
+        ));
+    }
+
+    #[test]
+    fn test_global_clock() {
+        let verilog = render(concat!(
+            ".model top\n",
+            ".inputs d e\n",
+            ".outputs y\n",
+            ".subckt cell d=d q=q\n",
+            ".subckt $_TBUF_ A=q E=e Y=y\n",
+            ".end\n",
+            "\n",
+            ".model cell\n",
+            ".inputs d\n",
+            ".outputs q\n",
+            ".latch d q 3\n",
+            ".end\n",
+        ));
+
+        assert!(verilog.starts_with("module top(d, e, global_clock, y);\n"), "{}", verilog);
+        assert!(verilog.contains("    .q(q),\n    .global_clock(global_clock)\n"), "{}", verilog);
+        assert!(verilog.contains("  assign y = e ? q : 1'bz;\n"), "{}", verilog);
+        assert!(verilog.contains("  always @(posedge global_clock) q <= d;\n"), "{}", verilog);
+        assert!(!verilog.contains("initial"), "{}", verilog);
+    }
+}